@@ -0,0 +1,155 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    time::Instant,
+};
+
+use nuvo_chess::{
+    board_rep::{board::WHITE, magic_bitboards::MagicBitboards},
+    book::{
+        pgn::parse_pgn,
+        polyglot::{Book, BookEntry, encode_move, get_polyglot_key},
+    },
+};
+
+const DEFAULT_MAX_PLY: usize = 20;
+const DEFAULT_MIN_COUNT: u32 = 3;
+
+#[derive(Default)]
+struct MoveStats {
+    count: u32,
+    points: u32,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 {
+        println!("usage: bookgen <output.bin> <input.pgn>... [-ply N] [-min N]");
+        println!("       defaults: -ply {DEFAULT_MAX_PLY} -min {DEFAULT_MIN_COUNT}");
+        return;
+    }
+    let output = &args[0];
+    let mut inputs = Vec::new();
+    let mut max_ply = DEFAULT_MAX_PLY;
+    let mut min_count = DEFAULT_MIN_COUNT;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-ply" => {
+                max_ply = get_arg(&args, i + 1, DEFAULT_MAX_PLY as u64) as usize;
+                i += 1;
+            }
+            "-min" => {
+                min_count = get_arg(&args, i + 1, DEFAULT_MIN_COUNT as u64) as u32;
+                i += 1;
+            }
+            input => inputs.push(input.to_string()),
+        }
+        i += 1;
+    }
+
+    if let Err(error) = generate(output, &inputs, max_ply, min_count) {
+        println!("{error}");
+    }
+}
+
+fn get_arg(args: &[String], index: usize, default: u64) -> u64 {
+    args.get(index)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(default)
+}
+
+fn generate(output: &str, inputs: &[String], max_ply: usize, min_count: u32) -> Result<(), String> {
+    let magic_bitboards = MagicBitboards::new();
+    let now = Instant::now();
+    let mut stats: HashMap<(u64, u16), MoveStats> = HashMap::new();
+    let mut games = 0;
+    let mut skipped = 0;
+
+    for input in inputs {
+        let text = fs::read_to_string(input).map_err(|error| format!("{input}: {error}"))?;
+        for game in parse_pgn(&text) {
+            let white_points = match game.get_result() {
+                "1-0" => 2,
+                "0-1" => 0,
+                "1/2-1/2" => 1,
+                _ => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let (positions, illegal) = game.replay(&magic_bitboards);
+            if let Some(san) = illegal {
+                println!(
+                    "warning: illegal move {san} after ply {} in game {}",
+                    positions.len(),
+                    games + skipped + 1
+                );
+            }
+            for (board, c_move) in positions.into_iter().take(max_ply) {
+                let entry = stats
+                    .entry((get_polyglot_key(&board), encode_move(c_move)))
+                    .or_default();
+                entry.count += 1;
+                entry.points += if board.stm == WHITE {
+                    white_points
+                } else {
+                    2 - white_points
+                };
+            }
+            games += 1;
+        }
+    }
+
+    let mut entries = get_entries(&stats, min_count);
+    entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+    let file =
+        File::create(output).map_err(|error| format!("failed to create {output}: {error}"))?;
+    let mut writer = BufWriter::new(file);
+    for entry in &entries {
+        writer
+            .write_all(&entry.to_bytes())
+            .map_err(|error| format!("failed to write {output}: {error}"))?;
+    }
+    writer
+        .flush()
+        .map_err(|error| format!("failed to write {output}: {error}"))?;
+
+    let book = Book::load(output)?;
+    println!(
+        "Games: {games} Skipped: {skipped} Moves: {} Entries: {} Time: {:.1}s",
+        stats.len(),
+        book.get_entry_count(),
+        now.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+fn get_entries(stats: &HashMap<(u64, u16), MoveStats>, min_count: u32) -> Vec<BookEntry> {
+    let mut by_key: HashMap<u64, Vec<(u16, u32)>> = HashMap::new();
+    for (&(key, raw_move), move_stats) in stats {
+        if move_stats.count >= min_count && move_stats.points > 0 {
+            by_key
+                .entry(key)
+                .or_default()
+                .push((raw_move, move_stats.points));
+        }
+    }
+
+    let mut entries = Vec::new();
+    for (key, moves) in by_key {
+        let max_points = moves.iter().map(|(_, points)| *points).max().unwrap_or(0);
+        let scale = (max_points as f64 / u16::MAX as f64).max(1.0);
+        for (raw_move, points) in moves {
+            entries.push(BookEntry {
+                key,
+                raw_move,
+                weight: ((points as f64 / scale) as u16).max(1),
+                learn: 0,
+            });
+        }
+    }
+    entries
+}
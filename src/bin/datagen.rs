@@ -0,0 +1,472 @@
+use std::{
+    env,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Instant,
+};
+
+use nuvo_chess::{
+    board_rep::{
+        bit_operations::{count_bits, first_bit_pop},
+        board::{BLACK, Board, COLOR_MASK, EMPTY, PIECE_MASK, WHITE},
+        c_move::CMove,
+        magic_bitboards::MagicBitboards,
+        move_gen::generate_moves,
+        rng::Rng,
+    },
+    engine::{
+        limits::SearchLimits,
+        search::{InfoFormat, MATE_BOUND, get_root_moves, is_capture},
+        searcher::{SEARCH_STACK_SIZE, run_search},
+        tt::{DEFAULT_HASH_MB, TranspositionTable},
+    },
+    evaluation::kpk::init_kpk,
+};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const RECORD_SIZE: usize = 36;
+const DEFAULT_GAMES: usize = 100;
+const DEFAULT_NODES: u64 = 5000;
+const DEFAULT_RANDOM_PLIES: usize = 8;
+const MAX_GAME_PLIES: usize = 400;
+const WIN_ADJUDICATION_SCORE: i32 = 1500;
+const WIN_ADJUDICATION_PLIES: usize = 4;
+
+const RESULT_BLACK_WIN: u8 = 0;
+const RESULT_DRAW: u8 = 1;
+const RESULT_WHITE_WIN: u8 = 2;
+
+const FLAG_BLACK: u8 = 1;
+const FLAG_CHESS960: u8 = 2;
+const DEFAULT_CASTLING_FILES: [u8; 4] = [7, 0, 7, 0];
+
+struct PackedPosition {
+    occupancy: u64,
+    pieces: [u8; 16],
+    score: i16,
+    best_move: u16,
+    result: u8,
+    flags: u8,
+    en_passant: u8,
+    halfmove_clock: u8,
+    fullmove_number: u16,
+    castling_files: u16,
+}
+
+impl PackedPosition {
+    fn from_board(board: &Board, score: i16, best_move: CMove) -> Self {
+        let mut pieces = [0; 16];
+        let mut occupancy = board.all_occupancy;
+        let mut index = 0;
+        while occupancy != 0 {
+            let square = first_bit_pop(&mut occupancy);
+            let piece = board.get_piece_at(square);
+            let code = (piece & PIECE_MASK) - 1 + if piece & BLACK != 0 { 6 } else { 0 };
+            pieces[index / 2] |= code << ((index % 2) * 4);
+            index += 1;
+        }
+        let castling_files = board
+            .castling_rooks
+            .iter()
+            .enumerate()
+            .fold(0, |files, (i, rook)| files | ((rook % 8) as u16) << (i * 3));
+        PackedPosition {
+            occupancy: board.all_occupancy,
+            pieces,
+            score,
+            best_move: pack_move(best_move),
+            result: RESULT_DRAW,
+            flags: if board.stm == BLACK { FLAG_BLACK } else { 0 }
+                | if board.chess960 { FLAG_CHESS960 } else { 0 }
+                | board.castling << 4,
+            en_passant: board.en_passant,
+            halfmove_clock: board.halfmove_clock,
+            fullmove_number: board.fullmove_number,
+            castling_files,
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0; RECORD_SIZE];
+        bytes[0..8].copy_from_slice(&self.occupancy.to_le_bytes());
+        bytes[8..24].copy_from_slice(&self.pieces);
+        bytes[24..26].copy_from_slice(&self.score.to_le_bytes());
+        bytes[26..28].copy_from_slice(&self.best_move.to_le_bytes());
+        bytes[28] = self.result;
+        bytes[29] = self.flags;
+        bytes[30] = self.en_passant;
+        bytes[31] = self.halfmove_clock;
+        bytes[32..34].copy_from_slice(&self.fullmove_number.to_le_bytes());
+        bytes[34..36].copy_from_slice(&self.castling_files.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; RECORD_SIZE]) -> Self {
+        PackedPosition {
+            occupancy: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            pieces: bytes[8..24].try_into().unwrap(),
+            score: i16::from_le_bytes([bytes[24], bytes[25]]),
+            best_move: u16::from_le_bytes([bytes[26], bytes[27]]),
+            result: bytes[28],
+            flags: bytes[29],
+            en_passant: bytes[30],
+            halfmove_clock: bytes[31],
+            fullmove_number: u16::from_le_bytes([bytes[32], bytes[33]]),
+            castling_files: u16::from_le_bytes([bytes[34], bytes[35]]),
+        }
+    }
+
+    fn get_fen(&self) -> String {
+        let mut rows = vec![vec![EMPTY; 8]; 8];
+        let mut occupancy = self.occupancy;
+        let mut index = 0;
+        while occupancy != 0 {
+            let square = first_bit_pop(&mut occupancy) as usize;
+            let code = (self.pieces[index / 2] >> ((index % 2) * 4)) & 15;
+            let color = if code >= 6 { BLACK } else { WHITE };
+            rows[square / 8][square % 8] = color | (code % 6 + 1);
+            index += 1;
+        }
+
+        let mut fen = String::new();
+        for (rank, row) in rows.iter().enumerate() {
+            let mut empty = 0;
+            for piece in row {
+                if *piece == EMPTY {
+                    empty += 1;
+                    continue;
+                }
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                let c = b" pnbrqk"[(piece & PIECE_MASK) as usize] as char;
+                fen.push(if piece & COLOR_MASK == WHITE {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                });
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank < 7 {
+                fen.push('/');
+            }
+        }
+
+        // Rooks outside the default corners are written by file, as in Shredder-FEN.
+        let mut castling = String::new();
+        for (i, c) in ['K', 'Q', 'k', 'q'].into_iter().enumerate() {
+            if (self.flags >> 4) & (1 << i) == 0 {
+                continue;
+            }
+            let file = (self.castling_files >> (i * 3)) as u8 & 7;
+            if file == DEFAULT_CASTLING_FILES[i] {
+                castling.push(c);
+            } else if c.is_ascii_uppercase() {
+                castling.push((b'A' + file) as char);
+            } else {
+                castling.push((b'a' + file) as char);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        let en_passant = if self.en_passant >= 64 {
+            "-".to_string()
+        } else {
+            format!(
+                "{}{}",
+                (b'a' + self.en_passant % 8) as char,
+                8 - self.en_passant / 8
+            )
+        };
+        format!(
+            "{fen} {} {castling} {en_passant} {} {}",
+            if self.flags & FLAG_BLACK == 0 {
+                "w"
+            } else {
+                "b"
+            },
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("dump") => {
+            let Some(path) = args.get(1) else {
+                println!("usage: datagen dump <input.bin> [limit]");
+                return;
+            };
+            let limit = args
+                .get(2)
+                .and_then(|limit| limit.parse().ok())
+                .unwrap_or(usize::MAX);
+            if let Err(error) = dump(path, limit) {
+                println!("{error}");
+            }
+        }
+        Some(path) => {
+            let path = path.to_string();
+            let games = get_arg(&args, 1, DEFAULT_GAMES as u64) as usize;
+            let nodes = get_arg(&args, 2, DEFAULT_NODES);
+            let random_plies = get_arg(&args, 3, DEFAULT_RANDOM_PLIES as u64) as usize;
+            let seed = get_arg(&args, 4, 1070372);
+            let result = thread::Builder::new()
+                .stack_size(SEARCH_STACK_SIZE)
+                .spawn(move || generate(&path, games, nodes, random_plies, seed))
+                .expect("Failed to spawn datagen thread")
+                .join()
+                .expect("Datagen thread panicked");
+            if let Err(error) = result {
+                println!("{error}");
+            }
+        }
+        None => {
+            println!("usage: datagen <output.bin> [games] [nodes] [random_plies] [seed]");
+            println!("       datagen dump <input.bin> [limit]");
+        }
+    }
+}
+
+fn get_arg(args: &[String], index: usize, default: u64) -> u64 {
+    args.get(index)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(default)
+}
+
+fn generate(
+    path: &str,
+    games: usize,
+    nodes: u64,
+    random_plies: usize,
+    seed: u64,
+) -> Result<(), String> {
+    let file = File::create(path).map_err(|error| format!("failed to create {path}: {error}"))?;
+    let mut writer = BufWriter::new(file);
+    let magic_bitboards = MagicBitboards::new();
+    init_kpk(&magic_bitboards);
+    let tt = TranspositionTable::new(DEFAULT_HASH_MB);
+    let stop = AtomicBool::new(false);
+    let mut limits = SearchLimits::new();
+    limits.nodes = nodes;
+    let mut rng = Rng::new();
+    rng.seed(seed);
+
+    let now = Instant::now();
+    let mut total_positions = 0;
+    for game in 1..=games {
+        let Some(board) = get_random_opening(&magic_bitboards, &mut rng, random_plies) else {
+            continue;
+        };
+        tt.clear();
+        let (positions, result) = play_game(board, &magic_bitboards, &tt, &stop, &limits);
+        for mut position in positions {
+            position.result = result;
+            writer
+                .write_all(&position.to_bytes())
+                .map_err(|error| format!("failed to write {path}: {error}"))?;
+            total_positions += 1;
+        }
+        if game % 10 == 0 || game == games {
+            println!(
+                "Games: {game} Positions: {total_positions} Positions per second: {:.0}",
+                total_positions as f64 / now.elapsed().as_secs_f64()
+            );
+        }
+    }
+    writer
+        .flush()
+        .map_err(|error| format!("failed to write {path}: {error}"))
+}
+
+fn get_random_opening(
+    magic_bitboards: &MagicBitboards,
+    rng: &mut Rng,
+    random_plies: usize,
+) -> Option<Board> {
+    let mut board = Board::new();
+    board.set_from_fen(START_FEN, magic_bitboards);
+    for _ in 0..random_plies {
+        let root_moves = get_root_moves(&board, magic_bitboards, &[]);
+        if root_moves.count == 0 {
+            return None;
+        }
+        let index = (rng.next_u64() % root_moves.count as u64) as usize;
+        board.make_move(root_moves.moves[index]);
+    }
+    if get_root_moves(&board, magic_bitboards, &[]).count == 0 {
+        return None;
+    }
+    Some(board)
+}
+
+fn play_game(
+    mut board: Board,
+    magic_bitboards: &MagicBitboards,
+    tt: &TranspositionTable,
+    stop: &AtomicBool,
+    limits: &SearchLimits,
+) -> (Vec<PackedPosition>, u8) {
+    let mut positions = Vec::new();
+    let mut hashes = vec![board.hash];
+    let mut winning_plies = 0;
+    for _ in 0..MAX_GAME_PLIES {
+        if board.halfmove_clock >= 100
+            || count_bits(board.all_occupancy) == 2
+            || hashes.iter().filter(|hash| **hash == board.hash).count() >= 3
+        {
+            return (positions, RESULT_DRAW);
+        }
+
+        stop.store(false, Ordering::Relaxed);
+        let Some((result, _)) = run_search(
+            &board,
+            &hashes[..hashes.len() - 1],
+            magic_bitboards,
+            tt,
+            stop,
+            limits,
+            1,
+            InfoFormat::None,
+        ) else {
+            let in_check = generate_moves(&board, magic_bitboards).check_count > 0;
+            let result = match (in_check, board.stm == WHITE) {
+                (false, _) => RESULT_DRAW,
+                (true, true) => RESULT_BLACK_WIN,
+                (true, false) => RESULT_WHITE_WIN,
+            };
+            return (positions, result);
+        };
+
+        let white_score = if board.stm == WHITE {
+            result.score
+        } else {
+            -result.score
+        };
+        if white_score.abs() >= WIN_ADJUDICATION_SCORE {
+            winning_plies += 1;
+            if winning_plies >= WIN_ADJUDICATION_PLIES {
+                let result = if white_score > 0 {
+                    RESULT_WHITE_WIN
+                } else {
+                    RESULT_BLACK_WIN
+                };
+                return (positions, result);
+            }
+        } else {
+            winning_plies = 0;
+        }
+
+        let in_check = generate_moves(&board, magic_bitboards).check_count > 0;
+        if !in_check
+            && !is_capture(&board, &result.best_move)
+            && result.best_move.promotion == 0
+            && result.score.abs() < MATE_BOUND
+        {
+            positions.push(PackedPosition::from_board(
+                &board,
+                white_score as i16,
+                result.best_move,
+            ));
+        }
+        board.make_move(result.best_move);
+        hashes.push(board.hash);
+    }
+    (positions, RESULT_DRAW)
+}
+
+fn dump(path: &str, limit: usize) -> Result<(), String> {
+    let file = File::open(path).map_err(|error| format!("failed to open {path}: {error}"))?;
+    let mut reader = BufReader::new(file);
+    let mut bytes = [0; RECORD_SIZE];
+    let mut count = 0;
+    while count < limit && reader.read_exact(&mut bytes).is_ok() {
+        let position = PackedPosition::from_bytes(&bytes);
+        let result = match position.result {
+            RESULT_WHITE_WIN => "1-0",
+            RESULT_BLACK_WIN => "0-1",
+            _ => "1/2-1/2",
+        };
+        println!(
+            "{} | {} | {} | {result}",
+            position.get_fen(),
+            position.score,
+            unpack_move(position.best_move).uci(position.flags & FLAG_CHESS960 != 0)
+        );
+        count += 1;
+    }
+    Ok(())
+}
+
+fn pack_move(c_move: CMove) -> u16 {
+    c_move.from_square as u16
+        | (c_move.to_square as u16) << 6
+        | (c_move.promotion as u16) << 12
+        | (c_move.castling as u16) << 15
+}
+
+fn unpack_move(data: u16) -> CMove {
+    CMove {
+        from_square: (data & 63) as u8,
+        to_square: ((data >> 6) & 63) as u8,
+        promotion: ((data >> 12) & 7) as u8,
+        castling: data >> 15 == 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_position_round_trip() {
+        thread::Builder::new()
+            .stack_size(SEARCH_STACK_SIZE)
+            .spawn(check_round_trip)
+            .expect("Failed to spawn test thread")
+            .join()
+            .expect("Round trip check failed");
+    }
+
+    fn check_round_trip() {
+        let magic_bitboards = Box::new(MagicBitboards::new());
+        for (fen, chess960) in [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 3 17",
+                false,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 142",
+                false,
+            ),
+            (
+                "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9",
+                true,
+            ),
+            (
+                "rbbqn1kr/pp2p1pp/6n1/2pp1p2/2P4P/P7/BP1PPPP1/R1BQNNKR w KQk - 1 9",
+                true,
+            ),
+        ] {
+            let mut board = Board::new();
+            board.chess960 = chess960;
+            board.set_from_fen(fen, &magic_bitboards);
+            let root_moves = get_root_moves(&board, &magic_bitboards, &[]);
+            let best_move = root_moves.moves[0];
+            let bytes = PackedPosition::from_board(&board, -37, best_move).to_bytes();
+            let position = PackedPosition::from_bytes(&bytes);
+            assert_eq!(position.get_fen(), fen);
+            assert_eq!(position.score, -37);
+            assert_eq!(position.flags & FLAG_CHESS960 != 0, chess960);
+            assert!(unpack_move(position.best_move) == best_move);
+        }
+    }
+}
@@ -0,0 +1,874 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use nuvo_chess::{
+    board_rep::{
+        board::{Board, WHITE},
+        c_move::CMove,
+        epd::EpdRecord,
+        magic_bitboards::MagicBitboards,
+        move_gen::generate_moves,
+        rng::Rng,
+        san::{get_legal_moves, get_san},
+    },
+    book::{
+        pgn::{PgnGame, parse_pgn},
+        polyglot::Book,
+    },
+};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const DEFAULT_GAMES: usize = 10;
+const DEFAULT_TIME: u64 = 10000;
+const DEFAULT_INCREMENT: u64 = 100;
+const DEFAULT_BOOK_PLIES: usize = 8;
+const TIME_MARGIN: u64 = 100;
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+const MATE_SCORE: i32 = 30000;
+
+#[derive(Default)]
+struct EngineConfig {
+    command: String,
+    options: Vec<(String, String)>,
+}
+
+struct Sprt {
+    elo0: f64,
+    elo1: f64,
+    alpha: f64,
+    beta: f64,
+}
+
+impl Sprt {
+    fn get_bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    fn get_llr(&self, wins: usize, losses: usize, draws: usize) -> f64 {
+        let games = (wins + losses + draws) as f64;
+        if games == 0.0 {
+            return 0.0;
+        }
+        let (win, loss, draw) = (
+            wins as f64 / games,
+            losses as f64 / games,
+            draws as f64 / games,
+        );
+        let score = win + draw / 2.0;
+        let variance =
+            win * (1.0 - score).powi(2) + loss * score.powi(2) + draw * (0.5 - score).powi(2);
+        if variance <= 0.0 {
+            return 0.0;
+        }
+        let score0 = get_expected_score(self.elo0);
+        let score1 = get_expected_score(self.elo1);
+        0.5 * games * (score1 - score0) * (2.0 * score - score0 - score1) / variance
+    }
+}
+
+struct Config {
+    engines: [EngineConfig; 2],
+    games: usize,
+    time: u64,
+    increment: u64,
+    move_time: Option<u64>,
+    openings: Option<String>,
+    book_plies: usize,
+    pgn_out: Option<String>,
+    resign_score: i32,
+    resign_moves: usize,
+    draw_score: i32,
+    draw_moves: usize,
+    draw_move_number: u16,
+    seed: u64,
+    sprt: Option<Sprt>,
+}
+
+impl Config {
+    fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut config = Config {
+            engines: [EngineConfig::default(), EngineConfig::default()],
+            games: DEFAULT_GAMES,
+            time: DEFAULT_TIME,
+            increment: DEFAULT_INCREMENT,
+            move_time: None,
+            openings: None,
+            book_plies: DEFAULT_BOOK_PLIES,
+            pgn_out: None,
+            resign_score: 0,
+            resign_moves: 0,
+            draw_score: 0,
+            draw_moves: 0,
+            draw_move_number: 0,
+            seed: 1070372,
+            sprt: None,
+        };
+        let mut i = 0;
+        while i < args.len() {
+            let arg = args[i].as_str();
+            let mut next = || get_value(args, &mut i);
+            match arg {
+                "-engine1" => config.engines[0].command = next()?,
+                "-engine2" => config.engines[1].command = next()?,
+                flag @ ("-option" | "-option1" | "-option2") => {
+                    let option = next()?;
+                    let (name, value) = option
+                        .split_once('=')
+                        .ok_or(format!("{flag} expects name=value"))?;
+                    for (index, engine) in config.engines.iter_mut().enumerate() {
+                        if flag == "-option" || flag.ends_with(&(index + 1).to_string()) {
+                            engine.options.push((name.to_string(), value.to_string()));
+                        }
+                    }
+                }
+                "-games" => config.games = parse_value(&next()?)?,
+                "-tc" => {
+                    let tc = next()?;
+                    let (base, increment) = tc.split_once('+').unwrap_or((&tc, "0"));
+                    config.time = (parse_value::<f64>(base)? * 1000.0) as u64;
+                    config.increment = (parse_value::<f64>(increment)? * 1000.0) as u64;
+                }
+                "-movetime" => config.move_time = Some(parse_value(&next()?)?),
+                "-openings" => config.openings = Some(next()?),
+                "-plies" => config.book_plies = parse_value(&next()?)?,
+                "-pgnout" => config.pgn_out = Some(next()?),
+                "-resign" => {
+                    config.resign_score = parse_value(&next()?)?;
+                    config.resign_moves = parse_value(&next()?)?;
+                }
+                "-draw" => {
+                    config.draw_move_number = parse_value(&next()?)?;
+                    config.draw_score = parse_value(&next()?)?;
+                    config.draw_moves = parse_value(&next()?)?;
+                }
+                "-seed" => config.seed = parse_value(&next()?)?,
+                "-sprt" => {
+                    config.sprt = Some(Sprt {
+                        elo0: parse_value(&next()?)?,
+                        elo1: parse_value(&next()?)?,
+                        alpha: parse_value(&next()?)?,
+                        beta: parse_value(&next()?)?,
+                    })
+                }
+                arg => return Err(format!("unknown argument {arg}")),
+            }
+            i += 1;
+        }
+        if config
+            .engines
+            .iter()
+            .any(|engine| engine.command.is_empty())
+        {
+            return Err("both -engine1 and -engine2 are required".to_string());
+        }
+        Ok(config)
+    }
+
+    fn get_time_control(&self) -> String {
+        match self.move_time {
+            Some(move_time) => format!("{:.3}/move", move_time as f64 / 1000.0),
+            None => format!(
+                "{}+{}",
+                self.time as f64 / 1000.0,
+                self.increment as f64 / 1000.0
+            ),
+        }
+    }
+}
+
+fn get_value(args: &[String], index: &mut usize) -> Result<String, String> {
+    *index += 1;
+    args.get(*index)
+        .cloned()
+        .ok_or(format!("missing value for {}", args[*index - 1]))
+}
+
+fn parse_value<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value {value}"))
+}
+
+enum GoError {
+    Timeout,
+    Disconnected,
+}
+
+struct UciEngine {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl UciEngine {
+    fn start(config: &EngineConfig) -> Result<Self, String> {
+        let mut child = Command::new(&config.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| format!("failed to start {}: {error}", config.command))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = UciEngine {
+            name: config.command.clone(),
+            child,
+            stdin,
+            lines,
+        };
+        engine.send("uci")?;
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            let line = engine
+                .read_line(deadline)
+                .map_err(|_| format!("{} did not answer uci", config.command))?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        for (name, value) in &config.options {
+            engine.send(&format!("setoption name {name} value {value}"))?;
+        }
+        engine.ready()?;
+        Ok(engine)
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{command}")
+            .and_then(|_| self.stdin.flush())
+            .map_err(|error| format!("failed to write to {}: {error}", self.name))
+    }
+
+    fn read_line(&self, deadline: Instant) -> Result<String, GoError> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        self.lines
+            .recv_timeout(timeout)
+            .map_err(|error| match error {
+                RecvTimeoutError::Timeout => GoError::Timeout,
+                RecvTimeoutError::Disconnected => GoError::Disconnected,
+            })
+    }
+
+    fn ready(&mut self) -> Result<(), String> {
+        self.send("isready")?;
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            match self.read_line(deadline) {
+                Ok(line) if line.trim() == "readyok" => return Ok(()),
+                Ok(_) => {}
+                Err(_) => return Err(format!("{} did not answer isready", self.name)),
+            }
+        }
+    }
+
+    fn go(
+        &mut self,
+        position: &str,
+        go: &str,
+        timeout: Duration,
+    ) -> Result<(String, Option<i32>), GoError> {
+        self.send(position).map_err(|_| GoError::Disconnected)?;
+        self.send(go).map_err(|_| GoError::Disconnected)?;
+        let deadline = Instant::now() + timeout;
+        let mut score = None;
+        loop {
+            let line = self.read_line(deadline)?;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts.first() {
+                Some(&"bestmove") => {
+                    return Ok((parts.get(1).unwrap_or(&"0000").to_string(), score));
+                }
+                Some(&"info") => {
+                    if let Some(index) = parts.iter().position(|part| *part == "score") {
+                        let value = parts.get(index + 2).and_then(|value| value.parse().ok());
+                        score = match (parts.get(index + 1), value) {
+                            (Some(&"cp"), Some(value)) => Some(value),
+                            (Some(&"mate"), Some(value)) if value > 0 => Some(MATE_SCORE - value),
+                            (Some(&"mate"), Some(value)) => Some(-MATE_SCORE - value),
+                            _ => score,
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        thread::sleep(Duration::from_millis(50));
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Clone, Default)]
+struct Opening {
+    fen: Option<String>,
+    moves: Vec<CMove>,
+}
+
+enum Openings {
+    None,
+    List(Vec<Opening>),
+    Book(Book),
+}
+
+impl Openings {
+    fn load(path: Option<&str>, magic_bitboards: &MagicBitboards) -> Result<Self, String> {
+        let Some(path) = path else {
+            return Ok(Openings::None);
+        };
+        if path.ends_with(".bin") {
+            return Ok(Openings::Book(Book::load(path)?));
+        }
+        let text = fs::read_to_string(path).map_err(|error| format!("{path}: {error}"))?;
+        let openings: Vec<Opening> = if path.ends_with(".pgn") {
+            parse_pgn(&text)
+                .iter()
+                .map(|game| Opening {
+                    fen: game.get_tag("FEN").map(str::to_string),
+                    moves: game
+                        .replay(magic_bitboards)
+                        .0
+                        .into_iter()
+                        .map(|(_, c_move)| c_move)
+                        .collect(),
+                })
+                .collect()
+        } else {
+            text.lines()
+                .filter_map(|line| EpdRecord::parse(line, magic_bitboards))
+                .map(|record| Opening {
+                    fen: Some(record.fen),
+                    moves: Vec::new(),
+                })
+                .collect()
+        };
+        if openings.is_empty() {
+            return Err(format!("no openings found in {path}"));
+        }
+        Ok(Openings::List(openings))
+    }
+
+    fn get(
+        &self,
+        index: usize,
+        plies: usize,
+        rng: &mut Rng,
+        magic_bitboards: &MagicBitboards,
+    ) -> Opening {
+        match self {
+            Openings::None => Opening::default(),
+            Openings::List(openings) => openings[index % openings.len()].clone(),
+            Openings::Book(book) => {
+                let mut board = Board::new();
+                board.set_from_fen(START_FEN, magic_bitboards);
+                let mut opening = Opening::default();
+                for _ in 0..plies {
+                    let Some(c_move) = book.get_weighted_move(&board, magic_bitboards, rng) else {
+                        break;
+                    };
+                    opening.moves.push(c_move);
+                    board.make_move(c_move);
+                }
+                opening
+            }
+        }
+    }
+}
+
+struct GameResult {
+    result: &'static str,
+    termination: String,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        println!("usage: match -engine1 <path> -engine2 <path> [options]");
+        println!("  -games N                 games to play ({DEFAULT_GAMES})");
+        println!("  -tc base+inc             time control in seconds (10+0.1)");
+        println!("  -movetime ms             fixed time per move");
+        println!("  -option[1|2] name=value  UCI option for both engines or one");
+        println!("  -openings file           .epd, .pgn or Polyglot .bin openings");
+        println!("  -plies N                 book plies for .bin openings ({DEFAULT_BOOK_PLIES})");
+        println!("  -pgnout file             append finished games to a PGN file");
+        println!("  -resign score moves      adjudicate a win when both engines agree");
+        println!("  -draw movenumber score moves  adjudicate a draw after movenumber");
+        println!("  -sprt elo0 elo1 alpha beta    stop when the SPRT accepts a hypothesis");
+        println!("  -seed N                  random seed for book openings");
+        return;
+    }
+    let config = match Config::from_args(&args) {
+        Ok(config) => config,
+        Err(error) => {
+            println!("{error}");
+            return;
+        }
+    };
+    if let Err(error) = run_match(&config) {
+        println!("{error}");
+    }
+}
+
+fn run_match(config: &Config) -> Result<(), String> {
+    let magic_bitboards = MagicBitboards::new();
+    let openings = Openings::load(config.openings.as_deref(), &magic_bitboards)?;
+    let mut engines = [
+        UciEngine::start(&config.engines[0])?,
+        UciEngine::start(&config.engines[1])?,
+    ];
+    if engines[0].name == engines[1].name {
+        engines[1].name.push_str(" (2)");
+    }
+    let mut rng = Rng::new();
+    rng.seed(config.seed);
+
+    let (mut wins, mut losses, mut draws) = (0, 0, 0);
+    let mut opening = Opening::default();
+    for game in 0..config.games {
+        if game % 2 == 0 {
+            opening = openings.get(game / 2, config.book_plies, &mut rng, &magic_bitboards);
+        }
+        let white = game % 2;
+        let (result, pgn) = play_game(
+            &mut engines,
+            white,
+            &opening,
+            config,
+            game + 1,
+            &magic_bitboards,
+        )?;
+        match (result.result, white) {
+            ("1-0", 0) | ("0-1", 1) => wins += 1,
+            ("1-0", 1) | ("0-1", 0) => losses += 1,
+            _ => draws += 1,
+        }
+        if let Some(path) = &config.pgn_out {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(pgn.format(&magic_bitboards).as_bytes()))
+                .map_err(|error| format!("failed to write {path}: {error}"))?;
+        }
+
+        println!(
+            "Finished game {} ({} vs {}): {} {{{}}}",
+            game + 1,
+            engines[white].name,
+            engines[1 - white].name,
+            result.result,
+            result.termination
+        );
+        print_score(&engines, wins, losses, draws);
+        if let Some(sprt) = &config.sprt {
+            let llr = sprt.get_llr(wins, losses, draws);
+            let (lower, upper) = sprt.get_bounds();
+            println!(
+                "SPRT: llr {llr:.2} ({:.1}%), lbound {lower:.2}, ubound {upper:.2}",
+                llr * 100.0 / upper
+            );
+            if llr >= upper {
+                println!("SPRT: H1 accepted");
+                break;
+            }
+            if llr <= lower {
+                println!("SPRT: H0 accepted");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_score(engines: &[UciEngine; 2], wins: usize, losses: usize, draws: usize) {
+    let games = wins + losses + draws;
+    let score = (wins as f64 + draws as f64 / 2.0) / games as f64;
+    println!(
+        "Score of {} vs {}: {wins} - {losses} - {draws} [{score:.3}] {games}",
+        engines[0].name, engines[1].name
+    );
+
+    let (elo, margin) = get_elo_margin(wins, losses, draws);
+    println!(
+        "Elo difference: {elo:.1} +/- {margin:.1}, LOS: {:.1} %, DrawRatio: {:.1} %",
+        get_los(wins, losses) * 100.0,
+        draws as f64 * 100.0 / games as f64
+    );
+}
+
+/// The Elo difference and the half width of its 95% confidence interval.
+fn get_elo_margin(wins: usize, losses: usize, draws: usize) -> (f64, f64) {
+    let games = (wins + losses + draws) as f64;
+    let (win, loss, draw) = (
+        wins as f64 / games,
+        losses as f64 / games,
+        draws as f64 / games,
+    );
+    let score = win + draw / 2.0;
+    let variance =
+        win * (1.0 - score).powi(2) + loss * score.powi(2) + draw * (0.5 - score).powi(2);
+    let deviation = (variance / games).sqrt();
+    let lower = get_elo(score - 1.96 * deviation);
+    let upper = get_elo(score + 1.96 * deviation);
+    (get_elo(score), (upper - lower) / 2.0)
+}
+
+fn get_expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+fn get_elo(score: f64) -> f64 {
+    if score <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if score >= 1.0 {
+        return f64::INFINITY;
+    }
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn get_los(wins: usize, losses: usize) -> f64 {
+    if wins + losses == 0 {
+        return 0.5;
+    }
+    let x = (wins as f64 - losses as f64) / (2.0 * (wins + losses) as f64).sqrt();
+    0.5 * (1.0 + erf(x))
+}
+
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let y = 1.0
+        - (((((1.061405429 * t - 1.453152027) * t) + 1.421413741) * t - 0.284496736) * t
+            + 0.254829592)
+            * t
+            * (-x * x).exp();
+    if x >= 0.0 { y } else { -y }
+}
+
+fn play_game(
+    engines: &mut [UciEngine; 2],
+    white: usize,
+    opening: &Opening,
+    config: &Config,
+    round: usize,
+    magic_bitboards: &MagicBitboards,
+) -> Result<(GameResult, PgnGame), String> {
+    let start_fen = opening.fen.as_deref().unwrap_or(START_FEN);
+    let mut board = Board::new();
+    board.set_from_fen(start_fen, magic_bitboards);
+    let mut pgn = PgnGame::default();
+    let mut uci_moves = Vec::new();
+    let mut hashes = vec![board.hash];
+    for c_move in &opening.moves {
+        pgn.moves.push(get_san(&board, magic_bitboards, *c_move));
+        uci_moves.push(c_move.uci(board.chess960).to_string());
+        board.make_move(*c_move);
+        hashes.push(board.hash);
+    }
+    for engine in engines.iter_mut() {
+        engine.send("ucinewgame")?;
+        engine.ready()?;
+    }
+
+    let mut clocks = [config.time as i64; 2];
+    let mut scores: Vec<Option<i32>> = Vec::new();
+    let result = loop {
+        if let Some(result) = get_game_result(&board, &hashes, magic_bitboards) {
+            break result;
+        }
+        if let Some(result) = adjudicate(&scores, board.fullmove_number, config) {
+            break result;
+        }
+
+        let side = (board.stm != WHITE) as usize;
+        let engine_index = if side == 0 { white } else { 1 - white };
+        let position = match (&opening.fen, uci_moves.is_empty()) {
+            (None, true) => "position startpos".to_string(),
+            (None, false) => format!("position startpos moves {}", uci_moves.join(" ")),
+            (Some(fen), true) => format!("position fen {fen}"),
+            (Some(fen), false) => format!("position fen {fen} moves {}", uci_moves.join(" ")),
+        };
+        let (go, time_left) = match config.move_time {
+            Some(move_time) => (format!("go movetime {move_time}"), move_time),
+            None => (
+                format!(
+                    "go wtime {} btime {} winc {} binc {}",
+                    clocks[0].max(1),
+                    clocks[1].max(1),
+                    config.increment,
+                    config.increment
+                ),
+                clocks[side].max(0) as u64,
+            ),
+        };
+
+        let now = Instant::now();
+        let timeout = Duration::from_millis(time_left + TIME_MARGIN);
+        let response = engines[engine_index].go(&position, &go, timeout);
+        let elapsed = now.elapsed().as_millis() as i64;
+        let loser = if side == 0 { "White" } else { "Black" };
+        let (move_str, score) = match response {
+            Ok(response) => response,
+            Err(GoError::Timeout) => break get_loss(side, format!("{loser} loses on time")),
+            Err(GoError::Disconnected) => {
+                break get_loss(side, format!("{loser} disconnects"));
+            }
+        };
+        if config.move_time.is_none() {
+            clocks[side] -= elapsed;
+            if clocks[side] < -(TIME_MARGIN as i64) {
+                break get_loss(side, format!("{loser} loses on time"));
+            }
+            clocks[side] += config.increment as i64;
+        }
+
+        let Some(c_move) = get_legal_moves(&board, magic_bitboards)
+            .into_iter()
+            .find(|c_move| c_move.uci(board.chess960).to_string() == move_str)
+        else {
+            break get_loss(side, format!("{loser} makes an illegal move: {move_str}"));
+        };
+        scores.push(get_white_score(score, side));
+        pgn.moves.push(get_san(&board, magic_bitboards, c_move));
+        uci_moves.push(move_str);
+        board.make_move(c_move);
+        if board.halfmove_clock == 0 {
+            hashes.clear();
+        }
+        hashes.push(board.hash);
+    };
+
+    let names = [&engines[white].name, &engines[1 - white].name];
+    pgn.tags = vec![
+        ("Event".to_string(), "nuvo_chess match".to_string()),
+        ("Site".to_string(), "local".to_string()),
+        ("Date".to_string(), get_date()),
+        ("Round".to_string(), round.to_string()),
+        ("White".to_string(), names[0].clone()),
+        ("Black".to_string(), names[1].clone()),
+        ("Result".to_string(), result.result.to_string()),
+    ];
+    if let Some(fen) = &opening.fen {
+        pgn.tags.push(("SetUp".to_string(), "1".to_string()));
+        pgn.tags.push(("FEN".to_string(), fen.clone()));
+    }
+    pgn.tags
+        .push(("TimeControl".to_string(), config.get_time_control()));
+    pgn.tags
+        .push(("PlyCount".to_string(), pgn.moves.len().to_string()));
+    pgn.tags
+        .push(("Termination".to_string(), result.termination.clone()));
+    pgn.result = result.result.to_string();
+    Ok((result, pgn))
+}
+
+/// Turns a score from the side to move's point of view into white's.
+fn get_white_score(score: Option<i32>, side: usize) -> Option<i32> {
+    score.map(|score| if side == 0 { score } else { -score })
+}
+
+fn get_loss(side: usize, termination: String) -> GameResult {
+    GameResult {
+        result: if side == 0 { "0-1" } else { "1-0" },
+        termination,
+    }
+}
+
+fn get_game_result(
+    board: &Board,
+    hashes: &[u64],
+    magic_bitboards: &MagicBitboards,
+) -> Option<GameResult> {
+    let draw = |termination: &str| {
+        Some(GameResult {
+            result: "1/2-1/2",
+            termination: termination.to_string(),
+        })
+    };
+    if get_legal_moves(board, magic_bitboards).is_empty() {
+        let in_check = generate_moves(board, magic_bitboards).check_count > 0;
+        return match (in_check, board.stm == WHITE) {
+            (true, true) => Some(get_loss(0, "Black mates".to_string())),
+            (true, false) => Some(get_loss(1, "White mates".to_string())),
+            (false, _) => draw("Stalemate"),
+        };
+    }
+    if board.halfmove_clock >= 100 {
+        return draw("Fifty move rule");
+    }
+    if hashes.iter().filter(|hash| **hash == board.hash).count() >= 3 {
+        return draw("3-fold repetition");
+    }
+    let heavy = board.w_pawns | board.b_pawns | board.w_rooks | board.b_rooks;
+    let minors = board.w_knights | board.b_knights | board.w_bishops | board.b_bishops;
+    if heavy | board.w_queens | board.b_queens == 0 && minors.count_ones() <= 1 {
+        return draw("Insufficient material");
+    }
+    None
+}
+
+fn adjudicate(scores: &[Option<i32>], fullmove_number: u16, config: &Config) -> Option<GameResult> {
+    let last = |moves: usize| {
+        (moves > 0 && scores.len() >= 2 * moves).then(|| &scores[scores.len() - 2 * moves..])
+    };
+    if let Some(recent) = last(config.resign_moves) {
+        if recent
+            .iter()
+            .all(|score| score.is_some_and(|score| score >= config.resign_score))
+        {
+            return Some(get_loss(1, "White wins by adjudication".to_string()));
+        }
+        if recent
+            .iter()
+            .all(|score| score.is_some_and(|score| score <= -config.resign_score))
+        {
+            return Some(get_loss(0, "Black wins by adjudication".to_string()));
+        }
+    }
+    if fullmove_number >= config.draw_move_number
+        && let Some(recent) = last(config.draw_moves)
+        && recent
+            .iter()
+            .all(|score| score.is_some_and(|score| score.abs() <= config.draw_score))
+    {
+        return Some(GameResult {
+            result: "1/2-1/2",
+            termination: "Draw by adjudication".to_string(),
+        });
+    }
+    None
+}
+
+fn get_date() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86400) as i64;
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year}.{month:02}.{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_config(args: &str) -> Config {
+        let args: Vec<String> = format!("-engine1 a -engine2 b {args}")
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        Config::from_args(&args).unwrap()
+    }
+
+    fn assert_close(value: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (value - expected).abs() <= tolerance,
+            "{value} != {expected}"
+        );
+    }
+
+    /// 60 wins, 40 losses and 100 draws, which cutechess reports as
+    /// "Elo difference: 34.9 +/- 34.2, LOS: 97.7 %".
+    #[test]
+    fn statistics_match_reference_values() {
+        let (elo, margin) = get_elo_margin(60, 40, 100);
+        assert_close(elo, 34.86, 0.01);
+        assert_close(margin, 34.16, 0.01);
+        assert_close(get_los(60, 40), 0.97725, 0.0001);
+        assert_close(get_los(40, 60), 1.0 - 0.97725, 0.0001);
+        assert_eq!(get_los(0, 0), 0.5);
+        assert_close(get_elo(0.75), 190.85, 0.01);
+        assert_eq!(get_elo(1.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn sprt_matches_reference_values() {
+        let sprt = Sprt {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+        let (lower, upper) = sprt.get_bounds();
+        assert_close(lower, -2.944, 0.001);
+        assert_close(upper, 2.944, 0.001);
+        assert_close(sprt.get_llr(60, 40, 100), 0.5451, 0.0001);
+        assert!(sprt.get_llr(40, 60, 100) < 0.0);
+        assert_eq!(sprt.get_llr(0, 0, 0), 0.0);
+        assert_eq!(sprt.get_llr(0, 0, 10), 0.0);
+    }
+
+    #[test]
+    fn adjudication_needs_consecutive_scores() {
+        let config = get_config("-resign 500 3 -draw 40 10 4");
+        let result = |scores: &[Option<i32>], fullmove_number| {
+            adjudicate(scores, fullmove_number, &config).map(|result| result.result)
+        };
+        assert_eq!(result(&[Some(600); 6], 20), Some("1-0"));
+        assert_eq!(result(&[Some(600); 5], 20), None);
+        assert_eq!(result(&[Some(600), None, Some(600)].repeat(2), 20), None);
+        assert_eq!(result(&[Some(-600); 6], 20), Some("0-1"));
+        assert_eq!(result(&[Some(5); 8], 40), Some("1/2-1/2"));
+        assert_eq!(result(&[Some(5); 8], 39), None);
+        assert_eq!(result(&[Some(5); 7], 40), None);
+    }
+
+    #[test]
+    fn resignation_credits_the_side_ahead() {
+        let config = get_config("-resign 500 3");
+        // Black's engine reports its own advantage while white's agrees it is lost.
+        let scores: Vec<Option<i32>> = (0..6)
+            .map(|ply| get_white_score(Some(if ply % 2 == 0 { -700 } else { 700 }), ply % 2))
+            .collect();
+        let result = adjudicate(&scores, 30, &config).unwrap();
+        assert_eq!(result.result, "0-1");
+        assert_eq!(result.termination, "Black wins by adjudication");
+
+        let scores: Vec<Option<i32>> = (0..6)
+            .map(|ply| get_white_score(Some(if ply % 2 == 0 { 700 } else { -700 }), ply % 2))
+            .collect();
+        let result = adjudicate(&scores, 30, &config).unwrap();
+        assert_eq!(result.result, "1-0");
+        assert_eq!(result.termination, "White wins by adjudication");
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    env, fs,
+    sync::atomic::{AtomicBool, AtomicU64},
+    time::Instant,
+};
+
+use nuvo_chess::{
+    board_rep::{board::Board, magic_bitboards::MagicBitboards},
+    engine::{limits::SearchLimits, search::Search, tt::TranspositionTable},
+    evaluation::{
+        endgame::SCALE_NORMAL,
+        eval::{get_phase, trace_evaluation},
+        kpk::init_kpk,
+        params::{PARAM_COUNT, PARAMS, print_params},
+    },
+};
+
+const DEFAULT_EPOCHS: usize = 500;
+const DEFAULT_LEARNING_RATE: f64 = 1.0;
+const MAX_PHASE: f64 = 24.0;
+const BETA1: f64 = 0.9;
+const BETA2: f64 = 0.999;
+const EPSILON: f64 = 1e-8;
+
+const TUNED_PARAMS: usize = PARAM_COUNT * 2;
+
+struct Entry {
+    coefficients: Vec<(u16, f32)>,
+    phase: f64,
+    scales: [f64; 2],
+    result: f64,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(path) = args.first() else {
+        println!("usage: tune <dataset.epd> [epochs] [learning_rate]");
+        return;
+    };
+    let epochs = args
+        .get(1)
+        .and_then(|epochs| epochs.parse().ok())
+        .unwrap_or(DEFAULT_EPOCHS);
+    let learning_rate = args
+        .get(2)
+        .and_then(|learning_rate| learning_rate.parse().ok())
+        .unwrap_or(DEFAULT_LEARNING_RATE);
+
+    let magic_bitboards = MagicBitboards::new();
+    init_kpk(&magic_bitboards);
+    let now = Instant::now();
+    let entries = match load_entries(path, &magic_bitboards) {
+        Ok(entries) => entries,
+        Err(error) => {
+            println!("{error}");
+            return;
+        }
+    };
+    println!(
+        "Loaded {} positions in {:.2} seconds",
+        entries.len(),
+        now.elapsed().as_secs_f64()
+    );
+    if entries.is_empty() {
+        return;
+    }
+
+    let mut params = get_initial_params();
+    let k = find_k(&entries, &params);
+    println!("K: {k:.4}");
+    println!("Initial error: {:.6}", get_error(&entries, &params, k));
+
+    let mut m = vec![0.0; TUNED_PARAMS];
+    let mut v = vec![0.0; TUNED_PARAMS];
+    for epoch in 1..=epochs {
+        let gradient = get_gradient(&entries, &params, k);
+        for i in 0..TUNED_PARAMS {
+            m[i] = BETA1 * m[i] + (1.0 - BETA1) * gradient[i];
+            v[i] = BETA2 * v[i] + (1.0 - BETA2) * gradient[i] * gradient[i];
+            let m_hat = m[i] / (1.0 - BETA1.powi(epoch as i32));
+            let v_hat = v[i] / (1.0 - BETA2.powi(epoch as i32));
+            params[i] -= learning_rate * m_hat / (v_hat.sqrt() + EPSILON);
+        }
+        if epoch % 10 == 0 || epoch == epochs {
+            println!(
+                "Epoch {epoch}: error {:.6}",
+                get_error(&entries, &params, k)
+            );
+        }
+    }
+
+    println!();
+    let weights: Vec<(i32, i32)> = (0..PARAM_COUNT)
+        .map(|i| {
+            (
+                params[i].round() as i32,
+                params[PARAM_COUNT + i].round() as i32,
+            )
+        })
+        .collect();
+    print_params(&weights);
+}
+
+fn load_entries(path: &str, magic_bitboards: &MagicBitboards) -> Result<Vec<Entry>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|error| format!("failed to read {path}: {error}"))?;
+    let tt = TranspositionTable::new(1);
+    let stop = AtomicBool::new(false);
+    let total_nodes = AtomicU64::new(0);
+    let limits = SearchLimits::new();
+
+    let mut entries = Vec::new();
+    for line in contents.lines() {
+        let Some(result) = parse_result(line) else {
+            continue;
+        };
+        let fen = line
+            .split_whitespace()
+            .take(4)
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let mut board = Board::new();
+        board.set_from_fen(&fen, magic_bitboards);
+        if board.w_king == 0 || board.b_king == 0 {
+            continue;
+        }
+
+        let mut search = Search::new(
+            magic_bitboards,
+            &tt,
+            &stop,
+            &total_nodes,
+            &limits,
+            board.stm,
+            0,
+        );
+        let quiet_board = search.get_quiet_position(&board);
+        if let Some(entry) = get_entry(&quiet_board, magic_bitboards, result) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads the game result from the tokens after the four FEN fields, accepting `1-0`/`0-1`/
+/// `1/2-1/2` and `1.0`/`0.0`/`0.5`, optionally quoted or bracketed.
+fn parse_result(line: &str) -> Option<f64> {
+    line.split_whitespace().skip(4).find_map(|token| {
+        match token.trim_matches(|c| matches!(c, '"' | ';' | '[' | ']')) {
+            "1-0" | "1.0" => Some(1.0),
+            "0-1" | "0.0" => Some(0.0),
+            "1/2-1/2" | "0.5" => Some(0.5),
+            _ => None,
+        }
+    })
+}
+
+fn get_entry(board: &Board, magic_bitboards: &MagicBitboards, result: f64) -> Option<Entry> {
+    let (trace, scales) = trace_evaluation(board, magic_bitboards)?;
+    let coefficients = trace
+        .coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, coefficient)| **coefficient != 0.0)
+        .map(|(param, coefficient)| (param as u16, *coefficient as f32))
+        .collect();
+    Some(Entry {
+        coefficients,
+        phase: get_phase(board) as f64 / MAX_PHASE,
+        scales: scales.map(|scale| scale as f64 / SCALE_NORMAL as f64),
+        result,
+    })
+}
+
+fn get_initial_params() -> Vec<f64> {
+    let mut params = vec![0.0; TUNED_PARAMS];
+    for (i, (mg, eg)) in PARAMS.iter().enumerate() {
+        params[i] = *mg as f64;
+        params[PARAM_COUNT + i] = *eg as f64;
+    }
+    params
+}
+
+/// Returns the white-relative evaluation of `entry` and the endgame scale applied to it.
+fn evaluate(entry: &Entry, params: &[f64]) -> (f64, f64) {
+    let mut mg = 0.0;
+    let mut eg = 0.0;
+    for (param, coefficient) in &entry.coefficients {
+        let param = *param as usize;
+        let coefficient = *coefficient as f64;
+        mg += coefficient * params[param];
+        eg += coefficient * params[PARAM_COUNT + param];
+    }
+    let scale = if eg > 0.0 {
+        entry.scales[0]
+    } else {
+        entry.scales[1]
+    };
+    (mg * entry.phase + eg * scale * (1.0 - entry.phase), scale)
+}
+
+fn sigmoid(score: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10.0_f64.powf(-k * score / 400.0))
+}
+
+fn get_error(entries: &[Entry], params: &[f64], k: f64) -> f64 {
+    entries
+        .iter()
+        .map(|entry| (entry.result - sigmoid(evaluate(entry, params).0, k)).powi(2))
+        .sum::<f64>()
+        / entries.len() as f64
+}
+
+fn find_k(entries: &[Entry], params: &[f64]) -> f64 {
+    let mut k = 1.0;
+    let mut step = 0.5;
+    let mut best_error = get_error(entries, params, k);
+    while step > 0.0001 {
+        let mut improved = false;
+        for candidate in [k - step, k + step] {
+            let error = get_error(entries, params, candidate);
+            if candidate > 0.0 && error < best_error {
+                best_error = error;
+                k = candidate;
+                improved = true;
+            }
+        }
+        if !improved {
+            step /= 2.0;
+        }
+    }
+    k
+}
+
+fn get_gradient(entries: &[Entry], params: &[f64], k: f64) -> Vec<f64> {
+    let mut gradient = vec![0.0; TUNED_PARAMS];
+    let k_scale = k * 10.0_f64.ln() / 400.0;
+    for entry in entries {
+        let (score, scale) = evaluate(entry, params);
+        let sigmoid = sigmoid(score, k);
+        let error = (sigmoid - entry.result) * sigmoid * (1.0 - sigmoid) * k_scale;
+        let mg_error = error * entry.phase;
+        let eg_error = error * scale * (1.0 - entry.phase);
+        for (param, coefficient) in &entry.coefficients {
+            let param = *param as usize;
+            let coefficient = *coefficient as f64;
+            gradient[param] += coefficient * mg_error;
+            gradient[PARAM_COUNT + param] += coefficient * eg_error;
+        }
+    }
+    let count = entries.len() as f64;
+    gradient.iter().map(|value| value * 2.0 / count).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_result;
+
+    #[test]
+    fn parses_result_tokens() {
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq -";
+        assert_eq!(parse_result(&format!("{fen} [1.0]")), Some(1.0));
+        assert_eq!(parse_result(&format!("{fen} [0.5]")), Some(0.5));
+        assert_eq!(parse_result(&format!("{fen} c9 \"0-1\";")), Some(0.0));
+        assert_eq!(parse_result(&format!("{fen} 1/2-1/2")), Some(0.5));
+        assert_eq!(parse_result(&format!("{fen} 1-0")), Some(1.0));
+        assert_eq!(parse_result(&format!("{fen} c0 \"10-15 opening\";")), None);
+        assert_eq!(parse_result(fen), None);
+    }
+}
@@ -1,3 +1,8 @@
+pub const FILE_A: u64 = 0x0101010101010101;
+pub const FILE_H: u64 = FILE_A << 7;
+pub const LIGHT_SQUARES: u64 = 0xAA55AA55AA55AA55;
+pub const DARK_SQUARES: u64 = !LIGHT_SQUARES;
+
 #[inline(always)]
 pub fn set_bit(bitboard: u64, square: u8) -> u64 {
     bitboard | (1 << square)
@@ -47,6 +52,35 @@ pub fn last_bit_pop(bitboard: &mut u64) -> u8 {
     bit
 }
 
+#[inline(always)]
+pub fn north_fill(mut bitboard: u64) -> u64 {
+    bitboard |= bitboard >> 8;
+    bitboard |= bitboard >> 16;
+    bitboard | bitboard >> 32
+}
+
+#[inline(always)]
+pub fn south_fill(mut bitboard: u64) -> u64 {
+    bitboard |= bitboard << 8;
+    bitboard |= bitboard << 16;
+    bitboard | bitboard << 32
+}
+
+#[inline(always)]
+pub fn file_fill(bitboard: u64) -> u64 {
+    north_fill(bitboard) | south_fill(bitboard)
+}
+
+#[inline(always)]
+pub fn east_one(bitboard: u64) -> u64 {
+    (bitboard & !FILE_H) << 1
+}
+
+#[inline(always)]
+pub fn west_one(bitboard: u64) -> u64 {
+    (bitboard & !FILE_A) >> 1
+}
+
 pub fn print_bitboard(bitboard: u64) {
     for rank in 0..8 {
         for file in 0..8 {
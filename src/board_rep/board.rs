@@ -1,9 +1,10 @@
 use crate::board_rep::{
-    bit_operations::{clear_bit, is_bit_set, set_bit},
+    bit_operations::{clear_bit, first_bit, is_bit_set, set_bit},
     c_move::CMove,
     magic_bitboards::MagicBitboards,
     move_gen::generate_moves,
-    squares::{A1, A8, C1, C8, D1, D8, E1, E8, G1, G8, H1, H8, SQUARE_NAMES},
+    squares::{A1, A8, H1, H8, SQUARE_NAMES},
+    zobrist::ZOBRIST,
 };
 
 pub const WHITE: u8 = 16;
@@ -15,6 +16,8 @@ pub const CASTLING_WK: u8 = 1;
 pub const CASTLING_WQ: u8 = 2;
 pub const CASTLING_BK: u8 = 4;
 pub const CASTLING_BQ: u8 = 8;
+pub const CASTLING_RIGHTS: [u8; 4] = [CASTLING_WK, CASTLING_WQ, CASTLING_BK, CASTLING_BQ];
+const DEFAULT_CASTLING_ROOKS: [u8; 4] = [H1, A1, H8, A8];
 
 pub const EN_PASSANT_NONE: u8 = 64;
 
@@ -27,10 +30,47 @@ pub const QUEEN: u8 = 5;
 pub const KING: u8 = 6;
 pub const PIECE_MASK: u8 = PAWN | KNIGHT | BISHOP | ROOK | QUEEN | KING;
 
+#[derive(Clone, Copy, Default)]
+pub struct PieceDelta {
+    pub removed: [(u8, u8); 2],
+    pub removed_count: usize,
+    pub added: [(u8, u8); 2],
+    pub added_count: usize,
+}
+
+impl PieceDelta {
+    pub fn get_removed(&self) -> &[(u8, u8)] {
+        &self.removed[..self.removed_count]
+    }
+
+    pub fn get_added(&self) -> &[(u8, u8)] {
+        &self.added[..self.added_count]
+    }
+
+    pub fn moves_king(&self, color: u8) -> bool {
+        self.get_removed()
+            .iter()
+            .any(|(piece, _)| *piece == color | KING)
+    }
+
+    fn remove(&mut self, piece: u8, square: u8) {
+        self.removed[self.removed_count] = (piece, square);
+        self.removed_count += 1;
+    }
+
+    fn add(&mut self, piece: u8, square: u8) {
+        self.added[self.added_count] = (piece, square);
+        self.added_count += 1;
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Board {
     pub stm: u8,
     pub castling: u8,
+    pub castling_rooks: [u8; 4],
+    /// Writes and reads castling moves as king takes rook. Kept across `set_from_fen`.
+    pub chess960: bool,
     pub en_passant: u8,
     pub halfmove_clock: u8,
     pub fullmove_number: u16,
@@ -49,6 +89,8 @@ pub struct Board {
     pub w_occupancy: u64,
     pub b_occupancy: u64,
     pub all_occupancy: u64,
+    pub hash: u64,
+    pub pawn_hash: u64,
 }
 
 impl Default for Board {
@@ -62,6 +104,8 @@ impl Board {
         Board {
             stm: WHITE,
             castling: CASTLING_NONE,
+            castling_rooks: DEFAULT_CASTLING_ROOKS,
+            chess960: false,
             en_passant: EN_PASSANT_NONE,
             halfmove_clock: 0,
             fullmove_number: 1,
@@ -80,6 +124,8 @@ impl Board {
             w_occupancy: 0,
             b_occupancy: 0,
             all_occupancy: 0,
+            hash: 0,
+            pawn_hash: 0,
         }
     }
 
@@ -114,6 +160,8 @@ impl Board {
         self.set_en_passant(en_passant);
         self.halfmove_clock = halfmove_clock;
         self.fullmove_number = fullmove_number;
+        self.hash = self.get_hash();
+        self.pawn_hash = self.get_pawn_hash();
         for move_str in fen_moves {
             self.move_from_str(magic_bitboards, move_str);
         }
@@ -123,7 +171,7 @@ impl Board {
         let mi = generate_moves(self, magic_bitboards);
         for i in 0..mi.c_move_list.count {
             let c_move = mi.c_move_list.moves[i];
-            if c_move.to_string() == move_str {
+            if c_move.uci(self.chess960).to_string() == move_str {
                 self.make_move(c_move);
                 return;
             }
@@ -197,9 +245,46 @@ impl Board {
         }
     }
 
+    pub fn make_move_with_delta(&mut self, c_move: CMove) -> PieceDelta {
+        let delta = self.get_piece_delta(c_move);
+        self.make_move(c_move);
+        delta
+    }
+
+    pub fn get_piece_delta(&self, c_move: CMove) -> PieceDelta {
+        let mut delta = PieceDelta::default();
+        let piece = self.get_piece_at(c_move.from_square);
+        delta.remove(piece, c_move.from_square);
+        if c_move.castling {
+            let (king_square, rook_square) = c_move.get_castling_squares();
+            delta.remove(self.stm | ROOK, c_move.to_square);
+            delta.add(piece, king_square);
+            delta.add(self.stm | ROOK, rook_square);
+            return delta;
+        }
+
+        let captured = self.get_piece_at(c_move.to_square);
+        if captured != EMPTY {
+            delta.remove(captured, c_move.to_square);
+        } else if piece & PIECE_MASK == PAWN && c_move.to_square == self.en_passant {
+            delta.remove((self.stm ^ COLOR_MASK) | PAWN, self.get_ep_pawn_index());
+        }
+        if c_move.promotion != 0 {
+            delta.add(self.stm | c_move.promotion, c_move.to_square);
+        } else {
+            delta.add(piece, c_move.to_square);
+        }
+        delta
+    }
+
     pub fn make_move(&mut self, c_move: CMove) {
         let from_piece = self.get_piece_at(c_move.from_square);
-        let to_piece = self.get_piece_at(c_move.to_square);
+        let to_piece = if c_move.castling {
+            EMPTY
+        } else {
+            self.get_piece_at(c_move.to_square)
+        };
+        self.hash ^= ZOBRIST.castling[self.castling as usize] ^ self.get_en_passant_key();
         if from_piece & PIECE_MASK == PAWN && c_move.to_square == self.en_passant {
             let ep_pawn_index = self.get_ep_pawn_index();
             self.set_piece_at(ep_pawn_index, EMPTY);
@@ -211,37 +296,25 @@ impl Board {
                 self.castling =
                     (self.castling | CASTLING_BK | CASTLING_BQ) ^ (CASTLING_BK | CASTLING_BQ);
             }
+        }
 
-            if c_move.from_square == E1 && c_move.to_square == G1 {
-                self.set_piece_at(H1, EMPTY);
-                self.set_piece_at(G1, WHITE | ROOK);
-            } else if c_move.from_square == E1 && c_move.to_square == C1 {
-                self.set_piece_at(A1, EMPTY);
-                self.set_piece_at(D1, WHITE | ROOK);
-            } else if c_move.from_square == E8 && c_move.to_square == G8 {
-                self.set_piece_at(H8, EMPTY);
-                self.set_piece_at(G8, BLACK | ROOK);
-            } else if c_move.from_square == E8 && c_move.to_square == C8 {
-                self.set_piece_at(A8, EMPTY);
-                self.set_piece_at(D8, BLACK | ROOK);
+        for (i, rook_square) in self.castling_rooks.iter().enumerate() {
+            if c_move.from_square == *rook_square || c_move.to_square == *rook_square {
+                self.castling = (self.castling | CASTLING_RIGHTS[i]) ^ CASTLING_RIGHTS[i];
             }
         }
 
-        if c_move.from_square == A1 || c_move.to_square == A1 {
-            self.castling = (self.castling | CASTLING_WQ) ^ CASTLING_WQ;
-        }
-        if c_move.from_square == H1 || c_move.to_square == H1 {
-            self.castling = (self.castling | CASTLING_WK) ^ CASTLING_WK;
-        }
-        if c_move.from_square == A8 || c_move.to_square == A8 {
-            self.castling = (self.castling | CASTLING_BQ) ^ CASTLING_BQ;
-        }
-        if c_move.from_square == H8 || c_move.to_square == H8 {
-            self.castling = (self.castling | CASTLING_BK) ^ CASTLING_BK;
+        if c_move.castling {
+            let (king_square, rook_square) = c_move.get_castling_squares();
+            self.set_piece_at(c_move.from_square, EMPTY);
+            self.set_piece_at(c_move.to_square, EMPTY);
+            self.set_piece_at(king_square, self.stm | KING);
+            self.set_piece_at(rook_square, self.stm | ROOK);
+        } else {
+            self.set_piece_at(c_move.from_square, EMPTY);
+            self.set_piece_at(c_move.to_square, EMPTY);
+            self.set_piece_at(c_move.to_square, from_piece);
         }
-
-        self.set_piece_at(c_move.from_square, EMPTY);
-        self.set_piece_at(c_move.to_square, from_piece);
         if c_move.promotion != 0 {
             self.set_piece_at(c_move.to_square, EMPTY);
             self.set_piece_at(c_move.to_square, self.stm | c_move.promotion);
@@ -267,6 +340,34 @@ impl Board {
 
         self.fullmove_number += if self.stm == BLACK { 1 } else { 0 };
         self.stm ^= COLOR_MASK;
+        self.hash ^=
+            ZOBRIST.castling[self.castling as usize] ^ self.get_en_passant_key() ^ ZOBRIST.side;
+    }
+
+    pub fn get_hash(&self) -> u64 {
+        let mut hash = 0;
+        for square in 0..64 {
+            let piece = self.get_piece_at(square);
+            if piece != EMPTY {
+                hash ^= ZOBRIST.piece_key(piece, square);
+            }
+        }
+        hash ^= ZOBRIST.castling[self.castling as usize] ^ self.get_en_passant_key();
+        if self.stm == BLACK {
+            hash ^= ZOBRIST.side;
+        }
+        hash
+    }
+
+    pub fn get_pawn_hash(&self) -> u64 {
+        let mut hash = 0;
+        for square in 0..64 {
+            let piece = self.get_piece_at(square);
+            if piece & PIECE_MASK == PAWN {
+                hash ^= ZOBRIST.piece_key(piece, square);
+            }
+        }
+        hash
     }
 
     pub fn get_ep_pawn_index(&self) -> u8 {
@@ -275,23 +376,36 @@ impl Board {
         }
 
         let file = (self.en_passant % 8) as i8;
-        let rank = (self.en_passant / 8) as i8 + if self.stm == WHITE { -1 } else { 1 };
+        let rank = (self.en_passant / 8) as i8 + if self.stm == WHITE { 1 } else { -1 };
         (rank * 8 + file) as u8
     }
 
+    fn get_en_passant_key(&self) -> u64 {
+        if self.en_passant == EN_PASSANT_NONE {
+            0
+        } else {
+            ZOBRIST.en_passant[(self.en_passant % 8) as usize]
+        }
+    }
+
     fn get_castling_str(&self) -> String {
         let mut castling_str = String::new();
-        if self.castling & CASTLING_WK != 0 {
-            castling_str.push('K');
-        }
-        if self.castling & CASTLING_WQ != 0 {
-            castling_str.push('Q');
-        }
-        if self.castling & CASTLING_BK != 0 {
-            castling_str.push('k');
-        }
-        if self.castling & CASTLING_BQ != 0 {
-            castling_str.push('q');
+        for (i, right) in CASTLING_RIGHTS.iter().enumerate() {
+            if self.castling & right == 0 {
+                continue;
+            }
+            let rook_square = self.castling_rooks[i];
+            let c = if rook_square == DEFAULT_CASTLING_ROOKS[i] {
+                ['K', 'Q', 'k', 'q'][i]
+            } else {
+                let file = (b'A' + rook_square % 8) as char;
+                if *right & (CASTLING_BK | CASTLING_BQ) != 0 {
+                    file.to_ascii_lowercase()
+                } else {
+                    file
+                }
+            };
+            castling_str.push(c);
         }
         if castling_str.is_empty() {
             castling_str.push('-');
@@ -395,6 +509,7 @@ impl Board {
     fn reset(&mut self) {
         self.stm = WHITE;
         self.castling = CASTLING_NONE;
+        self.castling_rooks = DEFAULT_CASTLING_ROOKS;
         self.en_passant = EN_PASSANT_NONE;
         self.halfmove_clock = 0;
         self.fullmove_number = 1;
@@ -413,6 +528,8 @@ impl Board {
         self.w_occupancy = 0;
         self.b_occupancy = 0;
         self.all_occupancy = 0;
+        self.hash = 0;
+        self.pawn_hash = 0;
     }
 
     fn set_pieces(&mut self, pieces: &str) {
@@ -478,10 +595,24 @@ impl Board {
         }
     }
 
-    fn set_piece_at(&mut self, square: u8, piece: u8) {
+    pub fn set_piece_at(&mut self, square: u8, piece: u8) {
         if square > H1 {
             return;
         }
+        if piece == EMPTY {
+            let old_piece = self.get_piece_at(square);
+            if old_piece != EMPTY {
+                self.hash ^= ZOBRIST.piece_key(old_piece, square);
+            }
+            if old_piece & PIECE_MASK == PAWN {
+                self.pawn_hash ^= ZOBRIST.piece_key(old_piece, square);
+            }
+        } else {
+            self.hash ^= ZOBRIST.piece_key(piece, square);
+            if piece & PIECE_MASK == PAWN {
+                self.pawn_hash ^= ZOBRIST.piece_key(piece, square);
+            }
+        }
         match piece {
             x if x == EMPTY => {
                 self.w_pawns = clear_bit(self.w_pawns, square);
@@ -566,14 +697,38 @@ impl Board {
 
     fn set_castling(&mut self, castling: &str) {
         self.castling = CASTLING_NONE;
+        self.castling_rooks = DEFAULT_CASTLING_ROOKS;
         for c in castling.chars() {
-            match c {
-                'K' => self.castling |= CASTLING_WK,
-                'Q' => self.castling |= CASTLING_WQ,
-                'k' => self.castling |= CASTLING_BK,
-                'q' => self.castling |= CASTLING_BQ,
-                _ => {}
-            }
+            let (color, rank_start, king) = if c.is_ascii_uppercase() {
+                (WHITE, A1, self.w_king)
+            } else {
+                (BLACK, A8, self.b_king)
+            };
+            if king == 0 || first_bit(king) / 8 != rank_start / 8 {
+                continue;
+            }
+            let king_file = first_bit(king) % 8;
+            let is_rook = |file: &u8| self.get_piece_at(rank_start + file) == color | ROOK;
+            let rook_file = match c.to_ascii_uppercase() {
+                'K' => (king_file + 1..8).rev().find(is_rook),
+                'Q' => (0..king_file).find(is_rook),
+                'A'..='H' => Some(c.to_ascii_uppercase() as u8 - b'A').filter(is_rook),
+                _ => None,
+            };
+            let Some(rook_file) = rook_file else {
+                continue;
+            };
+            if rook_file == king_file {
+                continue;
+            }
+            let index = match (color == WHITE, rook_file > king_file) {
+                (true, true) => 0,
+                (true, false) => 1,
+                (false, true) => 2,
+                (false, false) => 3,
+            };
+            self.castling |= CASTLING_RIGHTS[index];
+            self.castling_rooks[index] = rank_start + rook_file;
         }
     }
 
@@ -609,3 +764,34 @@ impl Board {
         self.en_passant = (rank * 8 + file) as u8;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    fn get_castling(fen: &str) -> (u8, [u8; 4]) {
+        let mut board = Board::new();
+        board.set_from_fen(fen, get_test_magic_bitboards());
+        (board.castling, board.castling_rooks)
+    }
+
+    #[test]
+    fn castling_rights_need_a_rook() {
+        let (castling, rooks) = get_castling("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1");
+        assert_eq!(
+            castling,
+            CASTLING_WK | CASTLING_WQ | CASTLING_BK | CASTLING_BQ
+        );
+        assert_eq!(rooks, [A1 + 6, A1 + 1, A8 + 6, A8 + 1]);
+
+        let (castling, _) = get_castling("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w HAha - 0 1");
+        assert_eq!(castling, CASTLING_NONE);
+        let (castling, _) = get_castling("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w Gb - 0 1");
+        assert_eq!(castling, CASTLING_WK | CASTLING_BQ);
+        let (castling, _) = get_castling("4k3/8/8/8/8/8/8/R3K3 w KQ - 0 1");
+        assert_eq!(castling, CASTLING_WQ);
+        let (castling, _) = get_castling("4k3/8/8/8/8/8/4K3/R6R w KQ - 0 1");
+        assert_eq!(castling, CASTLING_NONE);
+    }
+}
@@ -5,20 +5,56 @@ use crate::board_rep::{
     squares::SQUARE_NAMES,
 };
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
 pub struct CMove {
     pub from_square: u8,
     pub to_square: u8,
     pub promotion: u8,
+    pub castling: bool,
 }
 
-impl Display for CMove {
+impl CMove {
+    pub fn is_kingside_castling(&self) -> bool {
+        self.castling && self.to_square > self.from_square
+    }
+
+    pub fn get_castling_squares(&self) -> (u8, u8) {
+        let rank_start = self.from_square - self.from_square % 8;
+        if self.is_kingside_castling() {
+            (rank_start + 6, rank_start + 5)
+        } else {
+            (rank_start + 2, rank_start + 3)
+        }
+    }
+
+    /// Formats the move in UCI notation. Castling is written as the king's two square step, or as
+    /// king takes rook in Chess960.
+    pub fn uci(self, chess960: bool) -> UciMove {
+        UciMove {
+            c_move: self,
+            chess960,
+        }
+    }
+}
+
+pub struct UciMove {
+    c_move: CMove,
+    chess960: bool,
+}
+
+impl Display for UciMove {
     fn fmt(&self, f: &mut Formatter) -> Result {
+        let c_move = &self.c_move;
         let mut move_str = String::new();
-        move_str.push_str(SQUARE_NAMES[self.from_square as usize]);
-        move_str.push_str(SQUARE_NAMES[self.to_square as usize]);
-        if self.promotion != 0 {
-            match self.promotion {
+        let to_square = if c_move.castling && !self.chess960 {
+            c_move.get_castling_squares().0
+        } else {
+            c_move.to_square
+        };
+        move_str.push_str(SQUARE_NAMES[c_move.from_square as usize]);
+        move_str.push_str(SQUARE_NAMES[to_square as usize]);
+        if c_move.promotion != 0 {
+            match c_move.promotion {
                 KNIGHT => move_str.push('n'),
                 BISHOP => move_str.push('b'),
                 ROOK => move_str.push('r'),
@@ -29,3 +65,28 @@ impl Display for CMove {
         write!(f, "{move_str}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::squares::{E1, G1, H1};
+
+    #[test]
+    fn uci_castling_notation() {
+        let castling = CMove {
+            from_square: E1,
+            to_square: H1,
+            promotion: 0,
+            castling: true,
+        };
+        assert_eq!(castling.uci(false).to_string(), "e1g1");
+        assert_eq!(castling.uci(true).to_string(), "e1h1");
+        let promotion = CMove {
+            from_square: E1,
+            to_square: G1,
+            promotion: QUEEN,
+            castling: false,
+        };
+        assert_eq!(promotion.uci(true).to_string(), "e1g1q");
+    }
+}
@@ -24,7 +24,22 @@ impl CMoveList {
             from_square,
             to_square,
             promotion,
+            castling: false,
         };
+        self.push(c_move);
+    }
+
+    pub fn add_castling_move(&mut self, king_square: u8, rook_square: u8) {
+        let c_move = CMove {
+            from_square: king_square,
+            to_square: rook_square,
+            promotion: 0,
+            castling: true,
+        };
+        self.push(c_move);
+    }
+
+    pub fn push(&mut self, c_move: CMove) {
         self.moves[self.count] = c_move;
         self.count += 1;
     }
@@ -0,0 +1,105 @@
+use crate::board_rep::{board::Board, magic_bitboards::MagicBitboards};
+
+pub const CHESS960_POSITIONS: usize = 960;
+pub const DOUBLE_CHESS960_POSITIONS: usize = CHESS960_POSITIONS * CHESS960_POSITIONS;
+
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+pub fn get_960_back_rank(index: usize) -> [char; 8] {
+    let mut back_rank = [' '; 8];
+    let mut n = index % CHESS960_POSITIONS;
+
+    back_rank[(n % 4) * 2 + 1] = 'B';
+    n /= 4;
+    back_rank[(n % 4) * 2] = 'B';
+    n /= 4;
+    place_on_empty(&mut back_rank, n % 6, 'Q');
+    n /= 6;
+
+    let (first_knight, second_knight) = KNIGHT_PLACEMENTS[n];
+    place_on_empty(&mut back_rank, second_knight, 'N');
+    place_on_empty(&mut back_rank, first_knight, 'N');
+
+    place_on_empty(&mut back_rank, 0, 'R');
+    place_on_empty(&mut back_rank, 0, 'K');
+    place_on_empty(&mut back_rank, 0, 'R');
+    back_rank
+}
+
+pub fn get_960_fen(index: usize) -> String {
+    get_double_960_fen(index, index)
+}
+
+pub fn get_double_960_fen(white_index: usize, black_index: usize) -> String {
+    let white: String = get_960_back_rank(white_index).iter().collect();
+    let black: String = get_960_back_rank(black_index)
+        .iter()
+        .map(|piece| piece.to_ascii_lowercase())
+        .collect();
+    format!("{black}/pppppppp/8/8/8/8/PPPPPPPP/{white} w KQkq - 0 1")
+}
+
+/// Double Chess960 index `white_index * 960 + black_index`, so 0..921599 covers every pairing.
+pub fn get_double_960_fen_from_index(index: usize) -> String {
+    let index = index % DOUBLE_CHESS960_POSITIONS;
+    get_double_960_fen(index / CHESS960_POSITIONS, index % CHESS960_POSITIONS)
+}
+
+pub fn get_960_board(fen: &str, magic_bitboards: &MagicBitboards) -> Board {
+    let mut board = Board::new();
+    board.chess960 = true;
+    board.set_from_fen(fen, magic_bitboards);
+    board
+}
+
+fn place_on_empty(back_rank: &mut [char; 8], empty_index: usize, piece: char) {
+    let file = back_rank
+        .iter()
+        .enumerate()
+        .filter(|(_, square)| **square == ' ')
+        .nth(empty_index)
+        .map(|(file, _)| file)
+        .expect("No empty square left on the back rank");
+    back_rank[file] = piece;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_numbering() {
+        assert_eq!(
+            get_960_fen(518),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+        assert_eq!(get_960_back_rank(0).iter().collect::<String>(), "BBQNNRKR");
+        assert_eq!(
+            get_960_back_rank(959).iter().collect::<String>(),
+            "RKRNNQBB"
+        );
+    }
+
+    #[test]
+    fn double_index() {
+        assert_eq!(
+            get_double_960_fen_from_index(518 * CHESS960_POSITIONS + 959),
+            get_double_960_fen(518, 959)
+        );
+        assert_eq!(
+            get_double_960_fen_from_index(DOUBLE_CHESS960_POSITIONS - 1),
+            get_double_960_fen(959, 959)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::board_rep::{
+    board::Board, c_move::CMove, magic_bitboards::MagicBitboards, san::parse_san,
+};
+
+pub struct EpdRecord {
+    pub board: Board,
+    pub fen: String,
+    pub operations: Vec<(String, String)>,
+}
+
+impl EpdRecord {
+    pub fn parse(line: &str, magic_bitboards: &MagicBitboards) -> Option<Self> {
+        let line = line.trim();
+        let mut fields = Vec::new();
+        let mut rest = line;
+        for _ in 0..4 {
+            rest = rest.trim_start();
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            fields.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+        let operations = parse_operations(rest);
+
+        let get_number = |name: &str, default: &str| {
+            operations
+                .iter()
+                .find(|(opcode, _)| opcode == name)
+                .map_or(default.to_string(), |(_, operand)| operand.clone())
+        };
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            get_number("hmvc", "0"),
+            get_number("fmvn", "1")
+        );
+        let mut board = Board::new();
+        board.set_from_fen(&fen, magic_bitboards);
+        if board.w_king == 0 || board.b_king == 0 {
+            return None;
+        }
+        Some(EpdRecord {
+            board,
+            fen,
+            operations,
+        })
+    }
+
+    pub fn get_operation(&self, opcode: &str) -> Option<&str> {
+        self.operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operand)| operand.as_str())
+    }
+
+    pub fn get_id(&self) -> &str {
+        self.get_operation("id").unwrap_or("")
+    }
+
+    pub fn get_moves(&self, opcode: &str, magic_bitboards: &MagicBitboards) -> Vec<CMove> {
+        self.get_operation(opcode)
+            .unwrap_or("")
+            .split_whitespace()
+            .filter_map(|san| parse_san(&self.board, magic_bitboards, san))
+            .collect()
+    }
+
+    /// STS-style scores from `c0 "Nf3=10, e4=5"`, falling back to 10 points for each `bm` move.
+    pub fn get_move_points(&self, magic_bitboards: &MagicBitboards) -> Vec<(CMove, u32)> {
+        let points: Vec<(CMove, u32)> = self
+            .get_operation("c0")
+            .unwrap_or("")
+            .split(',')
+            .filter_map(|part| {
+                let (san, points) = part.trim().rsplit_once('=')?;
+                let c_move = parse_san(&self.board, magic_bitboards, san)?;
+                Some((c_move, points.trim().parse().ok()?))
+            })
+            .collect();
+        if !points.is_empty() {
+            return points;
+        }
+        self.get_moves("bm", magic_bitboards)
+            .into_iter()
+            .map(|c_move| (c_move, 10))
+            .collect()
+    }
+}
+
+fn parse_operations(text: &str) -> Vec<(String, String)> {
+    let mut operations = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let opcode: String =
+            std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && *c != ';')).collect();
+        if opcode.is_empty() {
+            if chars.next().is_none() {
+                break;
+            }
+            continue;
+        }
+
+        let mut operand = String::new();
+        let mut quoted = false;
+        for c in chars.by_ref() {
+            match c {
+                '"' => quoted = !quoted,
+                ';' if !quoted => break,
+                c => operand.push(c),
+            }
+        }
+        operations.push((opcode, operand.trim().to_string()));
+    }
+    operations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    const START_EPD: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+
+    fn get_uci_moves(moves: &[CMove]) -> Vec<String> {
+        moves
+            .iter()
+            .map(|c_move| c_move.uci(false).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn quoted_operands_may_contain_semicolons() {
+        let line = format!(r#"{START_EPD} id "start; test"; bm e4;"#);
+        let record = EpdRecord::parse(&line, get_test_magic_bitboards()).unwrap();
+        assert_eq!(record.get_id(), "start; test");
+        assert_eq!(record.get_operation("bm"), Some("e4"));
+    }
+
+    #[test]
+    fn move_counters_default_unless_given() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let record = EpdRecord::parse(&format!("{START_EPD} bm e4;"), magic_bitboards).unwrap();
+        assert_eq!(record.fen, format!("{START_EPD} 0 1"));
+        let line = format!("{START_EPD} hmvc 12; fmvn 30;");
+        let record = EpdRecord::parse(&line, magic_bitboards).unwrap();
+        assert_eq!(record.fen, format!("{START_EPD} 12 30"));
+        assert_eq!(record.board.halfmove_clock, 12);
+        assert_eq!(record.board.fullmove_number, 30);
+    }
+
+    #[test]
+    fn move_operations_hold_several_moves() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let line = format!("{START_EPD} bm e4 Nf3; am g4 f3;");
+        let record = EpdRecord::parse(&line, magic_bitboards).unwrap();
+        assert_eq!(
+            get_uci_moves(&record.get_moves("bm", magic_bitboards)),
+            ["e2e4", "g1f3"]
+        );
+        assert_eq!(
+            get_uci_moves(&record.get_moves("am", magic_bitboards)),
+            ["g2g4", "f2f3"]
+        );
+    }
+
+    #[test]
+    fn move_points_come_from_c0_or_bm() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let line = format!(r#"{START_EPD} bm e4; c0 "e4=10, Nf3=7, d4=5";"#);
+        let record = EpdRecord::parse(&line, magic_bitboards).unwrap();
+        let (moves, points): (Vec<CMove>, Vec<u32>) =
+            record.get_move_points(magic_bitboards).into_iter().unzip();
+        assert_eq!(get_uci_moves(&moves), ["e2e4", "g1f3", "d2d4"]);
+        assert_eq!(points, [10, 7, 5]);
+
+        let line = format!("{START_EPD} bm e4 d4;");
+        let record = EpdRecord::parse(&line, magic_bitboards).unwrap();
+        let (moves, points): (Vec<CMove>, Vec<u32>) =
+            record.get_move_points(magic_bitboards).into_iter().unzip();
+        assert_eq!(get_uci_moves(&moves), ["e2e4", "d2d4"]);
+        assert_eq!(points, [10, 10]);
+    }
+
+    #[test]
+    fn invalid_positions_are_rejected() {
+        let magic_bitboards = get_test_magic_bitboards();
+        assert!(EpdRecord::parse("8/8/8/8/8/8/8/4K3 w - - bm Kd2;", magic_bitboards).is_none());
+        assert!(EpdRecord::parse("4k3/8/8/8/8/8/8/8 b - - bm Kd7;", magic_bitboards).is_none());
+        assert!(EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 w", magic_bitboards).is_none());
+    }
+}
@@ -192,6 +192,24 @@ impl MagicBitboards {
         bitboard.wrapping_mul(magic) >> (64 - bit_count)
     }
 
+    pub fn get_rook_attacks(&self, square: u8, occupancy: u64) -> u64 {
+        let mask = self.rook_masks[square as usize];
+        let mask_bits = count_bits(mask);
+        let key = mask & occupancy;
+        let magic = self.rook_magics[square as usize];
+        let index = Self::magic_function(key, magic, mask_bits as u8);
+        self.rook_attacks[square as usize][index as usize]
+    }
+
+    pub fn get_bishop_attacks(&self, square: u8, occupancy: u64) -> u64 {
+        let mask = self.bishop_masks[square as usize];
+        let mask_bits = count_bits(mask);
+        let key = mask & occupancy;
+        let magic = self.bishop_magics[square as usize];
+        let index = Self::magic_function(key, magic, mask_bits as u8);
+        self.bishop_attacks[square as usize][index as usize]
+    }
+
     pub fn generate_magic_numbers(&mut self) {
         let seed = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -513,3 +531,18 @@ impl MagicBitboards {
         self.rng.next_u64() & self.rng.next_u64() & self.rng.next_u64()
     }
 }
+
+/// Shared tables for tests; built on a large stack since the attack tables do not fit the
+/// default test thread stack.
+#[cfg(test)]
+pub fn get_test_magic_bitboards() -> &'static MagicBitboards {
+    static MAGIC_BITBOARDS: std::sync::OnceLock<Box<MagicBitboards>> = std::sync::OnceLock::new();
+    MAGIC_BITBOARDS.get_or_init(|| {
+        std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(|| Box::new(MagicBitboards::new()))
+            .expect("Failed to spawn magic bitboard thread")
+            .join()
+            .expect("Magic bitboard thread panicked")
+    })
+}
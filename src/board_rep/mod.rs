@@ -2,9 +2,13 @@ pub mod bit_operations;
 pub mod board;
 pub mod c_move;
 pub mod c_move_list;
+pub mod chess960;
 pub mod directions;
+pub mod epd;
 pub mod magic_bitboards;
 pub mod move_gen;
 pub mod perft;
 pub mod rng;
+pub mod san;
 pub mod squares;
+pub mod zobrist;
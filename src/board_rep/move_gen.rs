@@ -1,15 +1,17 @@
 use crate::board_rep::{
-    bit_operations::{count_bits, first_bit, first_bit_pop, set_bit},
-    board::{Board, EMPTY, EN_PASSANT_NONE, KING, KNIGHT, PAWN, PIECE_MASK, WHITE},
+    bit_operations::{count_bits, first_bit, first_bit_pop, is_bit_set, set_bit},
+    board::{
+        Board, CASTLING_RIGHTS, COLOR_MASK, EMPTY, EN_PASSANT_NONE, KING, KNIGHT, PAWN, PIECE_MASK,
+        WHITE,
+    },
     c_move::CMove,
     c_move_list::CMoveList,
     magic_bitboards::MagicBitboards,
     squares::{H2, H8},
 };
 
-const PINNER: u8 = 0;
-const PIN: u8 = 16;
-const EP_PIN: u8 = 32;
+pub const PINNER: u8 = 15;
+pub const PIN: u8 = 16;
 const DEFEND: u8 = 64;
 
 pub struct MoveInfo {
@@ -50,6 +52,9 @@ impl MoveInfo {
         magic_bitboards: &MagicBitboards,
         c_move: &CMove,
     ) -> bool {
+        if c_move.castling {
+            return true;
+        }
         let from_square = board.get_piece_at(c_move.from_square);
         if from_square & PIECE_MASK == KING {
             if is_square_attacked(board, magic_bitboards, c_move.to_square) {
@@ -57,22 +62,17 @@ impl MoveInfo {
             }
         } else if self.check_count > 1 {
             return false;
+        } else if from_square & PIECE_MASK == PAWN && c_move.to_square == board.en_passant {
+            return !is_king_attacked_after(board, magic_bitboards, c_move);
         } else if self.check_count == 1 {
-            if (c_move.to_square == board.en_passant
-                && board.en_passant != EN_PASSANT_NONE
-                && self.pin_defend_map[board.en_passant as usize] & DEFEND == DEFEND
-                && from_square & PIECE_MASK != PAWN)
-                || self.pin_defend_map[c_move.to_square as usize] & DEFEND != DEFEND
+            if self.pin_defend_map[c_move.to_square as usize] & DEFEND != DEFEND
                 || self.pin_defend_map[c_move.from_square as usize] & PIN == PIN
             {
                 return false;
             }
-        } else if (self.pin_defend_map[c_move.from_square as usize] & PIN == PIN
+        } else if self.pin_defend_map[c_move.from_square as usize] & PIN == PIN
             && (self.pin_defend_map[c_move.from_square as usize] & PINNER
-                != self.pin_defend_map[c_move.to_square as usize] & PINNER))
-            || (from_square & PIECE_MASK == PAWN
-                && c_move.to_square == board.en_passant
-                && self.pin_defend_map[c_move.to_square as usize] & EP_PIN == EP_PIN)
+                != self.pin_defend_map[c_move.to_square as usize] & PINNER)
         {
             return false;
         }
@@ -130,6 +130,7 @@ pub fn generate_moves(board: &Board, magic_bitboards: &MagicBitboards) -> MoveIn
             let square = first_bit_pop(&mut king);
             generate_king_moves(magic_bitboards, attackable_squares, square, &mut mi);
         }
+        generate_castling_moves(board, magic_bitboards, &mut mi);
     } else {
         let empty_squares = !(board.all_occupancy);
         let opponent_occupancy = board.w_occupancy;
@@ -178,6 +179,7 @@ pub fn generate_moves(board: &Board, magic_bitboards: &MagicBitboards) -> MoveIn
             let square = first_bit_pop(&mut king);
             generate_king_moves(magic_bitboards, attackable_squares, square, &mut mi);
         }
+        generate_castling_moves(board, magic_bitboards, &mut mi);
     }
     mi
 }
@@ -194,7 +196,7 @@ fn generate_w_pawn_moves(
     non_capture_pawn_moves &= empty_squares;
 
     let mut capture_pawn_moves = magic_bitboards.w_pawn_capture[square as usize];
-    capture_pawn_moves &= opponent_occupancy | set_bit(0, board.en_passant);
+    capture_pawn_moves &= opponent_occupancy | get_en_passant_bitboard(board);
 
     let mut pawn_moves = non_capture_pawn_moves | capture_pawn_moves;
     while pawn_moves != 0 {
@@ -227,7 +229,7 @@ fn generate_b_pawn_moves(
     non_capture_pawn_moves &= empty_squares;
 
     let mut capture_pawn_moves = magic_bitboards.b_pawn_capture[square as usize];
-    capture_pawn_moves &= opponent_occupancy | set_bit(0, board.en_passant);
+    capture_pawn_moves &= opponent_occupancy | get_en_passant_bitboard(board);
 
     let mut pawn_moves = non_capture_pawn_moves | capture_pawn_moves;
     while pawn_moves != 0 {
@@ -298,7 +300,7 @@ fn generate_rook_moves(
     rook_moves &= attackable_squares;
     while rook_moves != 0 {
         let target_square = first_bit_pop(&mut rook_moves);
-        mi.c_move_list.add_move(square, target_square as u8, 0);
+        mi.c_move_list.add_move(square, target_square, 0);
     }
 }
 
@@ -316,7 +318,54 @@ fn generate_king_moves(
     }
 }
 
-fn is_square_attacked(board: &Board, magic_bitboards: &MagicBitboards, square: u8) -> bool {
+fn generate_castling_moves(board: &Board, magic_bitboards: &MagicBitboards, mi: &mut MoveInfo) {
+    if mi.check_count != 0 {
+        return;
+    }
+
+    let (king_square, rights) = if board.stm == WHITE {
+        (first_bit(board.w_king), 0..2)
+    } else {
+        (first_bit(board.b_king), 2..4)
+    };
+    for i in rights {
+        if board.castling & CASTLING_RIGHTS[i] == 0 {
+            continue;
+        }
+        let rook_square = board.castling_rooks[i];
+        let c_move = CMove {
+            from_square: king_square,
+            to_square: rook_square,
+            promotion: 0,
+            castling: true,
+        };
+        let (king_to, rook_to) = c_move.get_castling_squares();
+
+        let low = king_square.min(rook_square).min(king_to).min(rook_to);
+        let high = king_square.max(rook_square).max(king_to).max(rook_to);
+        let blocked = (low..=high).any(|square| {
+            square != king_square
+                && square != rook_square
+                && is_bit_set(board.all_occupancy, square)
+        });
+        if blocked {
+            continue;
+        }
+
+        let attacked = (king_square.min(king_to)..=king_square.max(king_to)).any(|square| {
+            square != king_square
+                && square != king_to
+                && is_square_attacked(board, magic_bitboards, square)
+        });
+        if attacked || is_king_attacked_after(board, magic_bitboards, &c_move) {
+            continue;
+        }
+        mi.c_move_list
+            .add_castling_move(c_move.from_square, c_move.to_square);
+    }
+}
+
+pub fn is_square_attacked(board: &Board, magic_bitboards: &MagicBitboards, square: u8) -> bool {
     let stm_king: u64;
     let opponent_kings: u64;
     let opponent_rook_queens: u64;
@@ -344,23 +393,13 @@ fn is_square_attacked(board: &Board, magic_bitboards: &MagicBitboards, square: u
         return true;
     }
 
-    let rook_mask = magic_bitboards.rook_masks[square as usize];
-    let rook_mask_bits = count_bits(rook_mask);
-    let rook_key = rook_mask & (board.all_occupancy ^ stm_king);
-    let rook_magic = magic_bitboards.rook_magics[square as usize];
-    let rook_index = MagicBitboards::magic_function(rook_key, rook_magic, rook_mask_bits as u8);
-    let rook_attacks = magic_bitboards.rook_attacks[square as usize][rook_index as usize];
+    let occupancy = board.all_occupancy & !stm_king;
+    let rook_attacks = magic_bitboards.get_rook_attacks(square, occupancy);
     if rook_attacks & opponent_rook_queens != 0 {
         return true;
     }
 
-    let bishop_mask = magic_bitboards.bishop_masks[square as usize];
-    let bishop_mask_bits = count_bits(bishop_mask);
-    let bishop_key = bishop_mask & (board.all_occupancy ^ stm_king);
-    let bishop_magic = magic_bitboards.bishop_magics[square as usize];
-    let bishop_index =
-        MagicBitboards::magic_function(bishop_key, bishop_magic, bishop_mask_bits as u8);
-    let bishop_attacks = magic_bitboards.bishop_attacks[square as usize][bishop_index as usize];
+    let bishop_attacks = magic_bitboards.get_bishop_attacks(square, occupancy);
     if bishop_attacks & opponent_bishop_queens != 0 {
         return true;
     }
@@ -381,19 +420,32 @@ fn is_square_attacked(board: &Board, magic_bitboards: &MagicBitboards, square: u
     false
 }
 
+pub fn is_king_attacked_after(
+    board: &Board,
+    magic_bitboards: &MagicBitboards,
+    c_move: &CMove,
+) -> bool {
+    let mut new_board = *board;
+    new_board.make_move(*c_move);
+    new_board.stm ^= COLOR_MASK;
+    let king = if new_board.stm == WHITE {
+        new_board.w_king
+    } else {
+        new_board.b_king
+    };
+    is_square_attacked(&new_board, magic_bitboards, first_bit(king))
+}
+
 fn generate_move_info(board: &Board, magic_bitboards: &MagicBitboards) -> MoveInfo {
-    let stm_king_square: u64;
-    let stm_kings: u64;
+    let stm_king_square: u8;
     let stm_occupancy: u64;
     let mut pawn_attacks: u64;
-    let ep_pin_squares = get_ep_pin_squares(board);
     let opponent_rook_queens: u64;
     let opponent_bishop_queens: u64;
     let opponent_knights: u64;
     let opponent_pawns: u64;
     if board.stm == WHITE {
-        stm_king_square = first_bit(board.w_king) as u64;
-        stm_kings = board.w_king;
+        stm_king_square = first_bit(board.w_king);
         stm_occupancy = board.w_occupancy;
         pawn_attacks = magic_bitboards.w_pawn_capture[stm_king_square as usize];
         opponent_rook_queens = board.b_rooks | board.b_queens;
@@ -401,8 +453,7 @@ fn generate_move_info(board: &Board, magic_bitboards: &MagicBitboards) -> MoveIn
         opponent_knights = board.b_knights;
         opponent_pawns = board.b_pawns;
     } else {
-        stm_king_square = first_bit(board.b_king) as u64;
-        stm_kings = board.b_king;
+        stm_king_square = first_bit(board.b_king);
         stm_occupancy = board.b_occupancy;
         pawn_attacks = magic_bitboards.b_pawn_capture[stm_king_square as usize];
         opponent_rook_queens = board.w_rooks | board.w_queens;
@@ -421,12 +472,6 @@ fn generate_move_info(board: &Board, magic_bitboards: &MagicBitboards) -> MoveIn
             mi.pin_defend_map[target_square as usize] |= DEFEND;
         }
     }
-    let ep_pawn_index = board.get_ep_pawn_index();
-    if ep_pawn_index != EN_PASSANT_NONE
-        && mi.pin_defend_map[ep_pawn_index as usize] & DEFEND == DEFEND
-    {
-        mi.pin_defend_map[ep_pawn_index as usize] |= DEFEND;
-    }
 
     let mut knight_attacks = magic_bitboards.knight_attacks[stm_king_square as usize];
     knight_attacks &= opponent_knights;
@@ -438,118 +483,84 @@ fn generate_move_info(board: &Board, magic_bitboards: &MagicBitboards) -> MoveIn
         }
     }
 
-    let rook_mask = magic_bitboards.rook_masks[stm_king_square as usize];
-    let rook_mask_bits = count_bits(rook_mask);
-    let rook_key = rook_mask & (board.all_occupancy ^ stm_kings);
-    let rook_magic = magic_bitboards.rook_magics[stm_king_square as usize];
-    let rook_index = MagicBitboards::magic_function(rook_key, rook_magic, rook_mask_bits as u8);
-    let rook_attacks = magic_bitboards.rook_attacks[stm_king_square as usize][rook_index as usize];
+    let occupancy = board.all_occupancy;
+    let rook_attacks = magic_bitboards.get_rook_attacks(stm_king_square, occupancy);
     let mut rook_attackers = rook_attacks & opponent_rook_queens;
-    let ep_possible_pin = rook_attacks & ep_pin_squares;
     let mut possible_pins = rook_attacks & stm_occupancy;
     if rook_attackers != 0 {
         mi.check_count += count_bits(rook_attackers) as u8;
         while rook_attackers != 0 {
             let target_square = first_bit_pop(&mut rook_attackers);
-            mi.pin_defend_map[target_square as usize] |= DEFEND;
+            let mut defend_squares = magic_bitboards.get_rook_attacks(target_square, occupancy)
+                & rook_attacks
+                | set_bit(0, target_square);
+            while defend_squares != 0 {
+                let defend_square = first_bit_pop(&mut defend_squares);
+                mi.pin_defend_map[defend_square as usize] |= DEFEND;
+            }
         }
     }
-    if ep_possible_pin != 0 {
-        let rook_key = rook_mask & (board.all_occupancy ^ (stm_kings | ep_possible_pin));
-        let rook_index = MagicBitboards::magic_function(rook_key, rook_magic, rook_mask_bits as u8);
-        let rook_attacks = magic_bitboards.rook_attacks[stm_king_square as usize][rook_index as usize];
-        let mut pin_squares = rook_attacks & opponent_rook_queens;
-        if pin_squares != 0 {
-            mi.pin_defend_map[board.en_passant as usize] |= EP_PIN;
+    while possible_pins != 0 {
+        let possible_pin_square = first_bit_pop(&mut possible_pins);
+        let pin_occupancy = occupancy ^ set_bit(0, possible_pin_square);
+        let pin_attacks = magic_bitboards.get_rook_attacks(stm_king_square, pin_occupancy);
+        let mut pinners = pin_attacks & opponent_rook_queens & !rook_attacks;
+        while pinners != 0 {
+            let pinner_square = first_bit_pop(&mut pinners);
+            let mut pin_squares = magic_bitboards.get_rook_attacks(pinner_square, pin_occupancy)
+                & pin_attacks
+                | set_bit(0, pinner_square);
             while pin_squares != 0 {
                 let pin_square = first_bit_pop(&mut pin_squares);
                 mi.pin_defend_map[pin_square as usize] |= pinner;
             }
+            mi.pin_defend_map[possible_pin_square as usize] |= PIN;
             pinner += 1;
         }
     }
-    if possible_pins != 0 {
-        while possible_pins != 0 {
-            let possible_pin = first_bit_pop(&mut possible_pins) as u64;
-            let rook_key = rook_mask & (board.all_occupancy ^ (stm_kings | possible_pin));
-            let rook_index =
-                MagicBitboards::magic_function(rook_key, rook_magic, rook_mask_bits as u8);
-            let rook_attacks = magic_bitboards.rook_attacks[stm_king_square as usize][rook_index as usize];
-            let mut pin_squares = rook_attacks & opponent_rook_queens;
-            if pin_squares != 0 {
-                while pin_squares != 0 {
-                    let pin_square = first_bit_pop(&mut pin_squares);
-                    mi.pin_defend_map[pin_square as usize] |= pinner;
-                }
-                pinner += 1;
-            }
-        }
-    }
 
-    let bishop_mask = magic_bitboards.bishop_masks[stm_king_square as usize];
-    let bishop_mask_bits = count_bits(bishop_mask);
-    let bishop_key = bishop_mask & (board.all_occupancy ^ stm_kings);
-    let bishop_magic = magic_bitboards.bishop_magics[stm_king_square as usize];
-    let bishop_index =
-        MagicBitboards::magic_function(bishop_key, bishop_magic, bishop_mask_bits as u8);
-    let bishop_attacks = magic_bitboards.bishop_attacks[stm_king_square as usize][bishop_index as usize];
+    let bishop_attacks = magic_bitboards.get_bishop_attacks(stm_king_square, occupancy);
     let mut bishop_attackers = bishop_attacks & opponent_bishop_queens;
     let mut possible_pins = bishop_attacks & stm_occupancy;
     if bishop_attackers != 0 {
         mi.check_count += count_bits(bishop_attackers) as u8;
         while bishop_attackers != 0 {
             let target_square = first_bit_pop(&mut bishop_attackers);
-            mi.pin_defend_map[target_square as usize] |= DEFEND;
+            let mut defend_squares = magic_bitboards.get_bishop_attacks(target_square, occupancy)
+                & bishop_attacks
+                | set_bit(0, target_square);
+            while defend_squares != 0 {
+                let defend_square = first_bit_pop(&mut defend_squares);
+                mi.pin_defend_map[defend_square as usize] |= DEFEND;
+            }
         }
     }
-    if possible_pins != 0 {
-        while possible_pins != 0 {
-            let possible_pin_square = first_bit_pop(&mut possible_pins);
-            let possible_pin = set_bit(0, possible_pin_square);
-            let bishop_key = bishop_mask & (board.all_occupancy ^ (stm_kings | possible_pin));
-            let bishop_index =
-                MagicBitboards::magic_function(bishop_key, bishop_magic, bishop_mask_bits as u8);
-            let mut bishop_attacks =
-                magic_bitboards.bishop_attacks[stm_king_square as usize][bishop_index as usize];
-            let is_pin = (bishop_attacks & opponent_bishop_queens) != 0;
-            if is_pin {
-                while bishop_attacks != 0 {
-                    let pin_square = first_bit_pop(&mut bishop_attacks);
-                    mi.pin_defend_map[pin_square as usize] |= pinner;
-                }
-                pinner += 1;
+    while possible_pins != 0 {
+        let possible_pin_square = first_bit_pop(&mut possible_pins);
+        let pin_occupancy = occupancy ^ set_bit(0, possible_pin_square);
+        let pin_attacks = magic_bitboards.get_bishop_attacks(stm_king_square, pin_occupancy);
+        let mut pinners = pin_attacks & opponent_bishop_queens & !bishop_attacks;
+        while pinners != 0 {
+            let pinner_square = first_bit_pop(&mut pinners);
+            let mut pin_squares = magic_bitboards.get_bishop_attacks(pinner_square, pin_occupancy)
+                & pin_attacks
+                | set_bit(0, pinner_square);
+            while pin_squares != 0 {
+                let pin_square = first_bit_pop(&mut pin_squares);
+                mi.pin_defend_map[pin_square as usize] |= pinner;
             }
+            mi.pin_defend_map[possible_pin_square as usize] |= PIN;
+            pinner += 1;
         }
     }
 
     mi
 }
 
-fn get_ep_pin_squares(board: &Board) -> u64 {
+fn get_en_passant_bitboard(board: &Board) -> u64 {
     if board.en_passant == EN_PASSANT_NONE {
-        return 0;
-    }
-
-    let file = (board.en_passant % 8) as i8;
-    let rank = (board.en_passant / 8) as i8 + if board.stm == WHITE { -1 } else { 1 };
-
-    let mut ep_pin_squares = 0;
-    let ep_pawn_file = file;
-    let left_pawn_file = file - 1;
-    let right_pawn_file = file + 1;
-
-    ep_pin_squares = set_bit(ep_pin_squares, (rank * 8 + ep_pawn_file) as u8);
-    if (0..8).contains(&left_pawn_file) {
-        ep_pin_squares = set_bit(ep_pin_squares, (rank * 8 + left_pawn_file) as u8);
-    }
-    if (0..8).contains(&right_pawn_file) {
-        ep_pin_squares = set_bit(ep_pin_squares, (rank * 8 + right_pawn_file) as u8);
-    }
-
-    ep_pin_squares &= board.w_pawns | board.b_pawns;
-    if count_bits(ep_pin_squares) == 2 {
-        ep_pin_squares
-    } else {
         0
+    } else {
+        set_bit(0, board.en_passant)
     }
 }
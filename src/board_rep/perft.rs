@@ -2,6 +2,39 @@ use std::time::Instant;
 
 use crate::board_rep::{board::Board, magic_bitboards::MagicBitboards, move_gen::generate_moves};
 
+pub const PERFT_960_POSITIONS: [(&str, usize, usize); 6] = [
+    (
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        4,
+        326672,
+    ),
+    (
+        "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        4,
+        667366,
+    ),
+    (
+        "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        4,
+        273318,
+    ),
+    (
+        "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+        4,
+        1171749,
+    ),
+    (
+        "qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9",
+        4,
+        824055,
+    ),
+    (
+        "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9",
+        4,
+        287739,
+    ),
+];
+
 pub fn print_perft(
     board: &Board,
     magic_bitboards: &MagicBitboards,
@@ -24,7 +57,7 @@ pub fn print_perft(
         let mut new_board = *board;
         new_board.make_move(c_move);
         let nodes = perft(&new_board, magic_bitboards, depth - 1, perft_full);
-        println!("{c_move}: {nodes}");
+        println!("{}: {nodes}", c_move.uci(board.chess960));
         total_nodes += nodes;
     }
     let elapsed = now.elapsed();
@@ -58,3 +91,131 @@ fn perft(board: &Board, magic_bitboards: &MagicBitboards, depth: usize, perft_fu
     }
     total_nodes
 }
+
+pub fn print_perft_960(magic_bitboards: &MagicBitboards) {
+    let now = Instant::now();
+    let mut failed = 0;
+    for (fen, depth, expected) in PERFT_960_POSITIONS {
+        let mut board = Board::new();
+        board.set_from_fen(fen, magic_bitboards);
+        let nodes = perft(&board, magic_bitboards, depth, false);
+        let status = if nodes == expected { "ok" } else { "FAILED" };
+        if nodes != expected {
+            failed += 1;
+        }
+        println!("{fen} depth {depth}: {nodes} (expected {expected}) {status}");
+    }
+    println!();
+    println!(
+        "{}/{} positions passed in {:.4} seconds",
+        PERFT_960_POSITIONS.len() - failed,
+        PERFT_960_POSITIONS.len(),
+        now.elapsed().as_secs_f64()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const KIWIPETE_FEN: &str =
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3_FEN: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4_FEN: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_5_FEN: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6_FEN: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn assert_perft(fen: &str, expected: &[usize]) {
+        let magic_bitboards = get_test_magic_bitboards();
+        let mut board = Board::new();
+        board.set_from_fen(fen, magic_bitboards);
+        for (depth, expected) in (1..).zip(expected) {
+            assert_eq!(
+                perft(&board, magic_bitboards, depth, false),
+                *expected,
+                "{fen} depth {depth}"
+            );
+            if depth <= 3 {
+                assert_eq!(
+                    perft(&board, magic_bitboards, depth, true),
+                    *expected,
+                    "{fen} full depth {depth}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn perft_start_position() {
+        assert_perft(START_FEN, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        assert_perft(KIWIPETE_FEN, &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn perft_position_3() {
+        assert_perft(POSITION_3_FEN, &[14, 191, 2812, 43238]);
+    }
+
+    #[test]
+    fn perft_position_4() {
+        assert_perft(POSITION_4_FEN, &[6, 264, 9467]);
+    }
+
+    #[test]
+    fn perft_position_5() {
+        assert_perft(POSITION_5_FEN, &[44, 1486, 62379]);
+    }
+
+    #[test]
+    fn perft_position_6() {
+        assert_perft(POSITION_6_FEN, &[46, 2079, 89890]);
+    }
+
+    #[test]
+    fn perft_960() {
+        let shallow = [
+            [21, 528, 12189],
+            [21, 807, 18002],
+            [20, 479, 10471],
+            [28, 1120, 31058],
+            [29, 899, 26578],
+            [29, 502, 14569],
+        ];
+        for ((fen, _, _), expected) in PERFT_960_POSITIONS.iter().zip(shallow) {
+            assert_perft(fen, &expected);
+        }
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds"]
+    fn perft_960_deep() {
+        let magic_bitboards = get_test_magic_bitboards();
+        for (fen, depth, expected) in PERFT_960_POSITIONS {
+            let mut board = Board::new();
+            board.set_from_fen(fen, magic_bitboards);
+            assert_eq!(
+                perft(&board, magic_bitboards, depth, false),
+                expected,
+                "{fen} depth {depth}"
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds"]
+    fn perft_deep() {
+        assert_perft(START_FEN, &[20, 400, 8902, 197281, 4865609]);
+        assert_perft(KIWIPETE_FEN, &[48, 2039, 97862, 4085603]);
+        assert_perft(POSITION_3_FEN, &[14, 191, 2812, 43238, 674624]);
+        assert_perft(POSITION_4_FEN, &[6, 264, 9467, 422333]);
+        assert_perft(POSITION_5_FEN, &[44, 1486, 62379, 2103487]);
+        assert_perft(POSITION_6_FEN, &[46, 2079, 89890, 3894594]);
+    }
+}
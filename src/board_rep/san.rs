@@ -0,0 +1,303 @@
+use crate::board_rep::{
+    board::{BISHOP, Board, EMPTY, KING, KNIGHT, PAWN, PIECE_MASK, QUEEN, ROOK},
+    c_move::CMove,
+    magic_bitboards::MagicBitboards,
+    move_gen::generate_moves,
+    squares::SQUARE_NAMES,
+};
+
+pub fn get_legal_moves(board: &Board, magic_bitboards: &MagicBitboards) -> Vec<CMove> {
+    let mi = generate_moves(board, magic_bitboards);
+    mi.c_move_list.moves[..mi.c_move_list.count]
+        .iter()
+        .filter(|c_move| mi.is_move_legal(board, magic_bitboards, c_move))
+        .copied()
+        .collect()
+}
+
+pub fn get_san(board: &Board, magic_bitboards: &MagicBitboards, c_move: CMove) -> String {
+    let mut san = String::new();
+    let piece = board.get_piece_at(c_move.from_square) & PIECE_MASK;
+    if c_move.castling {
+        san.push_str(if c_move.is_kingside_castling() {
+            "O-O"
+        } else {
+            "O-O-O"
+        });
+    } else {
+        let is_capture = board.get_piece_at(c_move.to_square) != EMPTY
+            || (piece == PAWN && c_move.to_square == board.en_passant);
+        if piece == PAWN {
+            if is_capture {
+                san.push(SQUARE_NAMES[c_move.from_square as usize].as_bytes()[0] as char);
+            }
+        } else {
+            san.push(get_piece_char(piece));
+            let others: Vec<CMove> = get_legal_moves(board, magic_bitboards)
+                .into_iter()
+                .filter(|other| {
+                    !other.castling
+                        && other.to_square == c_move.to_square
+                        && other.from_square != c_move.from_square
+                        && board.get_piece_at(other.from_square) & PIECE_MASK == piece
+                })
+                .collect();
+            if !others.is_empty() {
+                let from_name = SQUARE_NAMES[c_move.from_square as usize];
+                let same_file = others
+                    .iter()
+                    .any(|other| other.from_square % 8 == c_move.from_square % 8);
+                let same_rank = others
+                    .iter()
+                    .any(|other| other.from_square / 8 == c_move.from_square / 8);
+                if !same_file {
+                    san.push_str(&from_name[..1]);
+                } else if !same_rank {
+                    san.push_str(&from_name[1..]);
+                } else {
+                    san.push_str(from_name);
+                }
+            }
+        }
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(SQUARE_NAMES[c_move.to_square as usize]);
+        if c_move.promotion != 0 {
+            san.push('=');
+            san.push(get_piece_char(c_move.promotion));
+        }
+    }
+
+    let mut new_board = *board;
+    new_board.make_move(c_move);
+    if generate_moves(&new_board, magic_bitboards).check_count > 0 {
+        if get_legal_moves(&new_board, magic_bitboards).is_empty() {
+            san.push('#');
+        } else {
+            san.push('+');
+        }
+    }
+    san
+}
+
+pub fn parse_san(board: &Board, magic_bitboards: &MagicBitboards, san: &str) -> Option<CMove> {
+    let legal_moves = get_legal_moves(board, magic_bitboards);
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+    if let Some(c_move) = legal_moves
+        .iter()
+        .find(|c_move| c_move.uci(board.chess960).to_string() == san)
+    {
+        return Some(*c_move);
+    }
+
+    let castling = san.replace('0', "O");
+    if castling == "O-O" || castling == "O-O-O" {
+        return legal_moves.into_iter().find(|c_move| {
+            c_move.castling && c_move.is_kingside_castling() == (castling == "O-O")
+        });
+    }
+
+    let mut chars: Vec<char> = san.chars().filter(|c| *c != 'x' && *c != '=').collect();
+    let mut promotion = 0;
+    if let Some(last) = chars.last()
+        && let Some(piece) = get_piece_from_char(last.to_ascii_uppercase())
+        && chars.len() > 2
+    {
+        promotion = piece;
+        chars.pop();
+    }
+    let piece = match chars.first().and_then(|c| get_piece_from_char(*c)) {
+        Some(piece) => {
+            chars.remove(0);
+            piece
+        }
+        None => PAWN,
+    };
+    if chars.len() < 2 {
+        return None;
+    }
+    let to_name: String = chars[chars.len() - 2..].iter().collect();
+    let to_square = SQUARE_NAMES.iter().position(|name| *name == to_name)? as u8;
+    let hint = &chars[..chars.len() - 2];
+
+    let mut matches = legal_moves.into_iter().filter(|c_move| {
+        let from_name = SQUARE_NAMES[c_move.from_square as usize].as_bytes();
+        !c_move.castling
+            && c_move.to_square == to_square
+            && c_move.promotion == promotion
+            && board.get_piece_at(c_move.from_square) & PIECE_MASK == piece
+            && hint
+                .iter()
+                .all(|c| *c as u8 == from_name[0] || *c as u8 == from_name[1])
+    });
+    let c_move = matches.next()?;
+    if matches.next().is_some() {
+        return None;
+    }
+    Some(c_move)
+}
+
+fn get_piece_char(piece: u8) -> char {
+    match piece {
+        KNIGHT => 'N',
+        BISHOP => 'B',
+        ROOK => 'R',
+        QUEEN => 'Q',
+        KING => 'K',
+        _ => 'P',
+    }
+}
+
+fn get_piece_from_char(c: char) -> Option<u8> {
+    match c {
+        'N' => Some(KNIGHT),
+        'B' => Some(BISHOP),
+        'R' => Some(ROOK),
+        'Q' => Some(QUEEN),
+        'K' => Some(KING),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::{magic_bitboards::get_test_magic_bitboards, perft::PERFT_960_POSITIONS};
+
+    const ROUND_TRIP_FENS: [&str; 6] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    ];
+
+    fn get_board(fen: &str, chess960: bool) -> Board {
+        let mut board = Board::new();
+        board.chess960 = chess960;
+        board.set_from_fen(fen, get_test_magic_bitboards());
+        board
+    }
+
+    fn assert_round_trip(board: &Board) {
+        let magic_bitboards = get_test_magic_bitboards();
+        for c_move in get_legal_moves(board, magic_bitboards) {
+            let san = get_san(board, magic_bitboards, c_move);
+            let parsed = parse_san(board, magic_bitboards, &san);
+            assert!(
+                parsed == Some(c_move),
+                "{san} {}",
+                c_move.uci(board.chess960)
+            );
+        }
+    }
+
+    fn get_uci(board: &Board, san: &str) -> Option<String> {
+        parse_san(board, get_test_magic_bitboards(), san)
+            .map(|c_move| c_move.uci(board.chess960).to_string())
+    }
+
+    #[test]
+    fn san_round_trips_over_perft_positions() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let positions = ROUND_TRIP_FENS
+            .iter()
+            .map(|fen| (*fen, false))
+            .chain(PERFT_960_POSITIONS.iter().map(|(fen, _, _)| (*fen, true)));
+        for (fen, chess960) in positions {
+            let board = get_board(fen, chess960);
+            assert_round_trip(&board);
+            for c_move in get_legal_moves(&board, magic_bitboards) {
+                let mut child = board;
+                child.make_move(c_move);
+                assert_round_trip(&child);
+            }
+        }
+    }
+
+    #[test]
+    fn ambiguous_moves_are_disambiguated() {
+        let magic_bitboards = get_test_magic_bitboards();
+        for (fen, expected) in [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/5N2/PPP1PPPP/RNBQKB1R w KQkq - 0 1",
+                [("b1d2", "Nbd2"), ("f3d2", "Nfd2")].as_slice(),
+            ),
+            (
+                "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1",
+                &[("a1a3", "R1a3"), ("a5a3", "R5a3")],
+            ),
+            (
+                "8/8/8/1k6/4Q2Q/8/8/K6Q w - - 0 1",
+                &[("h4e1", "Qh4e1"), ("e4e1", "Qee1"), ("h1e1", "Q1e1")],
+            ),
+        ] {
+            let board = get_board(fen, false);
+            for (uci, san) in expected {
+                let c_move = get_legal_moves(&board, magic_bitboards)
+                    .into_iter()
+                    .find(|c_move| c_move.uci(false).to_string() == *uci)
+                    .unwrap();
+                assert_eq!(get_san(&board, magic_bitboards, c_move), *san);
+                assert_eq!(get_uci(&board, san).as_deref(), Some(*uci));
+            }
+        }
+    }
+
+    #[test]
+    fn ambiguous_input_is_rejected() {
+        let board = get_board(
+            "rnbqkbnr/pppppppp/8/8/8/5N2/PPP1PPPP/RNBQKB1R w KQkq - 0 1",
+            false,
+        );
+        assert_eq!(get_uci(&board, "Nd2"), None);
+        let board = get_board("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", false);
+        assert_eq!(get_uci(&board, "Ra3"), None);
+        assert_eq!(get_uci(&board, "Raa3"), None);
+        let board = get_board("8/8/8/1k6/4Q2Q/8/8/K6Q w - - 0 1", false);
+        assert_eq!(get_uci(&board, "Qe1"), None);
+        assert_eq!(get_uci(&board, "Qhe1"), None);
+        assert_eq!(get_uci(&board, "Q4e1"), None);
+    }
+
+    #[test]
+    fn promotions_parse_with_and_without_equals() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let board = get_board("1n6/P7/7k/8/8/8/8/K7 w - - 0 1", false);
+        for (san, uci) in [
+            ("a8=Q", "a7a8q"),
+            ("a8Q", "a7a8q"),
+            ("a8=N", "a7a8n"),
+            ("a8N", "a7a8n"),
+            ("axb8=R", "a7b8r"),
+            ("axb8B", "a7b8b"),
+        ] {
+            assert_eq!(get_uci(&board, san).as_deref(), Some(uci), "{san}");
+        }
+        let c_move = parse_san(&board, magic_bitboards, "axb8=Q").unwrap();
+        assert_eq!(get_san(&board, magic_bitboards, c_move), "axb8=Q");
+    }
+
+    #[test]
+    fn castling_parses_in_chess960() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let board = get_board("1k6/8/8/8/8/8/8/RK4R1 w AG - 0 1", true);
+        for (san, king_to, kingside) in [
+            ("O-O", 62, true),
+            ("0-0", 62, true),
+            ("O-O-O", 58, false),
+            ("0-0-0", 58, false),
+        ] {
+            let c_move = parse_san(&board, magic_bitboards, san).unwrap();
+            assert!(c_move.castling, "{san}");
+            assert_eq!(c_move.is_kingside_castling(), kingside, "{san}");
+            let mut child = board;
+            child.make_move(c_move);
+            assert_eq!(child.get_piece_at(king_to) & PIECE_MASK, KING, "{san}");
+            let expected = if kingside { "O-O" } else { "O-O-O" };
+            assert_eq!(get_san(&board, magic_bitboards, c_move), expected);
+        }
+    }
+}
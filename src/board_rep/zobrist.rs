@@ -0,0 +1,62 @@
+use crate::board_rep::board::{BLACK, PIECE_MASK};
+
+pub struct Zobrist {
+    pub pieces: [[u64; 64]; 12],
+    pub castling: [u64; 16],
+    pub en_passant: [u64; 8],
+    pub side: u64,
+}
+
+pub const ZOBRIST: Zobrist = Zobrist::new();
+
+impl Zobrist {
+    const fn new() -> Self {
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut pieces = [[0; 64]; 12];
+        let mut castling = [0; 16];
+        let mut en_passant = [0; 8];
+
+        let mut piece = 0;
+        while piece < 12 {
+            let mut square = 0;
+            while square < 64 {
+                state = next_key(state);
+                pieces[piece][square] = state;
+                square += 1;
+            }
+            piece += 1;
+        }
+        let mut i = 0;
+        while i < 16 {
+            state = next_key(state);
+            castling[i] = state;
+            i += 1;
+        }
+        let mut i = 0;
+        while i < 8 {
+            state = next_key(state);
+            en_passant[i] = state;
+            i += 1;
+        }
+        state = next_key(state);
+        Zobrist {
+            pieces,
+            castling,
+            en_passant,
+            side: state,
+        }
+    }
+
+    #[inline(always)]
+    pub fn piece_key(&self, piece: u8, square: u8) -> u64 {
+        let index = (piece & PIECE_MASK) as usize - 1 + if piece & BLACK != 0 { 6 } else { 0 };
+        self.pieces[index][square as usize]
+    }
+}
+
+const fn next_key(mut state: u64) -> u64 {
+    state ^= state >> 12;
+    state ^= state << 25;
+    state ^= state >> 27;
+    state.wrapping_mul(2685821657736338717)
+}
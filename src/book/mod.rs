@@ -0,0 +1,3 @@
+pub mod keys;
+pub mod pgn;
+pub mod polyglot;
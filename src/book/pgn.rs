@@ -0,0 +1,157 @@
+use crate::board_rep::{
+    board::{Board, WHITE},
+    c_move::CMove,
+    magic_bitboards::MagicBitboards,
+    san::parse_san,
+};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const PGN_LINE_LENGTH: usize = 80;
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+#[derive(Default)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_start_board(&self, magic_bitboards: &MagicBitboards) -> Board {
+        let mut board = Board::new();
+        board.set_from_fen(self.get_tag("FEN").unwrap_or(START_FEN), magic_bitboards);
+        board
+    }
+
+    pub fn get_result(&self) -> &str {
+        if self.result.is_empty() {
+            self.get_tag("Result").unwrap_or("*")
+        } else {
+            &self.result
+        }
+    }
+
+    pub fn replay(
+        &self,
+        magic_bitboards: &MagicBitboards,
+    ) -> (Vec<(Board, CMove)>, Option<String>) {
+        let mut board = self.get_start_board(magic_bitboards);
+        let mut positions = Vec::with_capacity(self.moves.len());
+        for san in &self.moves {
+            let Some(c_move) = parse_san(&board, magic_bitboards, san) else {
+                return (positions, Some(san.clone()));
+            };
+            positions.push((board, c_move));
+            board.make_move(c_move);
+        }
+        (positions, None)
+    }
+
+    pub fn format(&self, magic_bitboards: &MagicBitboards) -> String {
+        let mut pgn = String::new();
+        for (name, value) in &self.tags {
+            pgn.push_str(&format!("[{name} \"{}\"]\n", value.replace('"', "\\\"")));
+        }
+        pgn.push('\n');
+
+        let board = self.get_start_board(magic_bitboards);
+        let mut fullmove_number = board.fullmove_number;
+        let mut white_to_move = board.stm == WHITE;
+        let mut tokens = Vec::with_capacity(self.moves.len() * 3 / 2 + 1);
+        for (i, san) in self.moves.iter().enumerate() {
+            if white_to_move {
+                tokens.push(format!("{fullmove_number}."));
+            } else if i == 0 {
+                tokens.push(format!("{fullmove_number}..."));
+            }
+            tokens.push(san.clone());
+            if !white_to_move {
+                fullmove_number += 1;
+            }
+            white_to_move = !white_to_move;
+        }
+        tokens.push(self.get_result().to_string());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + token.len() + 1 > PGN_LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push_str("\n\n");
+        pgn
+    }
+}
+
+pub fn parse_pgn(text: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    let mut has_moves = false;
+    let mut chars = text.chars().peekable();
+    let mut variation_depth = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if variation_depth == 0 => {
+                if has_moves {
+                    games.push(std::mem::take(&mut game));
+                    has_moves = false;
+                }
+                let tag: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                if let Some((name, value)) = tag.split_once(' ') {
+                    let value = value.trim().trim_matches('"').replace("\\\"", "\"");
+                    game.tags.push((name.trim().to_string(), value));
+                }
+            }
+            '{' => {
+                chars.by_ref().find(|c| *c == '}');
+            }
+            ';' => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth -= 1,
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = c.to_string();
+                while let Some(next) = chars.peek() {
+                    if next.is_whitespace() || "{}();[".contains(*next) {
+                        break;
+                    }
+                    token.push(chars.next().unwrap());
+                }
+                if variation_depth > 0 {
+                    continue;
+                }
+                has_moves = true;
+                if RESULTS.contains(&token.as_str()) {
+                    game.result = token;
+                    games.push(std::mem::take(&mut game));
+                    has_moves = false;
+                    continue;
+                }
+                let san = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                if !san.is_empty() && !san.starts_with('$') {
+                    game.moves.push(san.to_string());
+                }
+            }
+        }
+    }
+    if has_moves || !game.tags.is_empty() {
+        games.push(game);
+    }
+    games
+}
@@ -0,0 +1,308 @@
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    board_rep::{
+        board::{Board, CASTLING_RIGHTS, EMPTY, EN_PASSANT_NONE, PAWN, PIECE_MASK, WHITE},
+        c_move::CMove,
+        magic_bitboards::MagicBitboards,
+        rng::Rng,
+    },
+    book::keys::POLYGLOT_RANDOM,
+    engine::search::get_root_moves,
+};
+
+pub const ENTRY_SIZE: usize = 16;
+
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BookEntry {
+    pub key: u64,
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        BookEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            raw_move: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|error| format!("{path}: {error}"))?;
+        if bytes.len() % ENTRY_SIZE != 0 {
+            return Err(format!("{path} is not a valid Polyglot book"));
+        }
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(BookEntry::from_bytes)
+            .collect();
+        if !entries.is_sorted_by_key(|entry| entry.key) {
+            entries.sort_by_key(|entry| entry.key);
+        }
+        Ok(Book { entries })
+    }
+
+    pub fn get_entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get_entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+
+    pub fn get_moves(&self, board: &Board, magic_bitboards: &MagicBitboards) -> Vec<(CMove, u16)> {
+        self.get_entries(get_polyglot_key(board))
+            .iter()
+            .filter_map(|entry| {
+                decode_move(board, magic_bitboards, entry.raw_move)
+                    .map(|c_move| (c_move, entry.weight))
+            })
+            .collect()
+    }
+
+    pub fn get_best_move(&self, board: &Board, magic_bitboards: &MagicBitboards) -> Option<CMove> {
+        self.get_moves(board, magic_bitboards)
+            .into_iter()
+            .filter(|(_, weight)| *weight > 0)
+            .max_by_key(|(_, weight)| *weight)
+            .map(|(c_move, _)| c_move)
+    }
+
+    pub fn get_weighted_move(
+        &self,
+        board: &Board,
+        magic_bitboards: &MagicBitboards,
+        rng: &mut Rng,
+    ) -> Option<CMove> {
+        let moves = self.get_moves(board, magic_bitboards);
+        let total: u64 = moves.iter().map(|(_, weight)| *weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = rng.next_u64() % total;
+        for (c_move, weight) in moves {
+            if pick < weight as u64 {
+                return Some(c_move);
+            }
+            pick -= weight as u64;
+        }
+        None
+    }
+
+    /// Picks the heaviest move if `best` is set, otherwise a move chosen at random by weight.
+    pub fn get_book_move(
+        &self,
+        board: &Board,
+        magic_bitboards: &MagicBitboards,
+        best: bool,
+    ) -> Option<CMove> {
+        if best {
+            return self.get_best_move(board, magic_bitboards);
+        }
+        let mut rng = Rng::new();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64);
+        rng.seed(nanos | 1);
+        self.get_weighted_move(board, magic_bitboards, &mut rng)
+    }
+}
+
+pub fn get_polyglot_key(board: &Board) -> u64 {
+    let mut key = 0;
+    for square in 0..64 {
+        let piece = board.get_piece_at(square);
+        if piece != EMPTY {
+            let kind = 2 * ((piece & PIECE_MASK) as usize - 1) + (piece & WHITE != 0) as usize;
+            key ^= POLYGLOT_RANDOM[64 * kind + get_polyglot_square(square) as usize];
+        }
+    }
+    for (i, right) in CASTLING_RIGHTS.iter().enumerate() {
+        if board.castling & right != 0 {
+            key ^= POLYGLOT_RANDOM[CASTLING_OFFSET + i];
+        }
+    }
+    if has_en_passant_capture(board) {
+        key ^= POLYGLOT_RANDOM[EN_PASSANT_OFFSET + board.en_passant as usize % 8];
+    }
+    if board.stm == WHITE {
+        key ^= POLYGLOT_RANDOM[TURN_OFFSET];
+    }
+    key
+}
+
+fn has_en_passant_capture(board: &Board) -> bool {
+    if board.en_passant == EN_PASSANT_NONE {
+        return false;
+    }
+    let pawn_square = board.get_ep_pawn_index();
+    let file = pawn_square % 8;
+    let own_pawn = board.stm | PAWN;
+    (file > 0 && board.get_piece_at(pawn_square - 1) == own_pawn)
+        || (file < 7 && board.get_piece_at(pawn_square + 1) == own_pawn)
+}
+
+fn get_polyglot_square(square: u8) -> u8 {
+    square ^ 56
+}
+
+pub fn decode_move(
+    board: &Board,
+    magic_bitboards: &MagicBitboards,
+    raw_move: u16,
+) -> Option<CMove> {
+    let to_square = get_polyglot_square((raw_move & 63) as u8);
+    let from_square = get_polyglot_square((raw_move >> 6 & 63) as u8);
+    let promotion = match raw_move >> 12 & 7 {
+        0 => 0,
+        piece => piece as u8 + 1,
+    };
+    let root_moves = get_root_moves(board, magic_bitboards, &[]);
+    root_moves.moves[..root_moves.count]
+        .iter()
+        .find(|c_move| {
+            c_move.from_square == from_square
+                && c_move.to_square == to_square
+                && c_move.promotion == promotion
+        })
+        .copied()
+}
+
+pub fn encode_move(c_move: CMove) -> u16 {
+    let promotion = match c_move.promotion {
+        0 => 0,
+        piece => piece as u16 - 1,
+    };
+    promotion << 12
+        | (get_polyglot_square(c_move.from_square) as u16) << 6
+        | get_polyglot_square(c_move.to_square) as u16
+}
+
+pub fn print_book_moves(board: &Board, magic_bitboards: &MagicBitboards, book: Option<&Book>) {
+    println!("key {:016x}", get_polyglot_key(board));
+    let Some(book) = book else {
+        println!("info string no book loaded");
+        return;
+    };
+    let moves = book.get_moves(board, magic_bitboards);
+    let total: u64 = moves.iter().map(|(_, weight)| *weight as u64).sum();
+    for (c_move, weight) in moves {
+        let percent = if total > 0 {
+            weight as f64 * 100.0 / total as f64
+        } else {
+            0.0
+        };
+        println!(
+            "{} weight {weight} ({percent:.1}%)",
+            c_move.uci(board.chess960)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    const POLYGLOT_POSITIONS: [(&str, u64); 9] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            0x463B96181691FC9C,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            0x823C9B50FD114196,
+        ),
+        (
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            0x0756B94461C50FB0,
+        ),
+        (
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+            0x662FAFB965DB29D4,
+        ),
+        (
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            0x22A48B5A8E47FF78,
+        ),
+        (
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+            0x652A607CA3F242C1,
+        ),
+        (
+            "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+            0x00FDD303C946BDD9,
+        ),
+        (
+            "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+            0x3C8123EA7B067637,
+        ),
+        (
+            "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4",
+            0x5C3F9B829B279560,
+        ),
+    ];
+
+    #[test]
+    fn keys_match_the_polyglot_reference() {
+        let magic_bitboards = get_test_magic_bitboards();
+        for (fen, expected) in POLYGLOT_POSITIONS {
+            let mut board = Board::new();
+            board.set_from_fen(fen, magic_bitboards);
+            assert_eq!(get_polyglot_key(&board), expected, "{fen}");
+        }
+    }
+
+    #[test]
+    fn castling_moves_round_trip() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let mut board = Board::new();
+        board.set_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", magic_bitboards);
+        for (raw_move, expected) in [(0x0107, "e1g1"), (0x0100, "e1c1")] {
+            let c_move = decode_move(&board, magic_bitboards, raw_move).unwrap();
+            assert!(c_move.castling, "{expected}");
+            assert_eq!(c_move.uci(false).to_string(), expected);
+            assert_eq!(encode_move(c_move), raw_move, "{expected}");
+        }
+    }
+
+    #[test]
+    fn promotions_round_trip() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let mut board = Board::new();
+        board.set_from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", magic_bitboards);
+        let root_moves = get_root_moves(&board, magic_bitboards, &[]);
+        for c_move in &root_moves.moves[..root_moves.count] {
+            let decoded = decode_move(&board, magic_bitboards, encode_move(*c_move));
+            assert!(decoded == Some(*c_move), "{}", c_move.uci(false));
+        }
+    }
+}
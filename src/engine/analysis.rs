@@ -0,0 +1,610 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64},
+    thread,
+};
+
+use crate::{
+    board_rep::{
+        bit_operations::{count_bits, first_bit_pop},
+        board::{BLACK, Board, COLOR_MASK, EMPTY, EN_PASSANT_NONE, KING, PIECE_MASK, WHITE},
+        c_move::CMove,
+        magic_bitboards::MagicBitboards,
+        move_gen::{PIN, PINNER, generate_moves},
+        san::get_san,
+        squares::SQUARE_NAMES,
+    },
+    engine::{
+        limits::{MAX_DEPTH, SearchLimits},
+        search::{MATE, MATE_BOUND, Search, format_score, get_root_moves},
+        searcher::SEARCH_STACK_SIZE,
+        tt::{DEFAULT_HASH_MB, TranspositionTable},
+    },
+    evaluation::{
+        eval::{PIECE_VALUES, evaluate},
+        nnue::get_network,
+        pawns::PawnTable,
+    },
+};
+
+pub const DEFAULT_ANALYSIS_DEPTH: usize = 6;
+pub const DEFAULT_ANALYSIS_LINES: usize = 3;
+
+const PIECE_CHARS: &[u8; 7] = b" pnbrqk";
+
+pub struct Pin {
+    pub pinned: u8,
+    pub pinner: u8,
+}
+
+pub struct HangingPiece {
+    pub square: u8,
+    pub attackers: u32,
+    pub defenders: u32,
+}
+
+pub struct AnalysisLine {
+    pub score: i32,
+    pub moves: Vec<CMove>,
+}
+
+pub struct Analysis {
+    pub board: Board,
+    pub legal_moves: Vec<CMove>,
+    pub check_count: u8,
+    pub pins: Vec<Pin>,
+    pub hanging: Vec<HangingPiece>,
+    pub material: [i32; 2],
+    pub static_eval: i32,
+    pub depth: usize,
+    pub lines: Vec<AnalysisLine>,
+}
+
+impl Analysis {
+    pub fn new(
+        board: &Board,
+        magic_bitboards: &MagicBitboards,
+        depth: usize,
+        line_count: usize,
+    ) -> Self {
+        let mi = generate_moves(board, magic_bitboards);
+        let root_moves = get_root_moves(board, magic_bitboards, &[]);
+        let mut pins = get_pins(board, magic_bitboards, WHITE);
+        pins.extend(get_pins(board, magic_bitboards, BLACK));
+        let mut analysis = Analysis {
+            board: *board,
+            legal_moves: root_moves.moves[..root_moves.count].to_vec(),
+            check_count: mi.check_count,
+            pins,
+            hanging: get_hanging_pieces(board, magic_bitboards),
+            material: [get_material(board, WHITE), get_material(board, BLACK)],
+            static_eval: get_static_eval(board, magic_bitboards),
+            depth: 0,
+            lines: Vec::new(),
+        };
+        if root_moves.count == 0 || line_count == 0 {
+            return analysis;
+        }
+
+        let tt = TranspositionTable::new(DEFAULT_HASH_MB);
+        let stop = AtomicBool::new(false);
+        let total_nodes = AtomicU64::new(0);
+        let limits = SearchLimits::new();
+        let lines = thread::scope(|scope| {
+            thread::Builder::new()
+                .stack_size(SEARCH_STACK_SIZE)
+                .spawn_scoped(scope, || {
+                    let mut search = Search::new(
+                        magic_bitboards,
+                        &tt,
+                        &stop,
+                        &total_nodes,
+                        &limits,
+                        board.stm,
+                        0,
+                    );
+                    let mut lines = Vec::new();
+                    for depth in 1..=depth.clamp(1, MAX_DEPTH) {
+                        if let Some(depth_lines) = search.get_root_lines(board, &root_moves, depth)
+                        {
+                            lines = depth_lines;
+                        }
+                    }
+                    lines
+                })
+                .expect("Failed to spawn analysis thread")
+                .join()
+                .expect("Analysis thread panicked")
+        });
+        analysis.depth = depth.clamp(1, MAX_DEPTH);
+        analysis.lines = lines
+            .into_iter()
+            .map(|(score, moves)| AnalysisLine { score, moves })
+            .collect();
+        analysis.lines.sort_by_key(|line| -line.score);
+        analysis.lines.truncate(line_count);
+        analysis
+    }
+
+    pub fn print(&self, magic_bitboards: &MagicBitboards) {
+        let board = &self.board;
+        println!(
+            "Side to move: {}",
+            if board.stm == WHITE { "White" } else { "Black" }
+        );
+        println!("Legal moves: {}", self.legal_moves.len());
+        println!(
+            "In check: {}",
+            match self.check_count {
+                0 => "no".to_string(),
+                1 => "yes".to_string(),
+                count => format!("yes (double check, {count} checkers)"),
+            }
+        );
+        if self.pins.is_empty() {
+            println!("Pins: none");
+        } else {
+            println!("Pins:");
+            for pin in &self.pins {
+                println!(
+                    "  {}{} pinned by {}{}",
+                    get_piece_char(board, pin.pinned),
+                    SQUARE_NAMES[pin.pinned as usize],
+                    get_piece_char(board, pin.pinner),
+                    SQUARE_NAMES[pin.pinner as usize]
+                );
+            }
+        }
+        if self.hanging.is_empty() {
+            println!("Hanging pieces: none");
+        } else {
+            println!("Hanging pieces:");
+            for piece in &self.hanging {
+                println!(
+                    "  {}{} attacked {} defended {}",
+                    get_piece_char(board, piece.square),
+                    SQUARE_NAMES[piece.square as usize],
+                    piece.attackers,
+                    piece.defenders
+                );
+            }
+        }
+        println!(
+            "Material: White {} Black {} (balance {:+})",
+            self.material[0],
+            self.material[1],
+            self.material[0] - self.material[1]
+        );
+        println!(
+            "Static eval: {} (side to move), {} (white side)",
+            self.static_eval,
+            self.get_white_eval()
+        );
+        if self.lines.is_empty() {
+            return;
+        }
+        println!("Top lines (depth {}):", self.depth);
+        for (i, line) in self.lines.iter().enumerate() {
+            println!(
+                "  {}. {:<10} {}",
+                i + 1,
+                format_score(line.score),
+                get_san_line(board, magic_bitboards, &line.moves).join(" ")
+            );
+        }
+    }
+
+    pub fn to_json(&self, magic_bitboards: &MagicBitboards) -> String {
+        let board = &self.board;
+        let legal_moves: Vec<String> = self
+            .legal_moves
+            .iter()
+            .map(|c_move| format!("\"{}\"", c_move.uci(board.chess960)))
+            .collect();
+        let pins: Vec<String> = self
+            .pins
+            .iter()
+            .map(|pin| {
+                format!(
+                    "{{\"pinned\":\"{}\",\"piece\":\"{}\",\"pinner\":\"{}\",\"pinner_piece\":\"{}\"}}",
+                    SQUARE_NAMES[pin.pinned as usize],
+                    get_piece_char(board, pin.pinned),
+                    SQUARE_NAMES[pin.pinner as usize],
+                    get_piece_char(board, pin.pinner)
+                )
+            })
+            .collect();
+        let hanging: Vec<String> = self
+            .hanging
+            .iter()
+            .map(|piece| {
+                format!(
+                    "{{\"square\":\"{}\",\"piece\":\"{}\",\"attackers\":{},\"defenders\":{}}}",
+                    SQUARE_NAMES[piece.square as usize],
+                    get_piece_char(board, piece.square),
+                    piece.attackers,
+                    piece.defenders
+                )
+            })
+            .collect();
+        let lines: Vec<String> = self
+            .lines
+            .iter()
+            .map(|line| {
+                let moves: Vec<String> = line
+                    .moves
+                    .iter()
+                    .map(|c_move| format!("\"{}\"", c_move.uci(board.chess960)))
+                    .collect();
+                let san: Vec<String> = get_san_line(board, magic_bitboards, &line.moves)
+                    .iter()
+                    .map(|san| format!("\"{san}\""))
+                    .collect();
+                format!(
+                    "{{\"score\":{},\"moves\":[{}],\"san\":[{}]}}",
+                    get_json_score(line.score),
+                    moves.join(","),
+                    san.join(",")
+                )
+            })
+            .collect();
+        format!(
+            "{{\"side_to_move\":\"{}\",\"legal_move_count\":{},\"legal_moves\":[{}],\
+             \"in_check\":{},\"check_count\":{},\"pins\":[{}],\"hanging\":[{}],\
+             \"material\":{{\"white\":{},\"black\":{},\"balance\":{}}},\
+             \"static_eval\":{{\"side_to_move\":{},\"white\":{}}},\"depth\":{},\"lines\":[{}]}}",
+            if board.stm == WHITE { "white" } else { "black" },
+            self.legal_moves.len(),
+            legal_moves.join(","),
+            self.check_count > 0,
+            self.check_count,
+            pins.join(","),
+            hanging.join(","),
+            self.material[0],
+            self.material[1],
+            self.material[0] - self.material[1],
+            self.static_eval,
+            self.get_white_eval(),
+            self.depth,
+            lines.join(",")
+        )
+    }
+
+    fn get_white_eval(&self) -> i32 {
+        if self.board.stm == WHITE {
+            self.static_eval
+        } else {
+            -self.static_eval
+        }
+    }
+}
+
+pub fn print_analysis(board: &Board, magic_bitboards: &MagicBitboards, args: &[&str]) {
+    let mut depth = DEFAULT_ANALYSIS_DEPTH;
+    let mut line_count = DEFAULT_ANALYSIS_LINES;
+    let mut json = false;
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1).unwrap_or(&"");
+        match args[i] {
+            "depth" => {
+                depth = value.parse().unwrap_or(DEFAULT_ANALYSIS_DEPTH);
+                i += 1;
+            }
+            "lines" => {
+                line_count = value.parse().unwrap_or(DEFAULT_ANALYSIS_LINES);
+                i += 1;
+            }
+            "json" => json = true,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let analysis = Analysis::new(board, magic_bitboards, depth, line_count);
+    if json {
+        println!("{}", analysis.to_json(magic_bitboards));
+    } else {
+        analysis.print(magic_bitboards);
+    }
+}
+
+/// Pins against the king of `color`, decoded from the pinner ids `generate_moves` leaves in
+/// `pin_defend_map`: a pinned piece carries `PIN` and the same id as its pinner's square.
+fn get_pins(board: &Board, magic_bitboards: &MagicBitboards, color: u8) -> Vec<Pin> {
+    let mut side_board = *board;
+    side_board.stm = color;
+    side_board.en_passant = EN_PASSANT_NONE;
+    let map = generate_moves(&side_board, magic_bitboards).pin_defend_map;
+    let mut pins = Vec::new();
+    for pinned in 0..64u8 {
+        if map[pinned as usize] & PIN == 0 {
+            continue;
+        }
+        let id = map[pinned as usize] & PINNER;
+        let pinner = (0..64u8).find(|square| {
+            let piece = board.get_piece_at(*square);
+            *square != pinned
+                && map[*square as usize] & PINNER == id
+                && piece != EMPTY
+                && piece & COLOR_MASK != color
+        });
+        if let Some(pinner) = pinner {
+            pins.push(Pin { pinned, pinner });
+        }
+    }
+    pins
+}
+
+/// Pieces attacked by a cheaper piece, or attacked and not defended at all.
+fn get_hanging_pieces(board: &Board, magic_bitboards: &MagicBitboards) -> Vec<HangingPiece> {
+    let mut hanging = Vec::new();
+    let mut occupancy = board.all_occupancy;
+    while occupancy != 0 {
+        let square = first_bit_pop(&mut occupancy);
+        let piece = board.get_piece_at(square);
+        let piece_type = piece & PIECE_MASK;
+        if piece_type == KING {
+            continue;
+        }
+        let color = piece & COLOR_MASK;
+        let mut attackers = get_attackers(board, magic_bitboards, square, color ^ COLOR_MASK);
+        if attackers == 0 {
+            continue;
+        }
+        let defenders = get_attackers(board, magic_bitboards, square, color);
+        let attacker_count = count_bits(attackers);
+        let mut cheapest_attacker = PIECE_VALUES[KING as usize];
+        while attackers != 0 {
+            let attacker = board.get_piece_at(first_bit_pop(&mut attackers)) & PIECE_MASK;
+            cheapest_attacker = cheapest_attacker.min(PIECE_VALUES[attacker as usize]);
+        }
+        if defenders == 0 || cheapest_attacker < PIECE_VALUES[piece_type as usize] {
+            hanging.push(HangingPiece {
+                square,
+                attackers: attacker_count,
+                defenders: count_bits(defenders),
+            });
+        }
+    }
+    hanging
+}
+
+fn get_attackers(board: &Board, magic_bitboards: &MagicBitboards, square: u8, color: u8) -> u64 {
+    let occupancy = board.all_occupancy;
+    let (pawns, knights, bishop_queens, rook_queens, king, pawn_attacks) = if color == WHITE {
+        (
+            board.w_pawns,
+            board.w_knights,
+            board.w_bishops | board.w_queens,
+            board.w_rooks | board.w_queens,
+            board.w_king,
+            magic_bitboards.b_pawn_capture[square as usize],
+        )
+    } else {
+        (
+            board.b_pawns,
+            board.b_knights,
+            board.b_bishops | board.b_queens,
+            board.b_rooks | board.b_queens,
+            board.b_king,
+            magic_bitboards.w_pawn_capture[square as usize],
+        )
+    };
+    (pawn_attacks & pawns)
+        | (magic_bitboards.knight_attacks[square as usize] & knights)
+        | (magic_bitboards.get_bishop_attacks(square, occupancy) & bishop_queens)
+        | (magic_bitboards.get_rook_attacks(square, occupancy) & rook_queens)
+        | (magic_bitboards.king_attacks[square as usize] & king)
+}
+
+fn get_material(board: &Board, color: u8) -> i32 {
+    let mut material = 0;
+    let mut occupancy = if color == WHITE {
+        board.w_occupancy
+    } else {
+        board.b_occupancy
+    };
+    while occupancy != 0 {
+        let piece = board.get_piece_at(first_bit_pop(&mut occupancy)) & PIECE_MASK;
+        if piece != KING {
+            material += PIECE_VALUES[piece as usize];
+        }
+    }
+    material
+}
+
+fn get_static_eval(board: &Board, magic_bitboards: &MagicBitboards) -> i32 {
+    match get_network() {
+        Some(network) => network.evaluate(board),
+        None => evaluate(board, magic_bitboards, &mut PawnTable::new(1)),
+    }
+}
+
+fn get_san_line(board: &Board, magic_bitboards: &MagicBitboards, moves: &[CMove]) -> Vec<String> {
+    let mut board = *board;
+    moves
+        .iter()
+        .map(|c_move| {
+            let san = get_san(&board, magic_bitboards, *c_move);
+            board.make_move(*c_move);
+            san
+        })
+        .collect()
+}
+
+fn get_json_score(score: i32) -> String {
+    if score > MATE_BOUND {
+        format!("{{\"mate\":{}}}", (MATE - score + 1) / 2)
+    } else if score < -MATE_BOUND {
+        format!("{{\"mate\":-{}}}", (MATE + score) / 2)
+    } else {
+        format!("{{\"cp\":{score}}}")
+    }
+}
+
+fn get_piece_char(board: &Board, square: u8) -> char {
+    let piece = board.get_piece_at(square);
+    let c = PIECE_CHARS[(piece & PIECE_MASK) as usize] as char;
+    if piece & COLOR_MASK == WHITE {
+        c.to_ascii_uppercase()
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{iter::Peekable, str::Chars};
+
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    #[derive(PartialEq, Debug)]
+    enum Json {
+        Bool(bool),
+        Number(f64),
+        String(String),
+        Array(Vec<Json>),
+        Object(Vec<(String, Json)>),
+    }
+
+    impl Json {
+        fn get(&self, key: &str) -> &Json {
+            match self {
+                Json::Object(fields) => fields
+                    .iter()
+                    .find(|(name, _)| name == key)
+                    .map(|(_, value)| value)
+                    .unwrap_or_else(|| panic!("missing {key}")),
+                _ => panic!("{self:?} is not an object"),
+            }
+        }
+
+        fn as_array(&self) -> &[Json] {
+            match self {
+                Json::Array(values) => values,
+                _ => panic!("{self:?} is not an array"),
+            }
+        }
+    }
+
+    /// Parses the subset of JSON that `to_json` writes: no escapes, nulls or exponents.
+    fn parse_json(text: &str) -> Json {
+        let mut chars = text.chars().peekable();
+        let json = parse_value(&mut chars);
+        assert_eq!(chars.next(), None, "trailing characters");
+        json
+    }
+
+    fn parse_value(chars: &mut Peekable<Chars>) -> Json {
+        match chars.next() {
+            Some('{') => {
+                let mut fields = Vec::new();
+                while chars.next_if_eq(&'}').is_none() {
+                    chars.next_if_eq(&',');
+                    let Json::String(name) = parse_value(chars) else {
+                        panic!("object keys are strings");
+                    };
+                    assert_eq!(chars.next(), Some(':'));
+                    fields.push((name, parse_value(chars)));
+                }
+                Json::Object(fields)
+            }
+            Some('[') => {
+                let mut values = Vec::new();
+                while chars.next_if_eq(&']').is_none() {
+                    chars.next_if_eq(&',');
+                    values.push(parse_value(chars));
+                }
+                Json::Array(values)
+            }
+            Some('"') => Json::String(chars.by_ref().take_while(|c| *c != '"').collect()),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let word: String = std::iter::once(c)
+                    .chain(std::iter::from_fn(|| {
+                        chars.next_if(char::is_ascii_alphabetic)
+                    }))
+                    .collect();
+                match word.as_str() {
+                    "true" => Json::Bool(true),
+                    "false" => Json::Bool(false),
+                    _ => panic!("unexpected {word}"),
+                }
+            }
+            Some(c) => {
+                let number: String = std::iter::once(c)
+                    .chain(std::iter::from_fn(|| {
+                        chars.next_if(|c| c.is_ascii_digit() || *c == '.')
+                    }))
+                    .collect();
+                Json::Number(
+                    number
+                        .parse()
+                        .unwrap_or_else(|_| panic!("bad number {number}")),
+                )
+            }
+            None => panic!("unexpected end"),
+        }
+    }
+
+    fn get_analysis_json(fen: &str, depth: usize, line_count: usize) -> Json {
+        let magic_bitboards = get_test_magic_bitboards();
+        let mut board = Board::new();
+        board.set_from_fen(fen, magic_bitboards);
+        let analysis = Analysis::new(&board, magic_bitboards, depth, line_count);
+        parse_json(&analysis.to_json(magic_bitboards))
+    }
+
+    fn get_string(value: &str) -> Json {
+        Json::String(value.to_string())
+    }
+
+    #[test]
+    fn json_reports_pins_hanging_pieces_and_checks() {
+        let json = get_analysis_json("4k3/4r3/8/8/8/2n5/4N3/4K3 w - - 0 1", 1, 1);
+        assert_eq!(json.get("side_to_move"), &get_string("white"));
+        assert_eq!(json.get("in_check"), &Json::Bool(false));
+        let pins = json.get("pins").as_array();
+        assert_eq!(pins.len(), 1);
+        assert_eq!(pins[0].get("pinned"), &get_string("e2"));
+        assert_eq!(pins[0].get("piece"), &get_string("N"));
+        assert_eq!(pins[0].get("pinner"), &get_string("e7"));
+        assert_eq!(pins[0].get("pinner_piece"), &get_string("r"));
+        let hanging = json.get("hanging").as_array();
+        assert_eq!(hanging.len(), 1);
+        assert_eq!(hanging[0].get("square"), &get_string("c3"));
+        assert_eq!(hanging[0].get("piece"), &get_string("n"));
+        assert_eq!(hanging[0].get("attackers"), &Json::Number(1.0));
+        assert_eq!(hanging[0].get("defenders"), &Json::Number(0.0));
+        assert_eq!(json.get("lines").as_array().len(), 1);
+
+        let json = get_analysis_json("4k3/8/8/8/1b6/8/8/4K3 w - - 0 1", 1, 0);
+        assert_eq!(json.get("in_check"), &Json::Bool(true));
+        assert_eq!(json.get("check_count"), &Json::Number(1.0));
+        assert!(json.get("pins").as_array().is_empty());
+        assert_eq!(json.get("lines"), &Json::Array(Vec::new()));
+    }
+
+    #[test]
+    fn json_mate_scores_count_moves_for_both_sides() {
+        for (score, expected) in [
+            (MATE - 1, "{\"mate\":1}"),
+            (MATE - 3, "{\"mate\":2}"),
+            (-(MATE - 2), "{\"mate\":-1}"),
+            (-(MATE - 4), "{\"mate\":-2}"),
+            (35, "{\"cp\":35}"),
+            (-35, "{\"cp\":-35}"),
+        ] {
+            assert_eq!(get_json_score(score), expected, "{score}");
+        }
+
+        let json = get_analysis_json("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3, 1);
+        let line = &json.get("lines").as_array()[0];
+        assert_eq!(line.get("score").get("mate"), &Json::Number(1.0));
+        assert_eq!(line.get("san").as_array()[0], get_string("Ra8#"));
+
+        let json = get_analysis_json("k7/8/1K6/8/8/8/8/7R b - - 0 1", 3, 1);
+        let line = &json.get("lines").as_array()[0];
+        assert_eq!(line.get("score").get("mate"), &Json::Number(-1.0));
+    }
+}
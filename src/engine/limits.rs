@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use crate::board_rep::board::WHITE;
+
+pub const MAX_DEPTH: usize = 64;
+
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+const MOVE_OVERHEAD: u64 = 30;
+
+#[derive(Clone)]
+pub struct SearchLimits {
+    pub depth: usize,
+    pub nodes: u64,
+    pub move_time: Option<u64>,
+    pub w_time: Option<u64>,
+    pub b_time: Option<u64>,
+    pub w_inc: u64,
+    pub b_inc: u64,
+    pub moves_to_go: Option<u64>,
+    pub infinite: bool,
+    pub mate: Option<usize>,
+    pub search_moves: Vec<String>,
+    pub elo: Option<u32>,
+    pub seed: Option<u64>,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        SearchLimits::new()
+    }
+}
+
+impl SearchLimits {
+    pub fn new() -> Self {
+        SearchLimits {
+            depth: MAX_DEPTH,
+            nodes: u64::MAX,
+            move_time: None,
+            w_time: None,
+            b_time: None,
+            w_inc: 0,
+            b_inc: 0,
+            moves_to_go: None,
+            infinite: false,
+            mate: None,
+            search_moves: Vec::new(),
+            elo: None,
+            seed: None,
+        }
+    }
+
+    pub fn from_go(parts: &[&str]) -> Self {
+        let mut limits = SearchLimits::new();
+        let mut i = 0;
+        while i < parts.len() {
+            let value = parts.get(i + 1).unwrap_or(&"").parse::<u64>().ok();
+            match parts[i] {
+                "depth" => limits.depth = value.unwrap_or(1).clamp(1, MAX_DEPTH as u64) as usize,
+                "nodes" => limits.nodes = value.unwrap_or(u64::MAX),
+                "movetime" => limits.move_time = value,
+                "wtime" => limits.w_time = value,
+                "btime" => limits.b_time = value,
+                "winc" => limits.w_inc = value.unwrap_or(0),
+                "binc" => limits.b_inc = value.unwrap_or(0),
+                "movestogo" => limits.moves_to_go = value,
+                "infinite" => limits.infinite = true,
+                "mate" => {
+                    limits.mate = value.map(|mate| mate.clamp(1, MAX_DEPTH as u64 / 2) as usize)
+                }
+                "searchmoves" => {
+                    while let Some(move_str) = parts.get(i + 1) {
+                        if is_go_keyword(move_str) {
+                            break;
+                        }
+                        limits.search_moves.push(move_str.to_string());
+                        i += 1;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        limits
+    }
+
+    pub fn get_time_limits(&self, stm: u8) -> (Option<Duration>, Option<Duration>) {
+        if self.infinite {
+            return (None, None);
+        }
+        if let Some(move_time) = self.move_time {
+            let move_time = Duration::from_millis(move_time.saturating_sub(MOVE_OVERHEAD).max(1));
+            return (Some(move_time), Some(move_time));
+        }
+
+        let (time, inc) = if stm == WHITE {
+            (self.w_time, self.w_inc)
+        } else {
+            (self.b_time, self.b_inc)
+        };
+        let Some(time) = time else {
+            return (None, None);
+        };
+        let time = time.saturating_sub(MOVE_OVERHEAD).max(1);
+        let moves_to_go = self.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let soft = (time / moves_to_go + inc * 3 / 4).min(time / 2).max(1);
+        let hard = (soft * 3).min(time * 3 / 4).max(soft);
+        (
+            Some(Duration::from_millis(soft)),
+            Some(Duration::from_millis(hard)),
+        )
+    }
+}
+
+fn is_go_keyword(part: &str) -> bool {
+    matches!(
+        part,
+        "depth"
+            | "nodes"
+            | "movetime"
+            | "wtime"
+            | "btime"
+            | "winc"
+            | "binc"
+            | "movestogo"
+            | "infinite"
+            | "ponder"
+            | "mate"
+            | "searchmoves"
+    )
+}
@@ -0,0 +1,293 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board_rep::{
+        board::Board, c_move::CMove, magic_bitboards::MagicBitboards, move_gen::generate_moves,
+    },
+    engine::{limits::SearchLimits, search::is_capture},
+};
+
+pub struct MateResult {
+    pub mate_in: Option<usize>,
+    pub keys: Vec<CMove>,
+    pub line: Vec<CMove>,
+    pub nodes: u64,
+    pub stopped: bool,
+}
+
+struct AttackerMove {
+    c_move: CMove,
+    board: Board,
+    check_count: u8,
+    is_capture: bool,
+}
+
+pub struct MateSearch<'a> {
+    magic_bitboards: &'a MagicBitboards,
+    stop: &'a AtomicBool,
+    start: Instant,
+    hard_limit: Option<Duration>,
+    max_nodes: u64,
+    root_moves: Vec<CMove>,
+    nodes: u64,
+    stopped: bool,
+    cache: HashMap<(u64, usize), bool>,
+}
+
+impl<'a> MateSearch<'a> {
+    pub fn new(
+        magic_bitboards: &'a MagicBitboards,
+        stop: &'a AtomicBool,
+        limits: &SearchLimits,
+        stm: u8,
+    ) -> Self {
+        MateSearch {
+            magic_bitboards,
+            stop,
+            start: Instant::now(),
+            hard_limit: limits.get_time_limits(stm).1,
+            max_nodes: limits.nodes,
+            root_moves: Vec::new(),
+            nodes: 0,
+            stopped: false,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Proves the shortest mate within `max_moves` starting with one of `root_moves`, stopping on
+    /// the stop flag or the time and node limits.
+    pub fn solve(&mut self, board: &Board, root_moves: &[CMove], max_moves: usize) -> MateResult {
+        let mut result = MateResult {
+            mate_in: None,
+            keys: Vec::new(),
+            line: Vec::new(),
+            nodes: 0,
+            stopped: false,
+        };
+
+        self.root_moves = root_moves.to_vec();
+        let proof = (1..=max_moves).find_map(|moves_left| {
+            self.get_root_attacker_moves(board, moves_left)
+                .iter()
+                .find(|attacker_move| self.defender_loses(&attacker_move.board, moves_left))
+                .map(|attacker_move| (moves_left, attacker_move.c_move))
+        });
+        if let Some((mate_in, key)) = proof {
+            result.mate_in = Some(mate_in);
+            for attacker_move in self.get_root_attacker_moves(board, max_moves) {
+                if attacker_move.c_move == key
+                    || self.defender_loses(&attacker_move.board, max_moves)
+                {
+                    result.keys.push(attacker_move.c_move);
+                }
+            }
+            result.line = self.get_line(board, mate_in);
+            if result.line.is_empty() {
+                result.line.push(key);
+            }
+        }
+        result.nodes = self.nodes;
+        result.stopped = self.stopped;
+        result
+    }
+
+    fn get_mate_distance(&mut self, board: &Board, max_moves: usize) -> Option<usize> {
+        (1..=max_moves).find(|&moves_left| self.attacker_wins(board, moves_left))
+    }
+
+    fn get_line(&mut self, board: &Board, mate_in: usize) -> Vec<CMove> {
+        let mut line = Vec::new();
+        let mut board = *board;
+        let mut moves_left = mate_in;
+        while moves_left > 0 && !self.stopped {
+            let attacker_moves = if line.is_empty() {
+                self.get_root_attacker_moves(&board, moves_left)
+            } else {
+                self.get_attacker_moves(&board, moves_left)
+            };
+            let Some((attacker_move, mate_in)) = (1..=moves_left).find_map(|n| {
+                attacker_moves
+                    .iter()
+                    .find(|attacker_move| self.defender_loses(&attacker_move.board, n))
+                    .map(|attacker_move| (attacker_move, n))
+            }) else {
+                break;
+            };
+            line.push(attacker_move.c_move);
+            board = attacker_move.board;
+
+            let mut longest_defense: Option<(CMove, Board, usize)> = None;
+            for c_move in self.get_legal_moves(&board).0 {
+                let mut new_board = board;
+                new_board.make_move(c_move);
+                let defense = self.get_mate_distance(&new_board, mate_in - 1).unwrap_or(0);
+                if longest_defense.is_none_or(|(_, _, longest)| defense > longest) {
+                    longest_defense = Some((c_move, new_board, defense));
+                }
+            }
+            let Some((c_move, new_board, defense)) = longest_defense else {
+                break;
+            };
+            line.push(c_move);
+            board = new_board;
+            moves_left = defense;
+        }
+        line
+    }
+
+    fn attacker_wins(&mut self, board: &Board, moves_left: usize) -> bool {
+        if self.should_stop() {
+            return false;
+        }
+        self.nodes += 1;
+        if let Some(&wins) = self.cache.get(&(board.hash, moves_left)) {
+            return wins;
+        }
+
+        let mut wins = false;
+        for attacker_move in self.get_attacker_moves(board, moves_left) {
+            if self.defender_loses(&attacker_move.board, moves_left) {
+                wins = true;
+                break;
+            }
+        }
+        if !self.stopped {
+            self.cache.insert((board.hash, moves_left), wins);
+        }
+        wins
+    }
+
+    fn defender_loses(&mut self, board: &Board, moves_left: usize) -> bool {
+        if self.should_stop() {
+            return false;
+        }
+        self.nodes += 1;
+
+        let (moves, check_count) = self.get_legal_moves(board);
+        if moves.is_empty() {
+            return check_count > 0;
+        }
+        if moves_left <= 1 {
+            return false;
+        }
+        for c_move in moves {
+            let mut new_board = *board;
+            new_board.make_move(c_move);
+            if !self.attacker_wins(&new_board, moves_left - 1) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn get_attacker_moves(&self, board: &Board, moves_left: usize) -> Vec<AttackerMove> {
+        let mut attacker_moves = Vec::new();
+        for c_move in self.get_legal_moves(board).0 {
+            let mut new_board = *board;
+            new_board.make_move(c_move);
+            let check_count = generate_moves(&new_board, self.magic_bitboards).check_count;
+            if moves_left == 1 && check_count == 0 {
+                continue;
+            }
+            attacker_moves.push(AttackerMove {
+                c_move,
+                board: new_board,
+                check_count,
+                is_capture: is_capture(board, &c_move),
+            });
+        }
+        attacker_moves.sort_by(|a, b| {
+            b.check_count
+                .cmp(&a.check_count)
+                .then(b.is_capture.cmp(&a.is_capture))
+        });
+        attacker_moves
+    }
+
+    fn get_root_attacker_moves(&self, board: &Board, moves_left: usize) -> Vec<AttackerMove> {
+        let mut attacker_moves = self.get_attacker_moves(board, moves_left);
+        attacker_moves.retain(|attacker_move| self.root_moves.contains(&attacker_move.c_move));
+        attacker_moves
+    }
+
+    fn get_legal_moves(&self, board: &Board) -> (Vec<CMove>, u8) {
+        let mi = generate_moves(board, self.magic_bitboards);
+        let moves = mi.c_move_list.moves[..mi.c_move_list.count]
+            .iter()
+            .filter(|c_move| mi.is_move_legal(board, self.magic_bitboards, c_move))
+            .copied()
+            .collect();
+        (moves, mi.check_count)
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped
+            && (self.nodes >= self.max_nodes
+                || (self.nodes & 4095 == 0
+                    && (self.stop.load(Ordering::Relaxed)
+                        || self
+                            .hard_limit
+                            .is_some_and(|hard| self.start.elapsed() >= hard))))
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board_rep::magic_bitboards::get_test_magic_bitboards, engine::search::get_root_moves,
+    };
+
+    const MATE_IN_3: &str = "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1";
+
+    fn solve(limits: &SearchLimits, mate: usize) -> MateResult {
+        let magic_bitboards = get_test_magic_bitboards();
+        let stop = AtomicBool::new(false);
+        let mut board = Board::new();
+        board.set_from_fen(MATE_IN_3, magic_bitboards);
+        let root_moves = get_root_moves(&board, magic_bitboards, &limits.search_moves);
+        let mut mate_search = MateSearch::new(magic_bitboards, &stop, limits, board.stm);
+        mate_search.solve(&board, &root_moves.moves[..root_moves.count], mate)
+    }
+
+    #[test]
+    fn proves_unique_key() {
+        let result = solve(&SearchLimits::new(), 3);
+        assert_eq!(result.mate_in, Some(3));
+        let keys: Vec<String> = result
+            .keys
+            .iter()
+            .map(|c_move| c_move.uci(false).to_string())
+            .collect();
+        assert_eq!(keys, ["f8c5"]);
+        assert_eq!(result.line.len(), 5);
+        assert!(!result.stopped);
+    }
+
+    #[test]
+    fn honours_search_moves() {
+        let mut limits = SearchLimits::new();
+        limits.search_moves = vec!["f6f2".to_string()];
+        let result = solve(&limits, 3);
+        assert_eq!(result.mate_in, None);
+        assert!(!result.stopped);
+    }
+
+    #[test]
+    fn honours_node_limit() {
+        let mut limits = SearchLimits::new();
+        limits.nodes = 1000;
+        let result = solve(&limits, 3);
+        assert_eq!(result.mate_in, None);
+        assert!(result.stopped);
+        assert!(result.nodes <= 1000);
+    }
+}
@@ -0,0 +1,10 @@
+pub mod analysis;
+pub mod bench;
+pub mod limits;
+pub mod mate_search;
+pub mod search;
+pub mod searcher;
+pub mod smp_bench;
+pub mod strength;
+pub mod test_suite;
+pub mod tt;
@@ -0,0 +1,757 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board_rep::{
+        board::{Board, EMPTY, PAWN, PIECE_MASK, QUEEN},
+        c_move::CMove,
+        c_move_list::CMoveList,
+        magic_bitboards::MagicBitboards,
+        move_gen::generate_moves,
+    },
+    engine::{
+        limits::{MAX_DEPTH, SearchLimits},
+        tt::{BOUND_EXACT, BOUND_LOWER, BOUND_UPPER, TTEntry, TranspositionTable},
+    },
+    evaluation::{
+        eval::{PIECE_VALUES, evaluate},
+        nnue::{AccumulatorStack, Network, get_network},
+        pawns::PawnTable,
+    },
+    tablebases::{
+        dtm::{Dtm, DtmTables, get_dtm_tables},
+        syzygy::{Tablebases, WDL_BLESSED_LOSS, WDL_CURSED_WIN, WDL_LOSS, WDL_WIN, get_tablebases},
+    },
+};
+
+pub const INFINITY: i32 = 32000;
+pub const MATE: i32 = 31000;
+pub const MATE_BOUND: i32 = MATE - 1000;
+pub const MAX_PLY: usize = 128;
+pub const TB_WIN: i32 = MATE_BOUND - MAX_PLY as i32;
+
+const TT_MOVE_SCORE: i32 = 2_000_000;
+const CAPTURE_SCORE: i32 = 1_000_000;
+const KILLER_SCORE: i32 = 900_000;
+const HISTORY_MAX: i32 = 800_000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum InfoFormat {
+    None,
+    Uci,
+    Xboard,
+}
+
+pub struct SearchResult {
+    pub best_move: CMove,
+    pub score: i32,
+    pub depth: usize,
+    pub nodes: u64,
+}
+
+pub struct Search<'a> {
+    magic_bitboards: &'a MagicBitboards,
+    tt: &'a TranspositionTable,
+    stop: &'a AtomicBool,
+    total_nodes: &'a AtomicU64,
+    limits: &'a SearchLimits,
+    thread_id: usize,
+    start: Instant,
+    soft_limit: Option<Duration>,
+    hard_limit: Option<Duration>,
+    stopped: bool,
+    pub nodes: u64,
+    reported_nodes: u64,
+    pub info_format: InfoFormat,
+    network: Option<Arc<Network>>,
+    accumulators: AccumulatorStack,
+    pawn_table: PawnTable,
+    tablebases: Option<Arc<Tablebases>>,
+    dtm_tables: Option<Arc<DtmTables>>,
+    history: [[i32; 64]; 64],
+    killers: [[CMove; 2]; MAX_PLY],
+    pv: [[CMove; MAX_PLY]; MAX_PLY],
+    pv_length: [usize; MAX_PLY],
+    hash_history: Vec<u64>,
+    chess960: bool,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(
+        magic_bitboards: &'a MagicBitboards,
+        tt: &'a TranspositionTable,
+        stop: &'a AtomicBool,
+        total_nodes: &'a AtomicU64,
+        limits: &'a SearchLimits,
+        stm: u8,
+        thread_id: usize,
+    ) -> Self {
+        let (soft_limit, hard_limit) = limits.get_time_limits(stm);
+        Search {
+            magic_bitboards,
+            tt,
+            stop,
+            total_nodes,
+            limits,
+            thread_id,
+            start: Instant::now(),
+            soft_limit,
+            hard_limit,
+            stopped: false,
+            nodes: 0,
+            reported_nodes: 0,
+            info_format: if thread_id == 0 {
+                InfoFormat::Uci
+            } else {
+                InfoFormat::None
+            },
+            network: get_network(),
+            accumulators: AccumulatorStack::new(),
+            pawn_table: PawnTable::default(),
+            tablebases: get_tablebases(),
+            dtm_tables: get_dtm_tables(),
+            history: [[0; 64]; 64],
+            killers: [[CMove::default(); 2]; MAX_PLY],
+            pv: [[CMove::default(); MAX_PLY]; MAX_PLY],
+            pv_length: [0; MAX_PLY],
+            hash_history: Vec::new(),
+            chess960: false,
+        }
+    }
+
+    /// Searches with the handcrafted evaluation and without tablebases, whatever the session
+    /// has loaded.
+    pub fn disable_external_data(&mut self) {
+        self.network = None;
+        self.tablebases = None;
+        self.dtm_tables = None;
+    }
+
+    /// Hashes of the game positions before the root, oldest first, for repetition detection.
+    pub fn set_history(&mut self, history: &[u64]) {
+        self.hash_history.clear();
+        self.hash_history.extend_from_slice(history);
+    }
+
+    pub fn iterate(&mut self, board: &Board, root_moves: &CMoveList) -> SearchResult {
+        self.chess960 = board.chess960;
+        let mut result = SearchResult {
+            best_move: root_moves.moves[0],
+            score: 0,
+            depth: 0,
+            nodes: 0,
+        };
+        let mut moves = root_moves.moves[..root_moves.count].to_vec();
+        if let Some(network) = &self.network {
+            self.accumulators.reset(network, board);
+        }
+
+        for iteration in 1..=self.limits.depth.min(MAX_DEPTH) {
+            let depth = if self.thread_id == 0 {
+                iteration
+            } else {
+                (iteration + self.thread_id % 2).min(MAX_DEPTH)
+            };
+            let mut alpha = -INFINITY;
+            let mut best_index = 0;
+            for (i, c_move) in moves.iter().enumerate() {
+                let new_board = self.make_move(board, *c_move);
+                let mut score;
+                if i == 0 {
+                    score = -self.negamax(&new_board, depth as i32 - 1, 1, -INFINITY, -alpha);
+                } else {
+                    score = -self.negamax(&new_board, depth as i32 - 1, 1, -alpha - 1, -alpha);
+                    if score > alpha {
+                        score = -self.negamax(&new_board, depth as i32 - 1, 1, -INFINITY, -alpha);
+                    }
+                }
+                self.unmake_move();
+                if self.stopped {
+                    break;
+                }
+                if score > alpha {
+                    alpha = score;
+                    best_index = i;
+                    self.update_pv(0, *c_move);
+                }
+            }
+            if self.stopped && alpha == -INFINITY {
+                break;
+            }
+
+            moves[..=best_index].rotate_right(1);
+            result.best_move = moves[0];
+            result.score = alpha;
+            result.depth = depth;
+            self.report_nodes();
+            self.tt.store(
+                board.hash,
+                0,
+                TTEntry {
+                    c_move: moves[0],
+                    score: alpha,
+                    depth: depth as i32,
+                    bound: BOUND_EXACT,
+                },
+            );
+            match self.info_format {
+                InfoFormat::Uci => self.print_info_line(depth, alpha),
+                InfoFormat::Xboard => self.print_xboard_post_line(depth, alpha),
+                InfoFormat::None => {}
+            }
+
+            if self.stopped
+                || self
+                    .soft_limit
+                    .is_some_and(|soft| self.start.elapsed() >= soft)
+            {
+                break;
+            }
+        }
+        self.report_nodes();
+        result.nodes = self.nodes;
+        result
+    }
+
+    /// Full-window score and principal variation of every root move, used where the ordering of
+    /// non-best moves matters.
+    pub fn get_root_lines(
+        &mut self,
+        board: &Board,
+        root_moves: &CMoveList,
+        depth: usize,
+    ) -> Option<Vec<(i32, Vec<CMove>)>> {
+        if let Some(network) = &self.network {
+            self.accumulators.reset(network, board);
+        }
+        let mut lines = Vec::with_capacity(root_moves.count);
+        for c_move in &root_moves.moves[..root_moves.count] {
+            let new_board = self.make_move(board, *c_move);
+            let score = -self.negamax(&new_board, depth as i32 - 1, 1, -INFINITY, INFINITY);
+            self.unmake_move();
+            if self.stopped {
+                return None;
+            }
+            let mut line = vec![*c_move];
+            line.extend_from_slice(&self.pv[1][1..self.pv_length[1].max(1)]);
+            lines.push((score, line));
+        }
+        self.report_nodes();
+        Some(lines)
+    }
+
+    pub fn get_quiet_position(&mut self, board: &Board) -> Board {
+        if let Some(network) = &self.network {
+            self.accumulators.reset(network, board);
+        }
+        self.quiescence(board, 0, -INFINITY, INFINITY);
+        let mut quiet_board = *board;
+        for c_move in &self.pv[0][..self.pv_length[0]] {
+            quiet_board.make_move(*c_move);
+        }
+        quiet_board
+    }
+
+    fn negamax(
+        &mut self,
+        board: &Board,
+        mut depth: i32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.pv_length[ply] = ply;
+        if self.should_stop() {
+            return 0;
+        }
+        if board.halfmove_clock >= 100 || (ply > 0 && self.is_repetition(board)) {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return self.evaluate(board);
+        }
+
+        let mut mi = generate_moves(board, self.magic_bitboards);
+        let in_check = mi.check_count > 0;
+        if in_check {
+            depth += 1;
+        }
+        if depth <= 0 {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+        self.nodes += 1;
+
+        let mut tt_move = CMove::default();
+        if let Some(entry) = self.tt.probe(board.hash, ply) {
+            tt_move = entry.c_move;
+            if beta - alpha == 1
+                && entry.depth >= depth
+                && (entry.bound == BOUND_EXACT
+                    || (entry.bound == BOUND_LOWER && entry.score >= beta)
+                    || (entry.bound == BOUND_UPPER && entry.score <= alpha))
+            {
+                return entry.score;
+            }
+        }
+
+        if ply > 0
+            && let Some(dtm_tables) = &self.dtm_tables
+            && let Some(dtm) = dtm_tables.probe(board)
+        {
+            return match dtm {
+                Dtm::Win(moves) => MATE - ply as i32 - (2 * moves as i32 - 1),
+                Dtm::Loss(moves) => -MATE + ply as i32 + 2 * moves as i32,
+                Dtm::Draw => 0,
+            };
+        }
+
+        if ply > 0
+            && board.halfmove_clock == 0
+            && let Some(tablebases) = &self.tablebases
+            && let Some(wdl) = tablebases.probe_wdl(board, self.magic_bitboards)
+        {
+            let score = match wdl {
+                WDL_WIN => TB_WIN - ply as i32,
+                WDL_CURSED_WIN => 1,
+                WDL_BLESSED_LOSS => -1,
+                WDL_LOSS => -TB_WIN + ply as i32,
+                _ => 0,
+            };
+            self.tt.store(
+                board.hash,
+                ply,
+                TTEntry {
+                    c_move: CMove::default(),
+                    score,
+                    depth,
+                    bound: BOUND_EXACT,
+                },
+            );
+            return score;
+        }
+
+        let original_alpha = alpha;
+        let mut best_move = CMove::default();
+        let mut scores = [0; 256];
+        self.score_moves(board, &mi.c_move_list, &mut scores, ply, tt_move);
+        let mut legal_moves = 0;
+        for i in 0..mi.c_move_list.count {
+            let c_move = pick_move(&mut mi.c_move_list, &mut scores, i);
+            if !mi.is_move_legal(board, self.magic_bitboards, &c_move) {
+                continue;
+            }
+            legal_moves += 1;
+
+            let new_board = self.make_move(board, c_move);
+            let mut score;
+            if legal_moves == 1 {
+                score = -self.negamax(&new_board, depth - 1, ply + 1, -beta, -alpha);
+            } else {
+                score = -self.negamax(&new_board, depth - 1, ply + 1, -alpha - 1, -alpha);
+                if score > alpha && score < beta {
+                    score = -self.negamax(&new_board, depth - 1, ply + 1, -beta, -alpha);
+                }
+            }
+            self.unmake_move();
+            if self.stopped {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = c_move;
+                self.update_pv(ply, c_move);
+                if score >= beta {
+                    if !is_capture(board, &c_move) {
+                        self.update_quiet_heuristics(c_move, depth, ply);
+                    }
+                    self.tt.store(
+                        board.hash,
+                        ply,
+                        TTEntry {
+                            c_move,
+                            score: beta,
+                            depth,
+                            bound: BOUND_LOWER,
+                        },
+                    );
+                    return beta;
+                }
+            }
+        }
+
+        if legal_moves == 0 {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+        self.tt.store(
+            board.hash,
+            ply,
+            TTEntry {
+                c_move: best_move,
+                score: alpha,
+                depth,
+                bound: if alpha > original_alpha {
+                    BOUND_EXACT
+                } else {
+                    BOUND_UPPER
+                },
+            },
+        );
+        alpha
+    }
+
+    fn quiescence(&mut self, board: &Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_length[ply] = ply;
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        let stand_pat = self.evaluate(board);
+        if ply >= MAX_PLY - 1 {
+            return stand_pat;
+        }
+        if stand_pat >= beta {
+            return beta;
+        }
+        if stand_pat > alpha {
+            alpha = stand_pat;
+        }
+
+        let mut mi = generate_moves(board, self.magic_bitboards);
+        let mut scores = [0; 256];
+        self.score_moves(board, &mi.c_move_list, &mut scores, ply, CMove::default());
+        for i in 0..mi.c_move_list.count {
+            let c_move = pick_move(&mut mi.c_move_list, &mut scores, i);
+            if !is_capture(board, &c_move) && c_move.promotion != QUEEN {
+                continue;
+            }
+            if !mi.is_move_legal(board, self.magic_bitboards, &c_move) {
+                continue;
+            }
+
+            let new_board = self.make_move(board, c_move);
+            let score = -self.quiescence(&new_board, ply + 1, -beta, -alpha);
+            self.unmake_move();
+            if self.stopped {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, c_move);
+                if score >= beta {
+                    return beta;
+                }
+            }
+        }
+        alpha
+    }
+
+    fn score_moves(
+        &self,
+        board: &Board,
+        c_move_list: &CMoveList,
+        scores: &mut [i32; 256],
+        ply: usize,
+        tt_move: CMove,
+    ) {
+        for (score, c_move) in scores
+            .iter_mut()
+            .zip(&c_move_list.moves[..c_move_list.count])
+        {
+            *score = if *c_move == tt_move {
+                TT_MOVE_SCORE
+            } else if is_capture(board, c_move) || c_move.promotion != 0 {
+                let victim = board.get_piece_at(c_move.to_square) & PIECE_MASK;
+                let attacker = board.get_piece_at(c_move.from_square) & PIECE_MASK;
+                CAPTURE_SCORE
+                    + PIECE_VALUES[victim as usize] * 10
+                    + PIECE_VALUES[c_move.promotion as usize]
+                    - PIECE_VALUES[attacker as usize] / 100
+            } else if *c_move == self.killers[ply][0] {
+                KILLER_SCORE
+            } else if *c_move == self.killers[ply][1] {
+                KILLER_SCORE - 1
+            } else {
+                self.history[c_move.from_square as usize][c_move.to_square as usize]
+            };
+        }
+    }
+
+    fn update_quiet_heuristics(&mut self, c_move: CMove, depth: i32, ply: usize) {
+        if self.killers[ply][0] != c_move {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = c_move;
+        }
+        let history = &mut self.history[c_move.from_square as usize][c_move.to_square as usize];
+        *history = (*history + depth * depth).min(HISTORY_MAX);
+    }
+
+    fn update_pv(&mut self, ply: usize, c_move: CMove) {
+        self.pv[ply][ply] = c_move;
+        let child_length = self.pv_length[ply + 1].max(ply + 1);
+        for next_ply in ply + 1..child_length {
+            self.pv[ply][next_ply] = self.pv[ply + 1][next_ply];
+        }
+        self.pv_length[ply] = child_length;
+    }
+
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        match &self.network {
+            Some(network) => network
+                .forward(self.accumulators.get_current(), board)
+                .clamp(-MATE_BOUND, MATE_BOUND),
+            None => evaluate(board, self.magic_bitboards, &mut self.pawn_table),
+        }
+    }
+
+    fn is_repetition(&self, board: &Board) -> bool {
+        self.hash_history
+            .iter()
+            .rev()
+            .take(board.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|hash| *hash == board.hash)
+    }
+
+    fn make_move(&mut self, board: &Board, c_move: CMove) -> Board {
+        self.hash_history.push(board.hash);
+        let mut new_board = *board;
+        match &self.network {
+            Some(network) => {
+                let delta = new_board.make_move_with_delta(c_move);
+                self.accumulators.push(network, &new_board, &delta);
+            }
+            None => new_board.make_move(c_move),
+        }
+        new_board
+    }
+
+    fn unmake_move(&mut self) {
+        self.hash_history.pop();
+        if self.network.is_some() {
+            self.accumulators.pop();
+        }
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if self.nodes & 2047 == 0 {
+            self.report_nodes();
+        }
+        if self.nodes >= self.limits.nodes
+            || (self.nodes & 2047 == 0
+                && (self.stop.load(Ordering::Relaxed)
+                    || self
+                        .hard_limit
+                        .is_some_and(|hard| self.start.elapsed() >= hard)))
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    fn report_nodes(&mut self) {
+        self.total_nodes
+            .fetch_add(self.nodes - self.reported_nodes, Ordering::Relaxed);
+        self.reported_nodes = self.nodes;
+    }
+
+    fn print_info_line(&self, depth: usize, score: i32) {
+        let elapsed = self.start.elapsed();
+        let nodes = self.total_nodes.load(Ordering::Relaxed);
+        let nps = (nodes as f64 / elapsed.as_secs_f64().max(0.001)) as u64;
+        let pv = self.pv[0][..self.pv_length[0]]
+            .iter()
+            .map(|c_move| c_move.uci(self.chess960).to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        println!(
+            "info depth {depth} score {} nodes {nodes} nps {nps} hashfull {} time {} pv {pv}",
+            format_score(score),
+            self.tt.get_hashfull(),
+            elapsed.as_millis()
+        );
+    }
+
+    fn print_xboard_post_line(&self, depth: usize, score: i32) {
+        let pv = self.pv[0][..self.pv_length[0]]
+            .iter()
+            .map(|c_move| c_move.uci(self.chess960).to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        println!(
+            "{depth} {} {} {} {pv}",
+            format_xboard_score(score),
+            self.start.elapsed().as_millis() / 10,
+            self.total_nodes.load(Ordering::Relaxed)
+        );
+    }
+}
+
+pub fn get_root_moves(
+    board: &Board,
+    magic_bitboards: &MagicBitboards,
+    search_moves: &[String],
+) -> CMoveList {
+    let mi = generate_moves(board, magic_bitboards);
+    let mut root_moves = CMoveList::new();
+    for i in 0..mi.c_move_list.count {
+        let c_move = mi.c_move_list.moves[i];
+        if !mi.is_move_legal(board, magic_bitboards, &c_move) {
+            continue;
+        }
+        if search_moves.is_empty() || search_moves.contains(&c_move.uci(board.chess960).to_string())
+        {
+            root_moves.push(c_move);
+        }
+    }
+
+    for move_str in get_ignored_search_moves(board, &root_moves, search_moves) {
+        println!("info string searchmoves: ignoring {move_str}, not a legal move");
+    }
+    root_moves
+}
+
+fn get_ignored_search_moves<'a>(
+    board: &Board,
+    root_moves: &CMoveList,
+    search_moves: &'a [String],
+) -> Vec<&'a String> {
+    search_moves
+        .iter()
+        .filter(|move_str| {
+            !root_moves.moves[..root_moves.count]
+                .iter()
+                .any(|c_move| c_move.uci(board.chess960).to_string() == **move_str)
+        })
+        .collect()
+}
+
+pub fn format_score(score: i32) -> String {
+    if score > MATE_BOUND {
+        format!("mate {}", (MATE - score + 1) / 2)
+    } else if score < -MATE_BOUND {
+        format!("mate -{}", (MATE + score) / 2)
+    } else {
+        format!("cp {score}")
+    }
+}
+
+pub fn format_xboard_score(score: i32) -> i32 {
+    if score > MATE_BOUND {
+        100000 + (MATE - score + 1) / 2
+    } else if score < -MATE_BOUND {
+        -100000 - (MATE + score) / 2
+    } else {
+        score
+    }
+}
+
+pub fn is_capture(board: &Board, c_move: &CMove) -> bool {
+    !c_move.castling
+        && (board.get_piece_at(c_move.to_square) != EMPTY
+            || (c_move.to_square == board.en_passant
+                && board.get_piece_at(c_move.from_square) & PIECE_MASK == PAWN))
+}
+
+fn pick_move(c_move_list: &mut CMoveList, scores: &mut [i32; 256], index: usize) -> CMove {
+    let mut best_index = index;
+    for i in index + 1..c_move_list.count {
+        if scores[i] > scores[best_index] {
+            best_index = i;
+        }
+    }
+    scores.swap(index, best_index);
+    c_move_list.moves.swap(index, best_index);
+    c_move_list.moves[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board_rep::magic_bitboards::get_test_magic_bitboards,
+        engine::searcher::{SEARCH_STACK_SIZE, run_search},
+    };
+
+    #[test]
+    fn repetition_from_game_history() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let tt = TranspositionTable::new(1);
+        let stop = AtomicBool::new(false);
+        let total_nodes = AtomicU64::new(0);
+        let limits = SearchLimits::new();
+        let mut board = Board::new();
+        board.set_from_fen(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            magic_bitboards,
+        );
+        let mut history = Vec::new();
+        for move_str in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            history.push(board.hash);
+            board.move_from_str(magic_bitboards, move_str);
+        }
+        let mut search = Search::new(
+            magic_bitboards,
+            &tt,
+            &stop,
+            &total_nodes,
+            &limits,
+            board.stm,
+            0,
+        );
+        assert!(!search.is_repetition(&board));
+        search.set_history(&history);
+        assert!(search.is_repetition(&board));
+        search.set_history(&history[1..]);
+        assert!(!search.is_repetition(&board));
+    }
+
+    #[test]
+    fn search_moves_restrict_the_root() {
+        std::thread::Builder::new()
+            .stack_size(SEARCH_STACK_SIZE)
+            .spawn(|| {
+                let magic_bitboards = get_test_magic_bitboards();
+                let mut board = Board::new();
+                board.set_from_fen(
+                    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                    magic_bitboards,
+                );
+                let limits = SearchLimits::from_go(&["depth", "3", "searchmoves", "e2e4", "e2e5"]);
+                assert_eq!(limits.search_moves, ["e2e4", "e2e5"]);
+
+                let root_moves = get_root_moves(&board, magic_bitboards, &limits.search_moves);
+                assert_eq!(root_moves.count, 1);
+                assert_eq!(root_moves.moves[0].uci(false).to_string(), "e2e4");
+                let ignored = get_ignored_search_moves(&board, &root_moves, &limits.search_moves);
+                assert_eq!(ignored, ["e2e5"]);
+
+                let tt = TranspositionTable::new(1);
+                let stop = AtomicBool::new(false);
+                let (result, _) = run_search(
+                    &board,
+                    &[],
+                    magic_bitboards,
+                    &tt,
+                    &stop,
+                    &limits,
+                    1,
+                    InfoFormat::None,
+                )
+                .unwrap();
+                assert_eq!(result.best_move.uci(false).to_string(), "e2e4");
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
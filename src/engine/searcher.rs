@@ -0,0 +1,382 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board_rep::{
+        board::{Board, WHITE},
+        c_move::CMove,
+        magic_bitboards::MagicBitboards,
+    },
+    book::polyglot::Book,
+    engine::{
+        limits::{MAX_DEPTH, SearchLimits},
+        mate_search::MateSearch,
+        search::{InfoFormat, Search, SearchResult, get_root_moves},
+        strength::{
+            DEFAULT_ELO, MAX_ELO, MIN_ELO, MIN_SCORING_NODES, SCORING_DEPTH, apply_strength_limits,
+            get_rng, pick_weakened_move,
+        },
+        tt::{DEFAULT_HASH_MB, TranspositionTable},
+    },
+    tablebases::syzygy::filter_root_moves,
+};
+
+pub const SEARCH_STACK_SIZE: usize = 16 * 1024 * 1024;
+pub const MAX_THREADS: usize = 256;
+
+pub struct Searcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    tt: Arc<TranspositionTable>,
+    threads: usize,
+    history: Vec<u64>,
+    book: Option<Book>,
+    own_book: bool,
+    best_book_move: bool,
+    limit_strength: bool,
+    elo: u32,
+    strength_seed: u64,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Searcher::new()
+    }
+}
+
+impl Searcher {
+    pub fn new() -> Self {
+        Searcher {
+            stop: Arc::new(AtomicBool::new(false)),
+            handle: None,
+            tt: Arc::new(TranspositionTable::new(DEFAULT_HASH_MB)),
+            threads: 1,
+            history: Vec::new(),
+            book: None,
+            own_book: false,
+            best_book_move: false,
+            limit_strength: false,
+            elo: DEFAULT_ELO,
+            strength_seed: 0,
+        }
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.stop();
+        self.threads = threads.clamp(1, MAX_THREADS);
+    }
+
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.stop();
+        self.tt = Arc::new(TranspositionTable::new(size_mb));
+    }
+
+    pub fn clear_hash(&mut self) {
+        self.stop();
+        self.tt.clear();
+    }
+
+    /// Hashes of the game positions before the next search root, oldest first.
+    pub fn set_history(&mut self, history: Vec<u64>) {
+        self.history = history;
+    }
+
+    pub fn set_book(&mut self, book: Option<Book>) {
+        self.book = book;
+    }
+
+    pub fn get_book(&self) -> Option<&Book> {
+        self.book.as_ref()
+    }
+
+    pub fn set_own_book(&mut self, own_book: bool) {
+        self.own_book = own_book;
+    }
+
+    pub fn set_best_book_move(&mut self, best: bool) {
+        self.best_book_move = best;
+    }
+
+    /// Returns a move from the book, if `OwnBook` is on and the position is in it.
+    pub fn get_book_move(&self, board: &Board, magic_bitboards: &MagicBitboards) -> Option<CMove> {
+        if !self.own_book {
+            return None;
+        }
+        self.book
+            .as_ref()?
+            .get_book_move(board, magic_bitboards, self.best_book_move)
+    }
+
+    pub fn set_limit_strength(&mut self, limit_strength: bool) {
+        self.limit_strength = limit_strength;
+    }
+
+    pub fn set_elo(&mut self, elo: u32) {
+        self.elo = elo.clamp(MIN_ELO, MAX_ELO);
+    }
+
+    /// Seeds the move choice so limited games can be reproduced, 0 seeds from the clock.
+    pub fn set_strength_seed(&mut self, seed: u64) {
+        self.strength_seed = seed;
+    }
+
+    /// Sets the rating and seed of `limits` from the strength options.
+    pub fn apply_strength_options(&self, limits: &mut SearchLimits) {
+        limits.elo = self.limit_strength.then_some(self.elo);
+        limits.seed = Some(self.strength_seed).filter(|seed| *seed != 0);
+    }
+
+    pub fn start<F>(
+        &mut self,
+        board: &Board,
+        magic_bitboards: &Arc<MagicBitboards>,
+        limits: SearchLimits,
+        info_format: InfoFormat,
+        on_best_move: F,
+    ) where
+        F: FnOnce(Option<CMove>) + Send + 'static,
+    {
+        self.stop();
+        self.stop.store(false, Ordering::Relaxed);
+
+        let board = *board;
+        let magic_bitboards = Arc::clone(magic_bitboards);
+        let tt = Arc::clone(&self.tt);
+        let stop = Arc::clone(&self.stop);
+        let threads = self.threads;
+        let history = self.history.clone();
+        let handle = thread::Builder::new()
+            .stack_size(SEARCH_STACK_SIZE)
+            .spawn(move || {
+                let best_move = match limits.mate {
+                    Some(mate) => run_mate_search(
+                        &board,
+                        &history,
+                        &magic_bitboards,
+                        &tt,
+                        &stop,
+                        &limits,
+                        mate,
+                    ),
+                    None => run_search(
+                        &board,
+                        &history,
+                        &magic_bitboards,
+                        &tt,
+                        &stop,
+                        &limits,
+                        threads,
+                        info_format,
+                    )
+                    .map(|(result, _)| result.best_move),
+                };
+                on_best_move(best_move);
+            })
+            .expect("Failed to spawn search thread");
+        self.handle = Some(handle);
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.wait();
+    }
+
+    pub fn wait(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("Search thread panicked");
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn run_search(
+    board: &Board,
+    history: &[u64],
+    magic_bitboards: &MagicBitboards,
+    tt: &TranspositionTable,
+    stop: &AtomicBool,
+    limits: &SearchLimits,
+    threads: usize,
+    info_format: InfoFormat,
+) -> Option<(SearchResult, u64)> {
+    let mut root_moves = get_root_moves(board, magic_bitboards, &limits.search_moves);
+    if root_moves.count == 0 {
+        return None;
+    }
+    if let Some(filtered) = filter_root_moves(board, magic_bitboards, &root_moves) {
+        root_moves = filtered;
+    }
+
+    let mut strength_limits = limits.clone();
+    apply_strength_limits(&mut strength_limits);
+    let limits = &strength_limits;
+
+    let root_moves = &root_moves;
+    let total_nodes = AtomicU64::new(0);
+    let result = thread::scope(|scope| {
+        for thread_id in 1..threads {
+            let total_nodes = &total_nodes;
+            thread::Builder::new()
+                .stack_size(SEARCH_STACK_SIZE)
+                .spawn_scoped(scope, move || {
+                    let mut search = Search::new(
+                        magic_bitboards,
+                        tt,
+                        stop,
+                        total_nodes,
+                        limits,
+                        board.stm,
+                        thread_id,
+                    );
+                    search.set_history(history);
+                    search.iterate(board, root_moves);
+                })
+                .expect("Failed to spawn helper search thread");
+        }
+
+        let mut search = Search::new(
+            magic_bitboards,
+            tt,
+            stop,
+            &total_nodes,
+            limits,
+            board.stm,
+            0,
+        );
+        search.info_format = info_format;
+        search.set_history(history);
+        let mut result = search.iterate(board, root_moves);
+        if let Some(elo) = limits.elo {
+            let mut scoring_limits = SearchLimits::new();
+            scoring_limits.nodes = result.nodes.max(MIN_SCORING_NODES);
+            let mut scoring = Search::new(
+                magic_bitboards,
+                tt,
+                stop,
+                &total_nodes,
+                &scoring_limits,
+                board.stm,
+                0,
+            );
+            scoring.set_history(history);
+            let mut lines = None;
+            for depth in 1..=result.depth.clamp(1, SCORING_DEPTH) {
+                match scoring.get_root_lines(board, root_moves, depth) {
+                    Some(depth_lines) => lines = Some(depth_lines),
+                    None => break,
+                }
+            }
+            if let Some(lines) = lines {
+                let scores: Vec<(CMove, i32)> = lines
+                    .iter()
+                    .map(|(score, line)| (line[0], *score))
+                    .collect();
+                let mut rng = get_rng(limits.seed, board.hash);
+                result.best_move = pick_weakened_move(&scores, result.best_move, elo, &mut rng);
+            }
+        }
+        while limits.infinite && !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        stop.store(true, Ordering::Relaxed);
+        result
+    });
+    Some((result, total_nodes.load(Ordering::Relaxed)))
+}
+
+pub fn run_mate_search(
+    board: &Board,
+    history: &[u64],
+    magic_bitboards: &MagicBitboards,
+    tt: &TranspositionTable,
+    stop: &AtomicBool,
+    limits: &SearchLimits,
+    mate: usize,
+) -> Option<CMove> {
+    let start = Instant::now();
+    let root_moves = get_root_moves(board, magic_bitboards, &limits.search_moves);
+    if root_moves.count == 0 {
+        return None;
+    }
+    let mut mate_search = MateSearch::new(magic_bitboards, stop, limits, board.stm);
+    let result = mate_search.solve(board, &root_moves.moves[..root_moves.count], mate);
+    if let Some(mate_in) = result.mate_in {
+        let pv = result
+            .line
+            .iter()
+            .map(|c_move| c_move.uci(board.chess960).to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        println!(
+            "info depth {} score mate {mate_in} nodes {} time {} pv {pv}",
+            mate_in * 2 - 1,
+            result.nodes,
+            start.elapsed().as_millis()
+        );
+        let keys = result
+            .keys
+            .iter()
+            .map(|c_move| c_move.uci(board.chess960).to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        if result.stopped {
+            println!("info string mate in {mate_in} proven, stopped before checking all keys");
+        } else if result.keys.len() == 1 {
+            println!("info string mate in {mate_in} proven, key {keys} is unique");
+        } else {
+            println!(
+                "info string mate in {mate_in} proven, cooked with {} keys mating in {mate} or less: {keys}",
+                result.keys.len()
+            );
+        }
+        return result.line.first().copied();
+    }
+
+    if result.stopped {
+        println!("info string mate search stopped");
+    } else {
+        println!(
+            "info string no forced mate in {mate}, nodes {} time {}",
+            result.nodes,
+            start.elapsed().as_millis()
+        );
+    }
+    // The fallback search gets whatever the mate search left of the time and node budget.
+    let mut limits = limits.clone();
+    limits.mate = None;
+    limits.depth = if result.stopped {
+        1
+    } else {
+        (mate * 2).min(MAX_DEPTH)
+    };
+    limits.nodes = limits.nodes.saturating_sub(result.nodes).max(1);
+    let elapsed = start.elapsed().as_millis() as u64;
+    if let Some(move_time) = &mut limits.move_time {
+        *move_time = move_time.saturating_sub(elapsed).max(1);
+    }
+    let time = if board.stm == WHITE {
+        &mut limits.w_time
+    } else {
+        &mut limits.b_time
+    };
+    if let Some(time) = time {
+        *time = time.saturating_sub(elapsed).max(1);
+    }
+    run_search(
+        board,
+        history,
+        magic_bitboards,
+        tt,
+        stop,
+        &limits,
+        1,
+        InfoFormat::Uci,
+    )
+    .map(|(result, _)| result.best_move)
+}
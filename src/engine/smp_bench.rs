@@ -0,0 +1,70 @@
+use std::{sync::atomic::AtomicBool, thread, time::Instant};
+
+use crate::{
+    board_rep::{board::Board, magic_bitboards::MagicBitboards},
+    engine::{
+        limits::SearchLimits,
+        search::InfoFormat,
+        searcher::{SEARCH_STACK_SIZE, run_search},
+        tt::{DEFAULT_HASH_MB, TranspositionTable},
+    },
+};
+
+const SMP_BENCH_FENS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+pub fn print_smp_bench(magic_bitboards: &MagicBitboards, max_threads: usize, move_time: u64) {
+    let mut limits = SearchLimits::new();
+    limits.move_time = Some(move_time);
+
+    let mut base_nps = 0.0;
+    let mut threads = 1;
+    while threads <= max_threads.max(1) {
+        let mut total_nodes = 0;
+        let mut total_seconds = 0.0;
+        for fen in SMP_BENCH_FENS {
+            let mut board = Board::new();
+            board.set_from_fen(fen, magic_bitboards);
+            let tt = TranspositionTable::new(DEFAULT_HASH_MB);
+            let stop = AtomicBool::new(false);
+
+            let now = Instant::now();
+            let result = thread::scope(|scope| {
+                thread::Builder::new()
+                    .stack_size(SEARCH_STACK_SIZE)
+                    .spawn_scoped(scope, || {
+                        run_search(
+                            &board,
+                            &[],
+                            magic_bitboards,
+                            &tt,
+                            &stop,
+                            &limits,
+                            threads,
+                            InfoFormat::None,
+                        )
+                    })
+                    .expect("Failed to spawn search thread")
+                    .join()
+                    .expect("Search thread panicked")
+            });
+            total_seconds += now.elapsed().as_secs_f64();
+            total_nodes += result.map_or(0, |(_, nodes)| nodes);
+        }
+
+        let nps = total_nodes as f64 / total_seconds;
+        if threads == 1 {
+            base_nps = nps;
+        }
+        println!(
+            "Threads: {threads:>3}  Nodes: {total_nodes:>12}  NPS: {nps:>12.0}  Speedup: {:.2}x",
+            nps / base_nps
+        );
+        threads *= 2;
+    }
+    println!();
+}
@@ -0,0 +1,169 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    board_rep::{c_move::CMove, rng::Rng},
+    engine::{limits::SearchLimits, search::MATE_BOUND},
+};
+
+pub const MIN_ELO: u32 = 800;
+/// Full strength: no depth, node or move-choice limits are applied at this rating.
+pub const MAX_ELO: u32 = 2800;
+pub const DEFAULT_ELO: u32 = 1500;
+
+const MAX_LIMITED_DEPTH: f64 = 8.0;
+const MIN_NODES: f64 = 400.0;
+const NODE_DOUBLINGS: f64 = 8.0;
+const MAX_TEMPERATURE: f64 = 180.0;
+const MIN_TEMPERATURE: f64 = 4.0;
+const MAX_LOSS_TEMPERATURES: i32 = 4;
+pub const SCORING_DEPTH: usize = 4;
+pub const MIN_SCORING_NODES: u64 = 2000;
+
+/// Elo to skill anchors from self-play with the `match` tool. Each 0.1 step of skill played 200
+/// games against the next, and the measured differences were summed down from full strength at
+/// `MAX_ELO`. With no rated opponents to anchor against, this is a self-play scale, which runs
+/// wider than rating-list Elo. Each step was measured with
+///
+/// ```text
+/// match -engine1 nuvo_chess -engine2 nuvo_chess -games 200 -movetime 100 \
+///     -option UCI_LimitStrength=true -option1 UCI_Elo=<stronger> -option2 UCI_Elo=<weaker> \
+///     -resign 1000 3 -draw 40 10 8
+/// ```
+const ELO_LADDER: [(u32, f64); 8] = [
+    (800, 0.286),
+    (1040, 0.4),
+    (1410, 0.5),
+    (1740, 0.6),
+    (2110, 0.7),
+    (2410, 0.8),
+    (2590, 0.9),
+    (2800, 1.0),
+];
+
+/// Random source for one move choice. A seeded choice depends only on the seed and position.
+pub fn get_rng(seed: Option<u64>, hash: u64) -> Rng {
+    let seed = seed.map_or_else(
+        || {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_nanos() as u64)
+        },
+        |seed| seed ^ hash,
+    );
+    let mut rng = Rng::new();
+    rng.seed(seed | 1);
+    rng
+}
+
+fn get_skill(elo: u32) -> f64 {
+    let elo = elo.clamp(MIN_ELO, MAX_ELO);
+    let upper = ELO_LADDER
+        .iter()
+        .position(|(anchor, _)| *anchor >= elo)
+        .unwrap_or(ELO_LADDER.len() - 1)
+        .max(1);
+    let (low_elo, low_skill) = ELO_LADDER[upper - 1];
+    let (high_elo, high_skill) = ELO_LADDER[upper];
+    low_skill + (high_skill - low_skill) * (elo - low_elo) as f64 / (high_elo - low_elo) as f64
+}
+
+pub fn apply_strength_limits(limits: &mut SearchLimits) {
+    limits.elo = limits.elo.filter(|elo| *elo < MAX_ELO);
+    let Some(elo) = limits.elo else {
+        return;
+    };
+    let skill = get_skill(elo);
+    let depth = 1 + (skill * (MAX_LIMITED_DEPTH - 1.0)).round() as usize;
+    let nodes = (MIN_NODES * 2f64.powf(skill * NODE_DOUBLINGS)) as u64;
+    limits.depth = limits.depth.min(depth);
+    limits.nodes = limits.nodes.min(nodes);
+}
+
+pub fn get_temperature(elo: u32) -> f64 {
+    let weakness = 1.0 - get_skill(elo);
+    MIN_TEMPERATURE + (MAX_TEMPERATURE - MIN_TEMPERATURE) * weakness * weakness
+}
+
+/// Samples a root move with weights falling off exponentially with its loss against the best
+/// score. The searched best move counts as best even if the shallower scoring disagrees.
+pub fn pick_weakened_move(
+    scores: &[(CMove, i32)],
+    best_move: CMove,
+    elo: u32,
+    rng: &mut Rng,
+) -> CMove {
+    let Some(best_score) = scores.iter().map(|(_, score)| *score).max() else {
+        return best_move;
+    };
+    if best_score.abs() > MATE_BOUND {
+        return best_move;
+    }
+    let temperature = get_temperature(elo);
+    let max_loss = (temperature as i32) * MAX_LOSS_TEMPERATURES;
+    let weights: Vec<(CMove, f64)> = scores
+        .iter()
+        .map(|(c_move, score)| {
+            let score = if *c_move == best_move {
+                best_score
+            } else {
+                *score
+            };
+            (*c_move, score)
+        })
+        .filter(|(_, score)| best_score - score <= max_loss)
+        .map(|(c_move, score)| (c_move, ((score - best_score) as f64 / temperature).exp()))
+        .collect();
+
+    let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+    let mut pick = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64 * total;
+    for (c_move, weight) in &weights {
+        if pick < *weight {
+            return *c_move;
+        }
+        pick -= weight;
+    }
+    best_move
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::limits::MAX_DEPTH;
+
+    #[test]
+    fn max_elo_is_unlimited() {
+        let mut limits = SearchLimits::new();
+        limits.elo = Some(MAX_ELO);
+        apply_strength_limits(&mut limits);
+        assert_eq!(limits.elo, None);
+        assert_eq!(limits.depth, MAX_DEPTH);
+        assert_eq!(limits.nodes, u64::MAX);
+
+        limits.elo = Some(MAX_ELO - 1);
+        apply_strength_limits(&mut limits);
+        assert_eq!(limits.elo, Some(MAX_ELO - 1));
+        assert!(limits.depth < MAX_DEPTH && limits.nodes < u64::MAX);
+    }
+
+    #[test]
+    fn seeded_choice_is_reproducible() {
+        let scores: Vec<(CMove, i32)> = (0..8u8)
+            .map(|square| {
+                let c_move = CMove {
+                    to_square: square,
+                    ..CMove::default()
+                };
+                (c_move, -10 * square as i32)
+            })
+            .collect();
+        let pick = |seed, hash| {
+            let mut rng = get_rng(Some(seed), hash);
+            (0..16)
+                .map(|_| pick_weakened_move(&scores, scores[0].0, MIN_ELO, &mut rng).to_square)
+                .collect::<Vec<u8>>()
+        };
+        let picks = pick(7, 42);
+        assert_eq!(picks, pick(7, 42));
+        assert!(picks.iter().any(|square| *square != 0));
+    }
+}
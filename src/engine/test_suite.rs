@@ -0,0 +1,128 @@
+use std::{
+    fs,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Instant,
+};
+
+use crate::{
+    board_rep::{epd::EpdRecord, magic_bitboards::MagicBitboards, san::get_san},
+    engine::{
+        limits::SearchLimits,
+        search::{InfoFormat, format_score},
+        searcher::{SEARCH_STACK_SIZE, run_search},
+        tt::{DEFAULT_HASH_MB, TranspositionTable},
+    },
+};
+
+pub fn print_test_suite(magic_bitboards: &MagicBitboards, path: &str, limits: &SearchLimits) {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) => {
+            println!("info string {path}: {error}");
+            return;
+        }
+    };
+    let records: Vec<EpdRecord> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| EpdRecord::parse(line, magic_bitboards))
+        .collect();
+    let tt = TranspositionTable::new(DEFAULT_HASH_MB);
+    let stop = AtomicBool::new(false);
+
+    let now = Instant::now();
+    let mut solved = 0;
+    let mut points = 0;
+    let mut max_points = 0;
+    let mut total_nodes = 0;
+    for (i, record) in records.iter().enumerate() {
+        let best_moves = record.get_moves("bm", magic_bitboards);
+        let avoid_moves = record.get_moves("am", magic_bitboards);
+        let move_points = record.get_move_points(magic_bitboards);
+        tt.clear();
+        stop.store(false, Ordering::Relaxed);
+
+        let result = thread::scope(|scope| {
+            thread::Builder::new()
+                .stack_size(SEARCH_STACK_SIZE)
+                .spawn_scoped(scope, || {
+                    run_search(
+                        &record.board,
+                        &[],
+                        magic_bitboards,
+                        &tt,
+                        &stop,
+                        limits,
+                        1,
+                        InfoFormat::None,
+                    )
+                })
+                .expect("Failed to spawn search thread")
+                .join()
+                .expect("Search thread panicked")
+        });
+        let Some((result, nodes)) = result else {
+            println!(
+                "{:>4}/{} {} no legal moves",
+                i + 1,
+                records.len(),
+                record.get_id()
+            );
+            continue;
+        };
+        total_nodes += nodes;
+
+        let best_move = result.best_move;
+        let is_solved = (best_moves.is_empty() || best_moves.contains(&best_move))
+            && !avoid_moves.contains(&best_move)
+            && !(best_moves.is_empty() && avoid_moves.is_empty());
+        solved += is_solved as usize;
+        let earned = move_points
+            .iter()
+            .find(|(c_move, _)| *c_move == best_move)
+            .map_or(0, |(_, points)| *points);
+        points += earned;
+        max_points += move_points
+            .iter()
+            .map(|(_, points)| *points)
+            .max()
+            .unwrap_or(0);
+
+        let expected = match (record.get_operation("bm"), record.get_operation("am")) {
+            (Some(bm), _) => format!("bm {bm}"),
+            (None, Some(am)) => format!("am {am}"),
+            (None, None) => "-".to_string(),
+        };
+        println!(
+            "{:>4}/{} {:<16} {:<8} {:<12} {:<7} {expected}{}",
+            i + 1,
+            records.len(),
+            record.get_id(),
+            get_san(&record.board, magic_bitboards, best_move),
+            format_score(result.score),
+            if is_solved { "solved" } else { "failed" },
+            if move_points.is_empty() {
+                String::new()
+            } else {
+                format!(" points {earned}")
+            }
+        );
+    }
+
+    let elapsed = now.elapsed().as_secs_f64();
+    println!();
+    println!(
+        "Solved: {solved}/{} ({:.1}%)",
+        records.len(),
+        solved as f64 * 100.0 / records.len().max(1) as f64
+    );
+    if max_points > 0 {
+        println!(
+            "Points: {points}/{max_points} ({:.1}%)",
+            points as f64 * 100.0 / max_points as f64
+        );
+    }
+    println!("Nodes: {total_nodes}");
+    println!("Seconds: {elapsed:.2}");
+}
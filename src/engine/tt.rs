@@ -0,0 +1,130 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    board_rep::c_move::CMove,
+    engine::search::{MATE_BOUND, MAX_PLY},
+};
+
+pub const BOUND_EXACT: u8 = 1;
+pub const BOUND_LOWER: u8 = 2;
+pub const BOUND_UPPER: u8 = 3;
+
+pub const DEFAULT_HASH_MB: usize = 16;
+
+#[derive(Clone, Copy)]
+pub struct TTEntry {
+    pub c_move: CMove,
+    pub score: i32,
+    pub depth: i32,
+    pub bound: u8,
+}
+
+struct TTSlot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+pub struct TranspositionTable {
+    slots: Vec<TTSlot>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let slot_count = (size_mb.max(1) * 1024 * 1024 / size_of::<TTSlot>()).max(1);
+        let slots = (0..slot_count)
+            .map(|_| TTSlot {
+                key: AtomicU64::new(0),
+                data: AtomicU64::new(0),
+            })
+            .collect();
+        TranspositionTable { slots }
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn probe(&self, hash: u64, ply: usize) -> Option<TTEntry> {
+        let slot = &self.slots[self.get_index(hash)];
+        let data = slot.data.load(Ordering::Relaxed);
+        if slot.key.load(Ordering::Relaxed) ^ data != hash || data == 0 {
+            return None;
+        }
+
+        let mut entry = unpack(data);
+        if entry.score > MATE_BOUND {
+            entry.score -= ply as i32;
+        } else if entry.score < -MATE_BOUND {
+            entry.score += ply as i32;
+        }
+        Some(entry)
+    }
+
+    pub fn store(&self, hash: u64, ply: usize, entry: TTEntry) {
+        let slot = &self.slots[self.get_index(hash)];
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let same_position = slot.key.load(Ordering::Relaxed) ^ old_data == hash;
+        if same_position && entry.bound != BOUND_EXACT && unpack(old_data).depth > entry.depth + 2 {
+            return;
+        }
+
+        let mut entry = entry;
+        if entry.score > MATE_BOUND {
+            entry.score += ply as i32;
+        } else if entry.score < -MATE_BOUND {
+            entry.score -= ply as i32;
+        }
+        if same_position && entry.c_move == CMove::default() {
+            entry.c_move = unpack(old_data).c_move;
+        }
+        let data = pack(&entry);
+        slot.key.store(hash ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    pub fn get_hashfull(&self) -> usize {
+        let sample = self.slots.len().min(1000);
+        let used = self.slots[..sample]
+            .iter()
+            .filter(|slot| slot.data.load(Ordering::Relaxed) != 0)
+            .count();
+        used * 1000 / sample
+    }
+
+    fn get_index(&self, hash: u64) -> usize {
+        ((hash as u128 * self.slots.len() as u128) >> 64) as usize
+    }
+}
+
+fn pack(entry: &TTEntry) -> u64 {
+    let c_move = entry.c_move.from_square as u64
+        | (entry.c_move.to_square as u64) << 6
+        | (entry.c_move.promotion as u64) << 12
+        | (entry.c_move.castling as u64) << 15;
+    let score = entry.score as i16 as u16 as u64;
+    let depth = entry.depth.clamp(0, MAX_PLY as i32) as u64;
+    c_move | score << 16 | depth << 32 | (entry.bound as u64) << 40
+}
+
+fn unpack(data: u64) -> TTEntry {
+    TTEntry {
+        c_move: CMove {
+            from_square: (data & 63) as u8,
+            to_square: ((data >> 6) & 63) as u8,
+            promotion: ((data >> 12) & 7) as u8,
+            castling: (data >> 15) & 1 == 1,
+        },
+        score: (data >> 16) as u16 as i16 as i32,
+        depth: ((data >> 32) & 255) as i32,
+        bound: ((data >> 40) & 3) as u8,
+    }
+}
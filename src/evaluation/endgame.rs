@@ -0,0 +1,342 @@
+use crate::{
+    board_rep::{
+        bit_operations::{DARK_SQUARES, FILE_A, FILE_H, LIGHT_SQUARES, count_bits, first_bit},
+        board::{BISHOP, BLACK, Board, KNIGHT, PAWN, QUEEN, ROOK, WHITE},
+    },
+    evaluation::{
+        eval::Side,
+        kpk::kpk_probe,
+        params::EG_MATERIAL,
+        pawns::{get_front_span, get_relative_rank},
+    },
+};
+
+pub const SCALE_NORMAL: i32 = 64;
+pub const KNOWN_WIN: i32 = 10000;
+
+const KBNK: u64 = get_signature_key(b"KBN", b"K");
+const KPK: u64 = get_signature_key(b"KP", b"K");
+const KRPKR: u64 = get_signature_key(b"KRP", b"KR");
+const KK: u64 = get_signature_key(b"K", b"K");
+const KNK: u64 = get_signature_key(b"KN", b"K");
+const KBK: u64 = get_signature_key(b"KB", b"K");
+const KNNK: u64 = get_signature_key(b"KNN", b"K");
+
+const KRPKR_DRAW_SCALE: i32 = 8;
+const OPPOSITE_BISHOPS_SCALE: i32 = 8;
+const OPPOSITE_BISHOPS_PAWN_SCALE: i32 = 8;
+const OPPOSITE_BISHOPS_PIECES_SCALE: i32 = 48;
+const EDGE_BONUS: i32 = 20;
+const CORNER_BONUS: i32 = 20;
+const CLOSENESS_BONUS: i32 = 10;
+const KPK_RANK_BONUS: i32 = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EndgameEval {
+    Score(i32),
+    Scale([i32; 2]),
+}
+
+pub const fn get_signature_key(strong: &[u8], weak: &[u8]) -> u64 {
+    get_pieces_key(strong, 0) | get_pieces_key(weak, 20)
+}
+
+const fn get_pieces_key(pieces: &[u8], shift: u32) -> u64 {
+    let mut key = 0;
+    let mut i = 0;
+    while i < pieces.len() {
+        let piece = match pieces[i] {
+            b'P' => PAWN,
+            b'N' => KNIGHT,
+            b'B' => BISHOP,
+            b'R' => ROOK,
+            b'Q' => QUEEN,
+            _ => 0,
+        };
+        if piece != 0 {
+            key += 1 << (shift + (piece as u32 - 1) * 4);
+        }
+        i += 1;
+    }
+    key
+}
+
+pub fn get_material_key(board: &Board, strong: u8) -> u64 {
+    let [strong, weak] = [strong, strong ^ (WHITE | BLACK)].map(|color| Side::new(board, color));
+    get_side_key(&strong, 0) | get_side_key(&weak, 20)
+}
+
+fn get_side_key(side: &Side, shift: u32) -> u64 {
+    [
+        side.pawns,
+        side.knights,
+        side.bishops,
+        side.rooks,
+        side.queens,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0, |key, (i, pieces)| {
+        key | (count_bits(*pieces).min(15) as u64) << (shift + i as u32 * 4)
+    })
+}
+
+pub fn evaluate_endgame(board: &Board) -> Option<EndgameEval> {
+    for strong in [WHITE, BLACK] {
+        let key = get_material_key(board, strong);
+        let eval = match key {
+            KK | KNK | KBK | KNNK => Some(EndgameEval::Score(0)),
+            KBNK => Some(EndgameEval::Score(evaluate_kbnk(board, strong))),
+            KPK => {
+                kpk_probe(board).map(|wins| EndgameEval::Score(evaluate_kpk(board, strong, wins)))
+            }
+            KRPKR => scale_krpkr(board, strong),
+            _ => evaluate_kxk(board, strong)
+                .map(EndgameEval::Score)
+                .or_else(|| scale_wrong_bishop(board, strong)),
+        };
+        if eval.is_some() {
+            return eval;
+        }
+    }
+    scale_opposite_bishops(board)
+}
+
+fn evaluate_kxk(board: &Board, strong: u8) -> Option<i32> {
+    let [own, enemy] = [strong, strong ^ (WHITE | BLACK)].map(|color| Side::new(board, color));
+    if enemy.occupancy != enemy.king || own.king == 0 || enemy.king == 0 {
+        return None;
+    }
+    let has_mating_material = own.queens != 0
+        || own.rooks != 0
+        || own.bishops & LIGHT_SQUARES != 0 && own.bishops & DARK_SQUARES != 0;
+    if !has_mating_material {
+        return None;
+    }
+    let strong_king = first_bit(own.king);
+    let weak_king = first_bit(enemy.king);
+    let score = KNOWN_WIN
+        + get_material(&own)
+        + EDGE_BONUS * get_edge_distance(weak_king)
+        + CLOSENESS_BONUS * (7 - get_distance(strong_king, weak_king));
+    Some(if strong == WHITE { score } else { -score })
+}
+
+fn evaluate_kbnk(board: &Board, strong: u8) -> i32 {
+    let [own, enemy] = [strong, strong ^ (WHITE | BLACK)].map(|color| Side::new(board, color));
+    let strong_king = first_bit(own.king);
+    let weak_king = first_bit(enemy.king);
+    let corners = if own.bishops & LIGHT_SQUARES != 0 {
+        [0, 63]
+    } else {
+        [7, 56]
+    };
+    let corner_distance = corners
+        .iter()
+        .map(|corner| get_manhattan_distance(weak_king, *corner))
+        .min()
+        .unwrap_or(0);
+    let score = KNOWN_WIN
+        + get_material(&own)
+        + CORNER_BONUS * (14 - corner_distance)
+        + CLOSENESS_BONUS * (7 - get_distance(strong_king, weak_king));
+    if strong == WHITE { score } else { -score }
+}
+
+fn evaluate_kpk(board: &Board, strong: u8, wins: bool) -> i32 {
+    if !wins {
+        return 0;
+    }
+    let own = Side::new(board, strong);
+    let score = KNOWN_WIN
+        + EG_MATERIAL[PAWN as usize]
+        + KPK_RANK_BONUS * get_relative_rank(first_bit(own.pawns), strong) as i32;
+    if strong == WHITE { score } else { -score }
+}
+
+fn scale_krpkr(board: &Board, strong: u8) -> Option<EndgameEval> {
+    let [own, enemy] = [strong, strong ^ (WHITE | BLACK)].map(|color| Side::new(board, color));
+    if get_front_span(own.pawns, strong) & enemy.king != 0 {
+        Some(get_scale(strong, KRPKR_DRAW_SCALE))
+    } else {
+        None
+    }
+}
+
+fn scale_wrong_bishop(board: &Board, strong: u8) -> Option<EndgameEval> {
+    let [own, enemy] = [strong, strong ^ (WHITE | BLACK)].map(|color| Side::new(board, color));
+    if enemy.occupancy != enemy.king
+        || own.pawns == 0
+        || own.bishops == 0
+        || own.knights | own.rooks | own.queens != 0
+    {
+        return None;
+    }
+    let file = if own.pawns & !FILE_A == 0 {
+        0
+    } else if own.pawns & !FILE_H == 0 {
+        7
+    } else {
+        return None;
+    };
+    let promotion_square = if strong == WHITE { file } else { 56 + file };
+    let promotion_color = if LIGHT_SQUARES & 1 << promotion_square != 0 {
+        LIGHT_SQUARES
+    } else {
+        DARK_SQUARES
+    };
+    if own.bishops & promotion_color != 0 {
+        return None;
+    }
+    if get_distance(first_bit(enemy.king), promotion_square) <= 1 {
+        Some(get_scale(strong, 0))
+    } else {
+        None
+    }
+}
+
+fn scale_opposite_bishops(board: &Board) -> Option<EndgameEval> {
+    let white = Side::new(board, WHITE);
+    let black = Side::new(board, BLACK);
+    if count_bits(white.bishops) != 1 || count_bits(black.bishops) != 1 {
+        return None;
+    }
+    if (white.bishops & LIGHT_SQUARES != 0) == (black.bishops & LIGHT_SQUARES != 0) {
+        return None;
+    }
+    let pieces = white.knights | white.rooks | white.queens | black.knights | black.rooks;
+    let scale = if pieces | black.queens != 0 {
+        OPPOSITE_BISHOPS_PIECES_SCALE
+    } else {
+        let pawn_difference = count_bits(white.pawns).abs_diff(count_bits(black.pawns)) as i32;
+        (OPPOSITE_BISHOPS_SCALE + OPPOSITE_BISHOPS_PAWN_SCALE * pawn_difference).min(SCALE_NORMAL)
+    };
+    Some(EndgameEval::Scale([scale, scale]))
+}
+
+fn get_scale(strong: u8, scale: i32) -> EndgameEval {
+    if strong == WHITE {
+        EndgameEval::Scale([scale, SCALE_NORMAL])
+    } else {
+        EndgameEval::Scale([SCALE_NORMAL, scale])
+    }
+}
+
+fn get_material(side: &Side) -> i32 {
+    [
+        (side.pawns, PAWN),
+        (side.knights, KNIGHT),
+        (side.bishops, BISHOP),
+        (side.rooks, ROOK),
+        (side.queens, QUEEN),
+    ]
+    .iter()
+    .map(|(pieces, piece)| count_bits(*pieces) as i32 * EG_MATERIAL[*piece as usize])
+    .sum()
+}
+
+fn get_edge_distance(square: u8) -> i32 {
+    let file = (square % 8) as i32;
+    let rank = (square / 8) as i32;
+    (3 - file).max(file - 4) + (3 - rank).max(rank - 4)
+}
+
+pub fn get_distance(from: u8, to: u8) -> i32 {
+    let file_distance = ((from % 8) as i32 - (to % 8) as i32).abs();
+    let rank_distance = ((from / 8) as i32 - (to / 8) as i32).abs();
+    file_distance.max(rank_distance)
+}
+
+fn get_manhattan_distance(from: u8, to: u8) -> i32 {
+    let file_distance = ((from % 8) as i32 - (to % 8) as i32).abs();
+    let rank_distance = ((from / 8) as i32 - (to / 8) as i32).abs();
+    file_distance + rank_distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    fn get_endgame_eval(fen: &str) -> Option<EndgameEval> {
+        let mut board = Board::new();
+        board.set_from_fen(fen, get_test_magic_bitboards());
+        evaluate_endgame(&board)
+    }
+
+    fn get_score(fen: &str) -> i32 {
+        match get_endgame_eval(fen) {
+            Some(EndgameEval::Score(score)) => score,
+            eval => panic!("{fen}: {eval:?}"),
+        }
+    }
+
+    #[test]
+    fn insufficient_material_is_a_draw() {
+        for fen in [
+            "8/8/4k3/8/8/4K3/8/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4kn2/8/8/8/8/8/8/4K3 b - - 0 1",
+            "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+        ] {
+            assert_eq!(get_endgame_eval(fen), Some(EndgameEval::Score(0)), "{fen}");
+        }
+    }
+
+    #[test]
+    fn kbnk_drives_the_king_to_the_bishop_corner() {
+        let bishop_corner = get_score("k7/8/2K5/8/8/8/8/5BN1 w - - 0 1");
+        let other_corner = get_score("8/8/8/8/8/2K5/8/k4BN1 w - - 0 1");
+        let centre = get_score("8/8/2K5/8/3k4/8/8/5BN1 w - - 0 1");
+        assert!(
+            bishop_corner > other_corner,
+            "{bishop_corner} {other_corner}"
+        );
+        assert!(bishop_corner > centre, "{bishop_corner} {centre}");
+        assert!(other_corner > KNOWN_WIN && centre > KNOWN_WIN);
+        assert_eq!(get_score("5bn1/8/8/8/8/2k5/8/K7 b - - 0 1"), -bishop_corner);
+    }
+
+    #[test]
+    fn krpkr_is_drawish_with_the_king_in_front() {
+        assert_eq!(
+            get_endgame_eval("4k3/8/8/4P3/8/8/r7/R3K3 w - - 0 1"),
+            Some(EndgameEval::Scale([KRPKR_DRAW_SCALE, SCALE_NORMAL]))
+        );
+        assert_eq!(
+            get_endgame_eval("r3k3/8/8/8/4p3/8/R7/4K3 b - - 0 1"),
+            Some(EndgameEval::Scale([SCALE_NORMAL, KRPKR_DRAW_SCALE]))
+        );
+        assert_eq!(get_endgame_eval("7k/8/8/4P3/8/8/r7/R3K3 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn wrong_bishop_rook_pawn_is_a_draw() {
+        assert_eq!(
+            get_endgame_eval("7k/8/8/7P/8/8/8/4KB2 w - - 0 1"),
+            Some(EndgameEval::Scale([0, SCALE_NORMAL]))
+        );
+        assert_eq!(get_endgame_eval("7k/8/8/7P/8/8/8/4BK2 w - - 0 1"), None);
+        assert_eq!(get_endgame_eval("8/8/8/7P/8/8/2k5/4KB2 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn opposite_bishops_scale_down() {
+        let scale = OPPOSITE_BISHOPS_SCALE + OPPOSITE_BISHOPS_PAWN_SCALE;
+        assert_eq!(
+            get_endgame_eval("4k3/5b2/8/8/3P4/8/8/2B1K3 w - - 0 1"),
+            Some(EndgameEval::Scale([scale, scale]))
+        );
+        assert_eq!(
+            get_endgame_eval("4k3/5b2/8/8/3P4/8/8/2BRK3 w - - 0 1"),
+            Some(EndgameEval::Scale([
+                OPPOSITE_BISHOPS_PIECES_SCALE,
+                OPPOSITE_BISHOPS_PIECES_SCALE
+            ]))
+        );
+        assert_eq!(
+            get_endgame_eval("4k3/4b3/8/8/3P4/8/8/2B1K3 w - - 0 1"),
+            None
+        );
+    }
+}
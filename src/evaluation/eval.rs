@@ -0,0 +1,292 @@
+use crate::{
+    board_rep::{
+        bit_operations::{count_bits, first_bit_pop},
+        board::{BISHOP, Board, KING, KNIGHT, PAWN, QUEEN, ROOK, WHITE},
+        magic_bitboards::MagicBitboards,
+    },
+    evaluation::{
+        endgame::{EndgameEval, SCALE_NORMAL, evaluate_endgame},
+        king_safety::{KING_SAFETY_TERM_NAMES, add_king_safety_terms, evaluate_king_safety},
+        mobility::{MOBILITY_TERM_NAMES, add_mobility_terms, evaluate_mobility},
+        params::{EvalTerms, MATERIAL_PARAMS, PST_PARAMS, Trace},
+        pawns::{PAWN_TERM_NAMES, PawnTable, add_pawn_terms, evaluate_pawns},
+    },
+};
+
+pub const PIECE_VALUES: [i32; 7] = [0, 100, 320, 330, 500, 900, 20000];
+
+const PHASE_WEIGHTS: [i32; 7] = [0, 0, 1, 1, 2, 4, 0];
+const MAX_PHASE: i32 = 24;
+
+pub struct Side {
+    pub king: u64,
+    pub pawns: u64,
+    pub knights: u64,
+    pub bishops: u64,
+    pub rooks: u64,
+    pub queens: u64,
+    pub occupancy: u64,
+}
+
+impl Side {
+    pub fn new(board: &Board, color: u8) -> Self {
+        if color == WHITE {
+            Side {
+                king: board.w_king,
+                pawns: board.w_pawns,
+                knights: board.w_knights,
+                bishops: board.w_bishops,
+                rooks: board.w_rooks,
+                queens: board.w_queens,
+                occupancy: board.w_occupancy,
+            }
+        } else {
+            Side {
+                king: board.b_king,
+                pawns: board.b_pawns,
+                knights: board.b_knights,
+                bishops: board.b_bishops,
+                rooks: board.b_rooks,
+                queens: board.b_queens,
+                occupancy: board.b_occupancy,
+            }
+        }
+    }
+}
+
+pub fn evaluate(
+    board: &Board,
+    magic_bitboards: &MagicBitboards,
+    pawn_table: &mut PawnTable,
+) -> i32 {
+    let (mut mg, mut eg) = get_material_and_pst(board);
+    let (pawn_mg, pawn_eg, _) = pawn_table.probe(board);
+    let (king_mg, king_eg) = evaluate_king_safety(board, magic_bitboards).get_total();
+    let (mobility_mg, mobility_eg) = evaluate_mobility(board, magic_bitboards).get_total();
+    mg += pawn_mg + king_mg + mobility_mg;
+    eg += pawn_eg + king_eg + mobility_eg;
+    let score = match evaluate_endgame(board) {
+        Some(EndgameEval::Score(score)) => score,
+        Some(EndgameEval::Scale(scales)) => taper(mg, scale_endgame(eg, scales), get_phase(board)),
+        None => taper(mg, eg, get_phase(board)),
+    };
+    if board.stm == WHITE { score } else { -score }
+}
+
+fn scale_endgame(eg: i32, scales: [i32; 2]) -> i32 {
+    let scale = if eg > 0 { scales[0] } else { scales[1] };
+    eg * scale / SCALE_NORMAL
+}
+
+pub fn print_eval(board: &Board, magic_bitboards: &MagicBitboards) {
+    let phase = get_phase(board);
+    let (w_material, b_material) = get_side_material_and_pst(board);
+    let pawn_eval = evaluate_pawns(board);
+    let king_safety = evaluate_king_safety(board, magic_bitboards);
+    let mobility = evaluate_mobility(board, magic_bitboards);
+
+    println!("      Term     |     White     |     Black     |     Total");
+    println!("               |   MG     EG   |   MG     EG   |   MG     EG");
+    println!("---------------+---------------+---------------+--------------");
+    print_eval_term("Material/PST", w_material, b_material);
+    for (name, [white, black]) in PAWN_TERM_NAMES.iter().zip(pawn_eval.terms) {
+        print_eval_term(name, white, black);
+    }
+    for (name, [white, black]) in KING_SAFETY_TERM_NAMES.iter().zip(king_safety.terms) {
+        print_eval_term(name, white, black);
+    }
+    for (name, [white, black]) in MOBILITY_TERM_NAMES.iter().zip(mobility.terms) {
+        print_eval_term(name, white, black);
+    }
+    println!("---------------+---------------+---------------+--------------");
+    let (pawn_mg, pawn_eg) = pawn_eval.get_total();
+    let (king_mg, king_eg) = king_safety.get_total();
+    let (mobility_mg, mobility_eg) = mobility.get_total();
+    let mg = w_material.0 - b_material.0 + pawn_mg + king_mg + mobility_mg;
+    let eg = w_material.1 - b_material.1 + pawn_eg + king_eg + mobility_eg;
+    println!(
+        "{:>14} |               |               | {:>6} {:>6}",
+        "Total", mg, eg
+    );
+    println!();
+    println!("Phase: {phase}/{MAX_PHASE}");
+    let score = match evaluate_endgame(board) {
+        Some(EndgameEval::Score(score)) => {
+            println!("Endgame: specialized score {score}");
+            score
+        }
+        Some(EndgameEval::Scale(scales)) => {
+            println!(
+                "Endgame: scale factors {}/{SCALE_NORMAL} (white) {}/{SCALE_NORMAL} (black)",
+                scales[0], scales[1]
+            );
+            taper(mg, scale_endgame(eg, scales), phase)
+        }
+        None => taper(mg, eg, phase),
+    };
+    println!("Final evaluation: {score} (white side)");
+    let mut pawn_table = PawnTable::new(1);
+    let score = evaluate(board, magic_bitboards, &mut pawn_table);
+    println!("Final evaluation: {score} (side to move)");
+}
+
+fn print_eval_term(name: &str, white: (i32, i32), black: (i32, i32)) {
+    println!(
+        "{:>14} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6}",
+        name,
+        white.0,
+        white.1,
+        black.0,
+        black.1,
+        white.0 - black.0,
+        white.1 - black.1
+    );
+}
+
+pub fn get_phase(board: &Board) -> i32 {
+    let phase = count_bits(board.w_knights | board.b_knights) as i32
+        * PHASE_WEIGHTS[KNIGHT as usize]
+        + count_bits(board.w_bishops | board.b_bishops) as i32 * PHASE_WEIGHTS[BISHOP as usize]
+        + count_bits(board.w_rooks | board.b_rooks) as i32 * PHASE_WEIGHTS[ROOK as usize]
+        + count_bits(board.w_queens | board.b_queens) as i32 * PHASE_WEIGHTS[QUEEN as usize];
+    phase.min(MAX_PHASE)
+}
+
+pub fn taper(mg: i32, eg: i32, phase: i32) -> i32 {
+    (mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE
+}
+
+fn get_material_and_pst(board: &Board) -> (i32, i32) {
+    let (white, black) = get_side_material_and_pst(board);
+    (white.0 - black.0, white.1 - black.1)
+}
+
+fn get_side_material_and_pst(board: &Board) -> ((i32, i32), (i32, i32)) {
+    let mut terms = [[(0, 0); 2]; 1];
+    add_material_terms(board, &mut terms);
+    (terms[0][0], terms[0][1])
+}
+
+/// Adds material and piece-square weights to term 0 of `terms`.
+fn add_material_terms<T: EvalTerms>(board: &Board, terms: &mut T) {
+    let w_pieces = [
+        (board.w_pawns, PAWN),
+        (board.w_knights, KNIGHT),
+        (board.w_bishops, BISHOP),
+        (board.w_rooks, ROOK),
+        (board.w_queens, QUEEN),
+        (board.w_king, KING),
+    ];
+    let b_pieces = [
+        (board.b_pawns, PAWN),
+        (board.b_knights, KNIGHT),
+        (board.b_bishops, BISHOP),
+        (board.b_rooks, ROOK),
+        (board.b_queens, QUEEN),
+        (board.b_king, KING),
+    ];
+
+    for (side, pieces) in [w_pieces, b_pieces].into_iter().enumerate() {
+        let flip = if side == 0 { 0 } else { 56 };
+        for (mut pieces, piece) in pieces {
+            let piece = piece as usize;
+            while pieces != 0 {
+                let square = first_bit_pop(&mut pieces) as usize ^ flip;
+                if piece != KING as usize {
+                    terms.add(0, side, MATERIAL_PARAMS + piece - 1, 1);
+                }
+                terms.add(0, side, PST_PARAMS + (piece - 1) * 64 + square, 1);
+            }
+        }
+    }
+}
+
+/// Records the coefficient of every evaluation parameter in `board` from white's point of view,
+/// along with the endgame scale factors. Returns `None` for positions scored by a specialized
+/// endgame evaluation, which the parameters do not affect.
+pub fn trace_evaluation(
+    board: &Board,
+    magic_bitboards: &MagicBitboards,
+) -> Option<(Trace, [i32; 2])> {
+    let scales = match evaluate_endgame(board) {
+        Some(EndgameEval::Score(_)) => return None,
+        Some(EndgameEval::Scale(scales)) => scales,
+        None => [SCALE_NORMAL; 2],
+    };
+    let mut trace = Trace::new();
+    add_material_terms(board, &mut trace);
+    add_pawn_terms(board, &mut trace);
+    add_king_safety_terms(board, magic_bitboards, &mut trace);
+    add_mobility_terms(board, magic_bitboards, &mut trace);
+    Some((trace, scales))
+}
+
+pub fn get_piece_attacks(
+    magic_bitboards: &MagicBitboards,
+    piece: u8,
+    square: u8,
+    occupancy: u64,
+) -> u64 {
+    match piece {
+        KNIGHT => magic_bitboards.knight_attacks[square as usize],
+        BISHOP => magic_bitboards.get_bishop_attacks(square, occupancy),
+        ROOK => magic_bitboards.get_rook_attacks(square, occupancy),
+        _ => {
+            magic_bitboards.get_bishop_attacks(square, occupancy)
+                | magic_bitboards.get_rook_attacks(square, occupancy)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board_rep::magic_bitboards::get_test_magic_bitboards, evaluation::params::PARAMS};
+
+    const TRACE_POSITIONS: [&str; 5] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r1bq1rk1/pp2ppbp/2np1n2/6PP/3NP3/2N1B3/PPPQ4/2KR1B1R b - - 0 1",
+        "8/3p4/p1bk3p/Pp6/1Kp1PpPp/2P2P1P/2P5/5B2 b - - 0 1",
+        "2kr1b1r/pppq4/2n1b3/3np3/6pp/2NP1N2/PP2PPBP/R1BQ1RK1 w - - 0 1",
+    ];
+
+    #[test]
+    fn trace_reproduces_evaluation() {
+        let magic_bitboards = get_test_magic_bitboards();
+        for fen in TRACE_POSITIONS {
+            let mut board = Board::new();
+            board.set_from_fen(fen, magic_bitboards);
+            let (mut mg, mut eg) = get_material_and_pst(&board);
+            for (term_mg, term_eg) in [
+                evaluate_pawns(&board).get_total(),
+                evaluate_king_safety(&board, magic_bitboards).get_total(),
+                evaluate_mobility(&board, magic_bitboards).get_total(),
+            ] {
+                mg += term_mg;
+                eg += term_eg;
+            }
+
+            let (trace, scales) = trace_evaluation(&board, magic_bitboards).unwrap();
+            assert_eq!(scales, [SCALE_NORMAL; 2], "{fen}");
+            let (traced_mg, traced_eg) = trace.coefficients.iter().zip(PARAMS).fold(
+                (0.0, 0.0),
+                |(mg, eg), (coefficient, weight)| {
+                    (
+                        mg + coefficient * weight.0 as f64,
+                        eg + coefficient * weight.1 as f64,
+                    )
+                },
+            );
+            // King attack weights are scaled with integer division in the evaluation.
+            assert!(
+                (traced_mg - mg as f64).abs() < 2.0,
+                "{fen}: {traced_mg} {mg}"
+            );
+            assert!(
+                (traced_eg - eg as f64).abs() < 2.0,
+                "{fen}: {traced_eg} {eg}"
+            );
+        }
+    }
+}
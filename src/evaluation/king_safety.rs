@@ -0,0 +1,242 @@
+use crate::{
+    board_rep::{
+        bit_operations::{FILE_A, count_bits, first_bit, first_bit_pop, last_bit},
+        board::{BISHOP, BLACK, Board, KNIGHT, QUEEN, ROOK, WHITE},
+        magic_bitboards::MagicBitboards,
+    },
+    evaluation::{
+        eval::{Side, get_piece_attacks},
+        params::{
+            BLOCKED_STORM_PARAM, EvalTerms, OPEN_FILE_PARAM, SAFE_CHECK_PARAMS,
+            SEMI_OPEN_FILE_PARAM, SHIELD_PARAMS, STORM_PARAMS, ZONE_ATTACK_PARAMS,
+        },
+        pawns::{get_front_span, get_pawn_attacks, get_relative_rank},
+    },
+};
+
+pub const KING_ATTACKS: usize = 0;
+pub const PAWN_SHIELD: usize = 1;
+pub const PAWN_STORM: usize = 2;
+pub const OPEN_FILES: usize = 3;
+pub const SAFE_CHECKS: usize = 4;
+pub const KING_SAFETY_TERMS: usize = 5;
+pub const KING_SAFETY_TERM_NAMES: [&str; KING_SAFETY_TERMS] = [
+    "King attacks",
+    "Pawn shield",
+    "Pawn storm",
+    "Open files",
+    "Safe checks",
+];
+
+const ATTACKER_SCALE: [i32; 8] = [0, 0, 50, 75, 88, 94, 97, 99];
+
+#[derive(Clone, Copy, Default)]
+pub struct KingSafetyEval {
+    pub terms: [[(i32, i32); 2]; KING_SAFETY_TERMS],
+}
+
+impl KingSafetyEval {
+    pub fn get_total(&self) -> (i32, i32) {
+        self.terms.iter().fold((0, 0), |(mg, eg), [white, black]| {
+            (mg + white.0 - black.0, eg + white.1 - black.1)
+        })
+    }
+}
+
+pub fn evaluate_king_safety(board: &Board, magic_bitboards: &MagicBitboards) -> KingSafetyEval {
+    let mut king_safety = KingSafetyEval::default();
+    add_king_safety_terms(board, magic_bitboards, &mut king_safety.terms);
+    king_safety
+}
+
+/// Adds the king safety terms to `terms`.
+pub fn add_king_safety_terms<T: EvalTerms>(
+    board: &Board,
+    magic_bitboards: &MagicBitboards,
+    terms: &mut T,
+) {
+    for (side, color) in [WHITE, BLACK].into_iter().enumerate() {
+        let own = Side::new(board, color);
+        if own.king == 0 {
+            continue;
+        }
+        let enemy = Side::new(board, color ^ (WHITE | BLACK));
+        let king_square = first_bit(own.king);
+
+        add_king_attack_terms(board, magic_bitboards, &own, &enemy, color, side, terms);
+
+        let king_file = king_square % 8;
+        let ranks_ahead = get_front_span(0xFF << (king_square - king_file), color);
+        for file in king_file.max(1) - 1..=king_file.min(6) + 1 {
+            let file_mask = FILE_A << file;
+            let own_pawns = own.pawns & file_mask & ranks_ahead;
+            let enemy_pawns = enemy.pawns & file_mask & ranks_ahead;
+            let shield_square = get_closest_square(own_pawns, color);
+            let storm_square = get_closest_square(enemy_pawns, color);
+
+            let shield_distance = shield_square.map_or(0, |square| {
+                get_relative_rank(square, color) - get_relative_rank(king_square, color)
+            });
+            terms.add(PAWN_SHIELD, side, SHIELD_PARAMS + shield_distance, 1);
+
+            if let Some(storm_square) = storm_square {
+                let storm_distance =
+                    get_relative_rank(storm_square, color) - get_relative_rank(king_square, color);
+                let blocked = shield_square.is_some() && storm_distance == shield_distance + 1;
+                let param = if blocked {
+                    BLOCKED_STORM_PARAM
+                } else {
+                    STORM_PARAMS + storm_distance
+                };
+                terms.add(PAWN_STORM, side, param, 1);
+            }
+
+            if own.pawns & file_mask == 0 {
+                let param = if enemy.pawns & file_mask == 0 {
+                    OPEN_FILE_PARAM
+                } else {
+                    SEMI_OPEN_FILE_PARAM
+                };
+                terms.add(OPEN_FILES, side, param, 1);
+            }
+        }
+    }
+}
+
+fn add_king_attack_terms<T: EvalTerms>(
+    board: &Board,
+    magic_bitboards: &MagicBitboards,
+    own: &Side,
+    enemy: &Side,
+    color: u8,
+    side: usize,
+    terms: &mut T,
+) {
+    let king_square = first_bit(own.king);
+    let king_zone = magic_bitboards.king_attacks[king_square as usize] | own.king;
+    let occupancy = board.all_occupancy;
+
+    let mut defended =
+        get_pawn_attacks(own.pawns, color) | magic_bitboards.king_attacks[king_square as usize];
+    let mut attacked = [0; 7];
+    let mut attacker_count = 0;
+    let mut zone_attacks = [(0, 0); 4];
+    for (pieces_side, is_enemy) in [(own, false), (enemy, true)] {
+        for (i, (piece, mut pieces)) in [
+            (KNIGHT, pieces_side.knights),
+            (BISHOP, pieces_side.bishops),
+            (ROOK, pieces_side.rooks),
+            (QUEEN, pieces_side.queens),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            zone_attacks[i].0 = ZONE_ATTACK_PARAMS + i;
+            while pieces != 0 {
+                let square = first_bit_pop(&mut pieces);
+                let attacks = get_piece_attacks(magic_bitboards, piece, square, occupancy);
+                if !is_enemy {
+                    defended |= attacks;
+                    continue;
+                }
+                attacked[piece as usize] |= attacks;
+                let count = count_bits(attacks & king_zone) as i32;
+                if count > 0 {
+                    attacker_count += 1;
+                    zone_attacks[i].1 += count;
+                }
+            }
+        }
+    }
+    let scale = ATTACKER_SCALE[attacker_count.min(ATTACKER_SCALE.len() - 1)];
+    terms.add_scaled(KING_ATTACKS, side, &zone_attacks, -scale, 100);
+
+    let safe = !defended & !enemy.occupancy;
+    for (i, piece) in [KNIGHT, BISHOP, ROOK, QUEEN].into_iter().enumerate() {
+        let checks = get_piece_attacks(magic_bitboards, piece, king_square, occupancy);
+        let count = count_bits(checks & attacked[piece as usize] & safe) as i32;
+        terms.add(SAFE_CHECKS, side, SAFE_CHECK_PARAMS + i, -count);
+    }
+}
+
+fn get_closest_square(pawns: u64, color: u8) -> Option<u8> {
+    if pawns == 0 {
+        None
+    } else if color == WHITE {
+        Some(last_bit(pawns))
+    } else {
+        Some(first_bit(pawns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    /// Positions where the given side's king is clearly safer, paired with a similar position where
+    /// the other king is.
+    const KING_SAFETY_POSITIONS: [(&str, u8); 8] = [
+        (
+            "r1bqk2r/ppp2ppp/2n5/3p4/1b1P4/2N2N2/PP3PPP/R1BQ1RK1 b kq - 0 1",
+            WHITE,
+        ),
+        (
+            "r1bq1rk1/pp3ppp/2n2n2/3p4/1B1P4/2N5/PP3PPP/R2QK2R w KQ - 0 1",
+            BLACK,
+        ),
+        (
+            "r4rk1/ppp2ppp/2n5/3q4/6PP/5N2/PP3P2/R2Q1RK1 w - - 0 1",
+            BLACK,
+        ),
+        (
+            "r2q1rk1/pp3p2/5n2/6pp/3Q4/2N5/PPP2PPP/R4RK1 b - - 0 1",
+            WHITE,
+        ),
+        (
+            "r1b2rk1/pp3ppp/2n1p3/q5NQ/3P4/3B4/PP3PPP/R4RK1 b - - 0 1",
+            WHITE,
+        ),
+        (
+            "r4rk1/pp3ppp/3b4/3p4/Q5nq/2N1P3/PP3PPP/R1B2RK1 w - - 0 1",
+            BLACK,
+        ),
+        (
+            "r1bq1rk1/pp2ppbp/2np1n2/6PP/3NP3/2N1B3/PPPQ4/2KR1B1R b - - 0 1",
+            WHITE,
+        ),
+        (
+            "2kr1b1r/pppq4/2n1b3/3np3/6pp/2NP1N2/PP2PPBP/R1BQ1RK1 w - - 0 1",
+            BLACK,
+        ),
+    ];
+
+    /// Returns the midgame king safety of each side, where higher is safer.
+    fn get_side_safety(board: &Board, magic_bitboards: &MagicBitboards) -> [i32; 2] {
+        evaluate_king_safety(board, magic_bitboards)
+            .terms
+            .iter()
+            .fold([0, 0], |[white, black], [w_term, b_term]| {
+                [white + w_term.0, black + b_term.0]
+            })
+    }
+
+    #[test]
+    fn safer_king_scores_higher() {
+        let magic_bitboards = get_test_magic_bitboards();
+        for pair in KING_SAFETY_POSITIONS.chunks(2) {
+            assert_ne!(pair[0].1, pair[1].1);
+            for (fen, safer) in pair {
+                let mut board = Board::new();
+                board.set_from_fen(fen, magic_bitboards);
+                let [white, black] = get_side_safety(&board, magic_bitboards);
+                let (safer, other) = if *safer == WHITE {
+                    (white, black)
+                } else {
+                    (black, white)
+                };
+                assert!(safer > other, "{fen}: white {white} black {black}");
+            }
+        }
+    }
+}
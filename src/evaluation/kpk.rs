@@ -0,0 +1,207 @@
+use std::sync::OnceLock;
+
+use crate::board_rep::{
+    bit_operations::first_bit,
+    board::{BLACK, Board, WHITE},
+    magic_bitboards::MagicBitboards,
+};
+
+const PAWN_SQUARES: usize = 24;
+const KPK_SIZE: usize = 2 * 64 * 64 * PAWN_SQUARES;
+
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+static KPK: OnceLock<Vec<u64>> = OnceLock::new();
+
+pub fn init_kpk(magic_bitboards: &MagicBitboards) {
+    KPK.get_or_init(|| generate_kpk(magic_bitboards));
+}
+
+pub fn kpk_probe(board: &Board) -> Option<bool> {
+    let bitbase = KPK.get()?;
+    let (strong, pawns) = match (board.w_pawns, board.b_pawns) {
+        (pawns, 0) if pawns.count_ones() == 1 => (WHITE, pawns),
+        (0, pawns) if pawns.count_ones() == 1 => (BLACK, pawns),
+        _ => return None,
+    };
+    let (strong_king, weak_king) = if strong == WHITE {
+        (board.w_king, board.b_king)
+    } else {
+        (board.b_king, board.w_king)
+    };
+    if board.all_occupancy != strong_king | weak_king | pawns || strong_king == 0 || weak_king == 0
+    {
+        return None;
+    }
+    let flip = if strong == WHITE { 0 } else { 56 };
+    let [mut strong_king, mut weak_king, mut pawn] =
+        [strong_king, weak_king, pawns].map(|bitboard| first_bit(bitboard) ^ flip);
+    if pawn % 8 > 3 {
+        strong_king ^= 7;
+        weak_king ^= 7;
+        pawn ^= 7;
+    }
+    let index = get_index(board.stm != strong, strong_king, weak_king, pawn);
+    Some(bitbase[index / 64] & 1 << (index % 64) != 0)
+}
+
+fn get_index(weak_to_move: bool, strong_king: u8, weak_king: u8, pawn: u8) -> usize {
+    let pawn_index = (6 - pawn as usize / 8) * 4 + pawn as usize % 8;
+    ((weak_to_move as usize * 64 + weak_king as usize) * 64 + strong_king as usize) * PAWN_SQUARES
+        + pawn_index
+}
+
+fn get_position(index: usize) -> (bool, u8, u8, u8) {
+    let pawn_index = index % PAWN_SQUARES;
+    let rest = index / PAWN_SQUARES;
+    let pawn = ((6 - pawn_index / 4) * 8 + pawn_index % 4) as u8;
+    (
+        rest / 4096 == 1,
+        (rest % 64) as u8,
+        (rest / 64 % 64) as u8,
+        pawn,
+    )
+}
+
+fn generate_kpk(magic_bitboards: &MagicBitboards) -> Vec<u64> {
+    let mut results: Vec<u8> = (0..KPK_SIZE)
+        .map(|index| classify_initial(magic_bitboards, index))
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in 0..KPK_SIZE {
+            if results[index] == UNKNOWN {
+                results[index] = classify(magic_bitboards, &results, index);
+                changed |= results[index] != UNKNOWN;
+            }
+        }
+    }
+
+    let mut bitbase = vec![0u64; KPK_SIZE.div_ceil(64)];
+    for (index, result) in results.iter().enumerate() {
+        if *result == WIN {
+            bitbase[index / 64] |= 1 << (index % 64);
+        }
+    }
+    bitbase
+}
+
+fn classify_initial(magic_bitboards: &MagicBitboards, index: usize) -> u8 {
+    let (weak_to_move, strong_king, weak_king, pawn) = get_position(index);
+    let strong_attacks = magic_bitboards.king_attacks[strong_king as usize];
+    let weak_attacks = magic_bitboards.king_attacks[weak_king as usize];
+    let pawn_attacks = magic_bitboards.w_pawn_capture[pawn as usize];
+    let promotion = pawn - 8;
+
+    if strong_king == weak_king
+        || strong_king == pawn
+        || weak_king == pawn
+        || strong_attacks & 1 << weak_king != 0
+        || (!weak_to_move && pawn_attacks & 1 << weak_king != 0)
+    {
+        return INVALID;
+    }
+    if !weak_to_move
+        && pawn / 8 == 1
+        && strong_king != promotion
+        && weak_king != promotion
+        && (weak_attacks & 1 << promotion == 0 || strong_attacks & 1 << promotion != 0)
+    {
+        return WIN;
+    }
+    if weak_to_move
+        && (weak_attacks & !(strong_attacks | pawn_attacks) == 0
+            || weak_attacks & !strong_attacks & 1 << pawn != 0)
+    {
+        return DRAW;
+    }
+    UNKNOWN
+}
+
+fn classify(magic_bitboards: &MagicBitboards, results: &[u8], index: usize) -> u8 {
+    let (weak_to_move, strong_king, weak_king, pawn) = get_position(index);
+    let mut result = INVALID;
+    if weak_to_move {
+        let mut moves = magic_bitboards.king_attacks[weak_king as usize];
+        while moves != 0 {
+            let square = moves.trailing_zeros() as u8;
+            moves &= moves - 1;
+            result |= results[get_index(false, strong_king, square, pawn)];
+        }
+    } else {
+        let mut moves = magic_bitboards.king_attacks[strong_king as usize];
+        while moves != 0 {
+            let square = moves.trailing_zeros() as u8;
+            moves &= moves - 1;
+            result |= results[get_index(true, square, weak_king, pawn)];
+        }
+        let push = pawn - 8;
+        if pawn / 8 > 1 && push != strong_king && push != weak_king {
+            result |= results[get_index(true, strong_king, weak_king, push)];
+            let double = pawn - 16;
+            if pawn / 8 == 6 && double != strong_king && double != weak_king {
+                result |= results[get_index(true, strong_king, weak_king, double)];
+            }
+        }
+    }
+
+    let (good, bad) = if weak_to_move {
+        (DRAW, WIN)
+    } else {
+        (WIN, DRAW)
+    };
+    if result & good != 0 {
+        good
+    } else if result & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board_rep::{
+            board::{KING, PAWN},
+            magic_bitboards::get_test_magic_bitboards,
+        },
+        tablebases::{
+            dtm::{Dtm, DtmTables},
+            retrograde::ensure_table,
+        },
+    };
+
+    #[test]
+    fn bitbase_matches_dtm() {
+        let magic_bitboards = get_test_magic_bitboards();
+        init_kpk(magic_bitboards);
+        let mut tables = DtmTables::default();
+        ensure_table("KPvK", &mut tables, None, magic_bitboards).unwrap();
+        let table = tables.get("KPvK").unwrap();
+        for index in 0..KPK_SIZE {
+            if classify_initial(magic_bitboards, index) == INVALID {
+                continue;
+            }
+            let (weak_to_move, strong_king, weak_king, pawn) = get_position(index);
+            let mut board = Board::new();
+            board.set_piece_at(strong_king, WHITE | KING);
+            board.set_piece_at(weak_king, BLACK | KING);
+            board.set_piece_at(pawn, WHITE | PAWN);
+            board.stm = if weak_to_move { BLACK } else { WHITE };
+            let expected = matches!(
+                (
+                    weak_to_move,
+                    table.get(&[strong_king, weak_king, pawn], weak_to_move)
+                ),
+                (false, Dtm::Win(_)) | (true, Dtm::Loss(_))
+            );
+            assert_eq!(kpk_probe(&board), Some(expected), "index {index}");
+        }
+    }
+}
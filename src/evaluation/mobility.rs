@@ -0,0 +1,210 @@
+use crate::{
+    board_rep::{
+        bit_operations::{count_bits, east_one, first_bit, first_bit_pop, west_one},
+        board::{BISHOP, BLACK, Board, CASTLING_RIGHTS, KNIGHT, QUEEN, ROOK, WHITE},
+        magic_bitboards::MagicBitboards,
+    },
+    evaluation::{
+        eval::{Side, get_piece_attacks},
+        params::{
+            BISHOP_MOBILITY_PARAMS, BISHOP_OUTPOST_PARAM, EvalTerms, KNIGHT_MOBILITY_PARAMS,
+            KNIGHT_OUTPOST_PARAM, QUEEN_MOBILITY_PARAMS, ROOK_MOBILITY_PARAMS, ROOK_SEVENTH_PARAM,
+            THREAT_BY_MINOR_PARAM, THREAT_BY_PAWN_PARAM, THREAT_BY_ROOK_PARAM, TRAPPED_MINOR_PARAM,
+            TRAPPED_ROOK_PARAM,
+        },
+        pawns::{get_front_span, get_pawn_attacks, get_relative_rank, get_stop_squares},
+    },
+};
+
+pub const MOBILITY: usize = 0;
+pub const OUTPOSTS: usize = 1;
+pub const TRAPPED: usize = 2;
+pub const ROOK_ON_SEVENTH: usize = 3;
+pub const THREATS: usize = 4;
+pub const MOBILITY_TERMS: usize = 5;
+pub const MOBILITY_TERM_NAMES: [&str; MOBILITY_TERMS] =
+    ["Mobility", "Outposts", "Trapped", "Rook on 7th", "Threats"];
+
+#[derive(Clone, Copy, Default)]
+pub struct MobilityEval {
+    pub terms: [[(i32, i32); 2]; MOBILITY_TERMS],
+}
+
+impl MobilityEval {
+    pub fn get_total(&self) -> (i32, i32) {
+        self.terms.iter().fold((0, 0), |(mg, eg), [white, black]| {
+            (mg + white.0 - black.0, eg + white.1 - black.1)
+        })
+    }
+}
+
+pub fn evaluate_mobility(board: &Board, magic_bitboards: &MagicBitboards) -> MobilityEval {
+    let mut mobility_eval = MobilityEval::default();
+    add_mobility_terms(board, magic_bitboards, &mut mobility_eval.terms);
+    mobility_eval
+}
+
+/// Adds the piece activity terms to `terms`.
+pub fn add_mobility_terms<T: EvalTerms>(
+    board: &Board,
+    magic_bitboards: &MagicBitboards,
+    terms: &mut T,
+) {
+    let occupancy = board.all_occupancy;
+    for (side, color) in [WHITE, BLACK].into_iter().enumerate() {
+        let enemy_color = color ^ (WHITE | BLACK);
+        let own = Side::new(board, color);
+        let enemy = Side::new(board, enemy_color);
+        let own_pawn_attacks = get_pawn_attacks(own.pawns, color);
+        let enemy_pawn_attacks = get_pawn_attacks(enemy.pawns, enemy_color);
+        let blocked_pawns = own.pawns & get_stop_squares(occupancy, enemy_color);
+        let mobility_area = !(enemy_pawn_attacks | blocked_pawns | own.king);
+        let enemy_pawn_span = get_front_span(enemy.pawns, enemy_color);
+        let safe_from_pawns = !(east_one(enemy_pawn_span) | west_one(enemy_pawn_span));
+
+        let mut minor_attacks = 0;
+        let mut rook_attacks = 0;
+        for (piece, mut pieces) in [
+            (KNIGHT, own.knights),
+            (BISHOP, own.bishops),
+            (ROOK, own.rooks),
+            (QUEEN, own.queens),
+        ] {
+            while pieces != 0 {
+                let square = first_bit_pop(&mut pieces);
+                let attacks = get_piece_attacks(magic_bitboards, piece, square, occupancy);
+                let count = count_bits(attacks & mobility_area) as usize;
+                let params = match piece {
+                    KNIGHT => KNIGHT_MOBILITY_PARAMS,
+                    BISHOP => BISHOP_MOBILITY_PARAMS,
+                    ROOK => ROOK_MOBILITY_PARAMS,
+                    _ => QUEEN_MOBILITY_PARAMS,
+                };
+                terms.add(MOBILITY, side, params + count, 1);
+
+                match piece {
+                    KNIGHT | BISHOP => {
+                        minor_attacks |= attacks;
+                        let rank = get_relative_rank(square, color);
+                        let bit = 1 << square;
+                        if (3..=5).contains(&rank)
+                            && bit & own_pawn_attacks != 0
+                            && bit & safe_from_pawns != 0
+                        {
+                            let param = if piece == KNIGHT {
+                                KNIGHT_OUTPOST_PARAM
+                            } else {
+                                BISHOP_OUTPOST_PARAM
+                            };
+                            terms.add(OUTPOSTS, side, param, 1);
+                        }
+                        if count == 0 && bit & own_pawn_attacks == 0 {
+                            terms.add(TRAPPED, side, TRAPPED_MINOR_PARAM, 1);
+                        }
+                    }
+                    ROOK => {
+                        rook_attacks |= attacks;
+                        if count <= 3 && is_rook_trapped(board, square, own.king, color) {
+                            terms.add(TRAPPED, side, TRAPPED_ROOK_PARAM, 1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let seventh_rank = if color == WHITE {
+            0xFF << 8
+        } else {
+            0xFF << 48
+        };
+        let eighth_rank = if color == WHITE { 0xFF } else { 0xFF << 56 };
+        if enemy.pawns & seventh_rank != 0 || enemy.king & eighth_rank != 0 {
+            let count = count_bits(own.rooks & seventh_rank) as i32;
+            terms.add(ROOK_ON_SEVENTH, side, ROOK_SEVENTH_PARAM, count);
+        }
+
+        let minors = enemy.knights | enemy.bishops;
+        for (threatened, param) in [
+            (
+                own_pawn_attacks & (minors | enemy.rooks | enemy.queens),
+                THREAT_BY_PAWN_PARAM,
+            ),
+            (
+                minor_attacks & (enemy.rooks | enemy.queens),
+                THREAT_BY_MINOR_PARAM,
+            ),
+            (rook_attacks & enemy.queens, THREAT_BY_ROOK_PARAM),
+        ] {
+            terms.add(THREATS, side, param, count_bits(threatened) as i32);
+        }
+    }
+}
+
+fn is_rook_trapped(board: &Board, square: u8, king: u64, color: u8) -> bool {
+    let can_castle = CASTLING_RIGHTS
+        .iter()
+        .zip(board.castling_rooks)
+        .any(|(right, rook)| board.castling & right != 0 && rook == square);
+    if king == 0 || can_castle {
+        return false;
+    }
+    let king_square = first_bit(king);
+    if get_relative_rank(square, color) != 0 || get_relative_rank(king_square, color) != 0 {
+        return false;
+    }
+    let king_file = king_square % 8;
+    let rook_file = square % 8;
+    (king_file >= 4 && rook_file > king_file) || (king_file < 4 && rook_file < king_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    /// White's and black's score for `term`, summed over both phases.
+    fn get_term(fen: &str, term: usize) -> [i32; 2] {
+        let magic_bitboards = get_test_magic_bitboards();
+        let mut board = Board::new();
+        board.set_from_fen(fen, magic_bitboards);
+        evaluate_mobility(&board, magic_bitboards).terms[term].map(|(mg, eg)| mg + eg)
+    }
+
+    #[test]
+    fn outpost_is_a_bonus() {
+        let [white, black] = get_term("4k3/8/8/3N4/4P3/8/8/4K3 w - - 0 1", OUTPOSTS);
+        assert!(white > 0);
+        assert_eq!(black, 0);
+        let [white, _] = get_term("4k3/4p3/8/3N4/4P3/8/8/4K3 w - - 0 1", OUTPOSTS);
+        assert_eq!(white, 0);
+    }
+
+    #[test]
+    fn trapped_rook_is_a_penalty() {
+        let [white, black] = get_term("4k3/8/8/8/8/8/6PP/5K1R w - - 0 1", TRAPPED);
+        assert!(white < 0);
+        assert_eq!(black, 0);
+        let [white, _] = get_term("4k3/8/8/8/8/8/6PP/4K2R w K - 0 1", TRAPPED);
+        assert_eq!(white, 0);
+    }
+
+    #[test]
+    fn rook_on_seventh_is_a_bonus() {
+        let [white, black] = get_term("4k3/pR6/8/8/8/8/8/4K3 w - - 0 1", ROOK_ON_SEVENTH);
+        assert!(white > 0);
+        assert_eq!(black, 0);
+        let [white, _] = get_term("8/1R6/4k3/8/8/8/8/4K3 w - - 0 1", ROOK_ON_SEVENTH);
+        assert_eq!(white, 0);
+    }
+
+    #[test]
+    fn threat_is_a_bonus() {
+        let [white, black] = get_term("4k3/8/8/4n3/3P4/8/8/4K3 w - - 0 1", THREATS);
+        assert!(white > 0);
+        assert_eq!(black, 0);
+        let [white, black] = get_term("4k3/8/3q4/8/8/3R4/8/4K3 w - - 0 1", THREATS);
+        assert!(white > 0);
+        assert_eq!(black, 0);
+    }
+}
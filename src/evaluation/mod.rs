@@ -0,0 +1,8 @@
+pub mod endgame;
+pub mod eval;
+pub mod king_safety;
+pub mod kpk;
+pub mod mobility;
+pub mod nnue;
+pub mod params;
+pub mod pawns;
@@ -0,0 +1,525 @@
+use std::{
+    fs,
+    sync::{Arc, RwLock},
+};
+
+use crate::board_rep::{
+    bit_operations::{count_bits, first_bit, first_bit_pop},
+    board::{
+        BISHOP, BLACK, Board, COLOR_MASK, KING, KNIGHT, PAWN, PIECE_MASK, PieceDelta, QUEEN, ROOK,
+        WHITE,
+    },
+    rng::Rng,
+};
+
+pub const HIDDEN_SIZE: usize = 256;
+pub const INPUT_BUCKETS: usize = 4;
+pub const OUTPUT_BUCKETS: usize = 8;
+pub const FEATURES_PER_BUCKET: usize = 768;
+pub const INPUT_SIZE: usize = INPUT_BUCKETS * FEATURES_PER_BUCKET;
+pub const DEFAULT_EVAL_FILE: &str = "nuvo.nnue";
+
+const QA: i32 = 255;
+const QB: i32 = 64;
+const SCALE: i32 = 400;
+const NNUE_MAGIC: &[u8; 8] = b"NUVONNUE";
+
+#[rustfmt::skip]
+const KING_BUCKETS: [usize; 64] = [
+    0, 0, 1, 1, 1, 1, 0, 0,
+    2, 2, 2, 2, 2, 2, 2, 2,
+    3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 3, 3, 3, 3, 3,
+];
+
+static NETWORK: RwLock<Option<Arc<Network>>> = RwLock::new(None);
+
+pub fn set_network(network: Option<Network>) {
+    *NETWORK.write().unwrap() = network.map(Arc::new);
+}
+
+pub fn get_network() -> Option<Arc<Network>> {
+    NETWORK.read().unwrap().clone()
+}
+
+#[derive(Clone, Copy)]
+#[repr(C, align(64))]
+pub struct Accumulator {
+    pub values: [[i16; HIDDEN_SIZE]; 2],
+}
+
+impl Default for Accumulator {
+    fn default() -> Self {
+        Accumulator::new()
+    }
+}
+
+impl Accumulator {
+    pub fn new() -> Self {
+        Accumulator {
+            values: [[0; HIDDEN_SIZE]; 2],
+        }
+    }
+}
+
+pub struct AccumulatorStack {
+    accumulators: Vec<Accumulator>,
+    len: usize,
+}
+
+impl Default for AccumulatorStack {
+    fn default() -> Self {
+        AccumulatorStack::new()
+    }
+}
+
+impl AccumulatorStack {
+    pub fn new() -> Self {
+        AccumulatorStack {
+            accumulators: vec![Accumulator::new()],
+            len: 1,
+        }
+    }
+
+    pub fn reset(&mut self, network: &Network, board: &Board) {
+        self.len = 1;
+        network.refresh(board, &mut self.accumulators[0]);
+    }
+
+    pub fn push(&mut self, network: &Network, board: &Board, delta: &PieceDelta) {
+        if self.len == self.accumulators.len() {
+            self.accumulators.push(Accumulator::new());
+        }
+        let (previous, next) = self.accumulators.split_at_mut(self.len);
+        next[0] = previous[self.len - 1];
+        network.update(board, delta, &mut next[0]);
+        self.len += 1;
+
+        #[cfg(debug_assertions)]
+        {
+            let mut refreshed = Accumulator::new();
+            network.refresh(board, &mut refreshed);
+            assert!(
+                refreshed.values == self.get_current().values,
+                "incremental NNUE accumulator differs from a full refresh"
+            );
+        }
+    }
+
+    pub fn pop(&mut self) {
+        self.len = (self.len - 1).max(1);
+    }
+
+    pub fn get_current(&self) -> &Accumulator {
+        &self.accumulators[self.len - 1]
+    }
+}
+
+pub struct Network {
+    feature_weights: Vec<i16>,
+    feature_bias: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: Vec<i16>,
+}
+
+impl Network {
+    pub fn load(path: &str) -> Result<Network, String> {
+        let bytes = fs::read(path).map_err(|error| format!("failed to read {path}: {error}"))?;
+        Network::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, String> {
+        let expected = NNUE_MAGIC.len() + Network::get_parameter_count() * 2;
+        if bytes.len() != expected {
+            return Err(format!(
+                "network has {} bytes, expected {expected}",
+                bytes.len()
+            ));
+        }
+        if &bytes[..NNUE_MAGIC.len()] != NNUE_MAGIC {
+            return Err("network has an invalid header".to_string());
+        }
+
+        let mut values = bytes[NNUE_MAGIC.len()..]
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]));
+        let mut take = |count: usize| values.by_ref().take(count).collect::<Vec<i16>>();
+        Ok(Network {
+            feature_weights: take(INPUT_SIZE * HIDDEN_SIZE),
+            feature_bias: take(HIDDEN_SIZE),
+            output_weights: take(OUTPUT_BUCKETS * 2 * HIDDEN_SIZE),
+            output_bias: take(OUTPUT_BUCKETS),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = NNUE_MAGIC.to_vec();
+        for values in [
+            &self.feature_weights,
+            &self.feature_bias,
+            &self.output_weights,
+            &self.output_bias,
+        ] {
+            for value in values {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    pub fn random(seed: u64) -> Network {
+        let mut rng = Rng::new();
+        rng.seed(seed);
+        let mut random = |count: usize, range: u64| {
+            (0..count)
+                .map(|_| (rng.next_u64() % (range * 2)) as i16 - range as i16)
+                .collect::<Vec<i16>>()
+        };
+        Network {
+            feature_weights: random(INPUT_SIZE * HIDDEN_SIZE, 64),
+            feature_bias: random(HIDDEN_SIZE, 64),
+            output_weights: random(OUTPUT_BUCKETS * 2 * HIDDEN_SIZE, 64),
+            output_bias: random(OUTPUT_BUCKETS, 4096),
+        }
+    }
+
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        let mut accumulator = Accumulator::new();
+        self.refresh(board, &mut accumulator);
+        self.forward(&accumulator, board)
+    }
+
+    pub fn refresh(&self, board: &Board, accumulator: &mut Accumulator) {
+        for (perspective, values) in [WHITE, BLACK].iter().zip(accumulator.values.iter_mut()) {
+            self.refresh_perspective(board, *perspective, values);
+        }
+    }
+
+    pub fn refresh_perspective(
+        &self,
+        board: &Board,
+        perspective: u8,
+        values: &mut [i16; HIDDEN_SIZE],
+    ) {
+        values.copy_from_slice(&self.feature_bias);
+        let king_square = get_king_square(board, perspective);
+        for (mut bitboard, piece) in get_piece_bitboards(board) {
+            while bitboard != 0 {
+                let square = first_bit_pop(&mut bitboard);
+                let feature = get_feature_index(perspective, king_square, piece, square);
+                self.add_feature(values, feature);
+            }
+        }
+    }
+
+    pub fn update(&self, board: &Board, delta: &PieceDelta, accumulator: &mut Accumulator) {
+        for (perspective, values) in [WHITE, BLACK].iter().zip(accumulator.values.iter_mut()) {
+            if delta.moves_king(*perspective) {
+                self.refresh_perspective(board, *perspective, values);
+                continue;
+            }
+            let king_square = get_king_square(board, *perspective);
+            for (piece, square) in delta.get_removed() {
+                let feature = get_feature_index(*perspective, king_square, *piece, *square);
+                self.remove_feature(values, feature);
+            }
+            for (piece, square) in delta.get_added() {
+                let feature = get_feature_index(*perspective, king_square, *piece, *square);
+                self.add_feature(values, feature);
+            }
+        }
+    }
+
+    pub fn add_feature(&self, values: &mut [i16; HIDDEN_SIZE], feature: usize) {
+        let weights = &self.feature_weights[feature * HIDDEN_SIZE..(feature + 1) * HIDDEN_SIZE];
+        for (value, weight) in values.iter_mut().zip(weights) {
+            *value = value.wrapping_add(*weight);
+        }
+    }
+
+    pub fn remove_feature(&self, values: &mut [i16; HIDDEN_SIZE], feature: usize) {
+        let weights = &self.feature_weights[feature * HIDDEN_SIZE..(feature + 1) * HIDDEN_SIZE];
+        for (value, weight) in values.iter_mut().zip(weights) {
+            *value = value.wrapping_sub(*weight);
+        }
+    }
+
+    pub fn forward(&self, accumulator: &Accumulator, board: &Board) -> i32 {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if std::arch::is_x86_feature_detected!("avx2") {
+                // SAFETY: AVX2 support was checked at runtime.
+                return unsafe { self.forward_avx2(accumulator, board) };
+            }
+        }
+        self.forward_scalar(accumulator, board)
+    }
+
+    pub fn forward_scalar(&self, accumulator: &Accumulator, board: &Board) -> i32 {
+        let (us, them, weights, bias) = self.get_forward_inputs(accumulator, board);
+        let mut sum: i32 = 0;
+        for (value, weight) in us.iter().chain(them).zip(weights) {
+            let clipped = (*value as i32).clamp(0, QA);
+            sum = sum.wrapping_add(clipped * *weight as i32);
+        }
+        get_output(sum, bias)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn forward_avx2(&self, accumulator: &Accumulator, board: &Board) -> i32 {
+        use std::arch::x86_64::{
+            __m256i, _mm_add_epi32, _mm_cvtsi128_si32, _mm_shuffle_epi32, _mm256_add_epi32,
+            _mm256_castsi256_si128, _mm256_extracti128_si256, _mm256_loadu_si256,
+            _mm256_madd_epi16, _mm256_max_epi16, _mm256_min_epi16, _mm256_set1_epi16,
+            _mm256_setzero_si256,
+        };
+
+        let (us, them, weights, bias) = self.get_forward_inputs(accumulator, board);
+        let zero = _mm256_setzero_si256();
+        let qa = _mm256_set1_epi16(QA as i16);
+        let mut sum = _mm256_setzero_si256();
+        for (half, values) in [us, them].iter().enumerate() {
+            let weights = &weights[half * HIDDEN_SIZE..(half + 1) * HIDDEN_SIZE];
+            for i in (0..HIDDEN_SIZE).step_by(16) {
+                // SAFETY: both slices hold HIDDEN_SIZE values, a multiple of 16.
+                let (value, weight) = unsafe {
+                    (
+                        _mm256_loadu_si256(values.as_ptr().add(i) as *const __m256i),
+                        _mm256_loadu_si256(weights.as_ptr().add(i) as *const __m256i),
+                    )
+                };
+                let clipped = _mm256_min_epi16(_mm256_max_epi16(value, zero), qa);
+                sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, weight));
+            }
+        }
+        let sum = _mm_add_epi32(
+            _mm256_castsi256_si128(sum),
+            _mm256_extracti128_si256(sum, 1),
+        );
+        let sum = _mm_add_epi32(sum, _mm_shuffle_epi32(sum, 0b01_00_11_10));
+        let sum = _mm_add_epi32(sum, _mm_shuffle_epi32(sum, 0b10_11_00_01));
+        get_output(_mm_cvtsi128_si32(sum), bias)
+    }
+
+    fn get_forward_inputs<'a>(
+        &'a self,
+        accumulator: &'a Accumulator,
+        board: &Board,
+    ) -> (
+        &'a [i16; HIDDEN_SIZE],
+        &'a [i16; HIDDEN_SIZE],
+        &'a [i16],
+        i32,
+    ) {
+        let (us, them) = if board.stm == WHITE {
+            (&accumulator.values[0], &accumulator.values[1])
+        } else {
+            (&accumulator.values[1], &accumulator.values[0])
+        };
+        let bucket = get_output_bucket(board);
+        let weights =
+            &self.output_weights[bucket * 2 * HIDDEN_SIZE..(bucket + 1) * 2 * HIDDEN_SIZE];
+        (us, them, weights, self.output_bias[bucket] as i32)
+    }
+
+    fn get_parameter_count() -> usize {
+        INPUT_SIZE * HIDDEN_SIZE + HIDDEN_SIZE + OUTPUT_BUCKETS * 2 * HIDDEN_SIZE + OUTPUT_BUCKETS
+    }
+}
+
+pub fn get_feature_index(perspective: u8, king_square: u8, piece: u8, square: u8) -> usize {
+    let flip = |square: u8| {
+        if perspective == WHITE {
+            square ^ 56
+        } else {
+            square
+        }
+    };
+    let king_square = flip(king_square);
+    let mirror = if king_square % 8 >= 4 { 7 } else { 0 };
+    let bucket = KING_BUCKETS[(king_square ^ mirror) as usize];
+    let side = if piece & COLOR_MASK == perspective {
+        0
+    } else {
+        1
+    };
+    let piece_index = (piece & PIECE_MASK) as usize - 1;
+    let square = (flip(square) ^ mirror) as usize;
+    bucket * FEATURES_PER_BUCKET + side * 384 + piece_index * 64 + square
+}
+
+pub fn get_king_square(board: &Board, perspective: u8) -> u8 {
+    if perspective == WHITE {
+        first_bit(board.w_king)
+    } else {
+        first_bit(board.b_king)
+    }
+}
+
+fn get_output(sum: i32, bias: i32) -> i32 {
+    ((sum as i64 + bias as i64) * SCALE as i64 / (QA * QB) as i64) as i32
+}
+
+fn get_output_bucket(board: &Board) -> usize {
+    let pieces = count_bits(board.all_occupancy) as usize;
+    let divisor = 32_usize.div_ceil(OUTPUT_BUCKETS);
+    (pieces.saturating_sub(2) / divisor).min(OUTPUT_BUCKETS - 1)
+}
+
+fn get_piece_bitboards(board: &Board) -> [(u64, u8); 12] {
+    [
+        (board.w_pawns, WHITE | PAWN),
+        (board.w_knights, WHITE | KNIGHT),
+        (board.w_bishops, WHITE | BISHOP),
+        (board.w_rooks, WHITE | ROOK),
+        (board.w_queens, WHITE | QUEEN),
+        (board.w_king, WHITE | KING),
+        (board.b_pawns, BLACK | PAWN),
+        (board.b_knights, BLACK | KNIGHT),
+        (board.b_bishops, BLACK | BISHOP),
+        (board.b_rooks, BLACK | ROOK),
+        (board.b_queens, BLACK | QUEEN),
+        (board.b_king, BLACK | KING),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board_rep::magic_bitboards::get_test_magic_bitboards, engine::search::get_root_moves,
+    };
+
+    const NNUE_CHECK_SEED: u64 = 20240601;
+
+    /// Outputs of `Network::random(NNUE_CHECK_SEED)`.
+    const EXPECTED_OUTPUTS: [(&str, i32); 8] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            -1642,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 10",
+            -495,
+        ),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 11", 179),
+        (
+            "4rrk1/pp1n3p/3q2pQ/2p1pb2/2PP4/2P3N1/P2B2PP/4RRK1 b - - 7 19",
+            -1680,
+        ),
+        (
+            "3r1rk1/p5pp/bpp1pp2/8/q1PP1P2/b3P3/P2NQRPP/1R2B1K1 b - - 6 22",
+            4542,
+        ),
+        ("8/3p4/p1bk3p/Pp6/1Kp1PpPp/2P2P1P/2P5/5B2 b - - 0 1", -4868),
+        ("8/8/8/8/8/6k1/6p1/6K1 w - - 0 1", -1165),
+        ("7k/7P/6K1/8/3B4/8/8/8 b - - 0 1", -1127),
+    ];
+
+    /// Evaluates straight from the feature definition, without accumulators or i16 arithmetic.
+    fn evaluate_reference(network: &Network, board: &Board) -> i32 {
+        let bucket = get_output_bucket(board);
+        let mut sum = 0;
+        for (half, perspective) in [board.stm, board.stm ^ COLOR_MASK].into_iter().enumerate() {
+            let king_square = get_king_square(board, perspective);
+            let mut values: Vec<i32> = network.feature_bias.iter().map(|v| *v as i32).collect();
+            for (mut bitboard, piece) in get_piece_bitboards(board) {
+                while bitboard != 0 {
+                    let square = first_bit_pop(&mut bitboard);
+                    let feature = get_feature_index(perspective, king_square, piece, square);
+                    for (i, value) in values.iter_mut().enumerate() {
+                        *value += network.feature_weights[feature * HIDDEN_SIZE + i] as i32;
+                    }
+                }
+            }
+            for (i, value) in values.iter().enumerate() {
+                let weight = network.output_weights[(bucket * 2 + half) * HIDDEN_SIZE + i];
+                sum += (*value).clamp(0, QA) * weight as i32;
+            }
+        }
+        get_output(sum, network.output_bias[bucket] as i32)
+    }
+
+    #[test]
+    fn random_network_outputs() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let network = Network::random(NNUE_CHECK_SEED);
+        for (fen, expected) in EXPECTED_OUTPUTS {
+            let mut board = Board::new();
+            board.set_from_fen(fen, magic_bitboards);
+            let mut accumulator = Accumulator::new();
+            network.refresh(&board, &mut accumulator);
+            assert_eq!(
+                network.forward_scalar(&accumulator, &board),
+                expected,
+                "{fen}"
+            );
+            #[cfg(target_arch = "x86_64")]
+            if std::arch::is_x86_feature_detected!("avx2") {
+                // SAFETY: AVX2 support was checked at runtime.
+                let avx2 = unsafe { network.forward_avx2(&accumulator, &board) };
+                assert_eq!(avx2, expected, "{fen} avx2");
+            }
+            assert_eq!(network.forward(&accumulator, &board), expected, "{fen}");
+            assert_eq!(
+                evaluate_reference(&network, &board),
+                expected,
+                "{fen} reference"
+            );
+        }
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let network = Network::random(NNUE_CHECK_SEED);
+        let bytes = network.to_bytes();
+        let loaded = Network::from_bytes(&bytes).expect("serialized network loads");
+        assert_eq!(loaded.to_bytes(), bytes);
+        assert!(Network::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn incremental_updates_match_refresh() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let network = Network::random(NNUE_CHECK_SEED);
+        for (fen, _) in EXPECTED_OUTPUTS {
+            let mut board = Board::new();
+            board.set_from_fen(fen, magic_bitboards);
+            let mut stack = AccumulatorStack::new();
+            stack.reset(&network, &board);
+            let root_moves = get_root_moves(&board, magic_bitboards, &[]);
+            for c_move in &root_moves.moves[..root_moves.count] {
+                let mut new_board = board;
+                let delta = new_board.make_move_with_delta(*c_move);
+                stack.push(&network, &new_board, &delta);
+                assert_eq!(
+                    network.forward(stack.get_current(), &new_board),
+                    network.evaluate(&new_board),
+                    "{fen} {}",
+                    c_move.uci(false)
+                );
+                let replies = get_root_moves(&new_board, magic_bitboards, &[]);
+                for reply in &replies.moves[..replies.count] {
+                    let mut reply_board = new_board;
+                    let delta = reply_board.make_move_with_delta(*reply);
+                    stack.push(&network, &reply_board, &delta);
+                    let mut refreshed = Accumulator::new();
+                    network.refresh(&reply_board, &mut refreshed);
+                    assert!(
+                        refreshed.values == stack.get_current().values,
+                        "{fen} {} {}",
+                        c_move.uci(false),
+                        reply.uci(false)
+                    );
+                    stack.pop();
+                }
+                stack.pop();
+            }
+        }
+    }
+}
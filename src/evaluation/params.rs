@@ -0,0 +1,485 @@
+pub type Weight = (i32, i32);
+
+pub const MATERIAL_PARAMS: usize = 0;
+pub const PST_PARAMS: usize = MATERIAL_PARAMS + 5;
+pub const DOUBLED_PARAM: usize = PST_PARAMS + 6 * 64;
+pub const ISOLATED_PARAM: usize = DOUBLED_PARAM + 1;
+pub const BACKWARD_PARAM: usize = ISOLATED_PARAM + 1;
+pub const PASSED_PARAMS: usize = BACKWARD_PARAM + 1;
+pub const CONNECTED_PARAMS: usize = PASSED_PARAMS + 8;
+pub const CANDIDATE_PARAMS: usize = CONNECTED_PARAMS + 8;
+pub const KNIGHT_MOBILITY_PARAMS: usize = CANDIDATE_PARAMS + 8;
+pub const BISHOP_MOBILITY_PARAMS: usize = KNIGHT_MOBILITY_PARAMS + 9;
+pub const ROOK_MOBILITY_PARAMS: usize = BISHOP_MOBILITY_PARAMS + 14;
+pub const QUEEN_MOBILITY_PARAMS: usize = ROOK_MOBILITY_PARAMS + 15;
+pub const KNIGHT_OUTPOST_PARAM: usize = QUEEN_MOBILITY_PARAMS + 28;
+pub const BISHOP_OUTPOST_PARAM: usize = KNIGHT_OUTPOST_PARAM + 1;
+pub const TRAPPED_MINOR_PARAM: usize = BISHOP_OUTPOST_PARAM + 1;
+pub const TRAPPED_ROOK_PARAM: usize = TRAPPED_MINOR_PARAM + 1;
+pub const ROOK_SEVENTH_PARAM: usize = TRAPPED_ROOK_PARAM + 1;
+pub const THREAT_BY_PAWN_PARAM: usize = ROOK_SEVENTH_PARAM + 1;
+pub const THREAT_BY_MINOR_PARAM: usize = THREAT_BY_PAWN_PARAM + 1;
+pub const THREAT_BY_ROOK_PARAM: usize = THREAT_BY_MINOR_PARAM + 1;
+pub const ZONE_ATTACK_PARAMS: usize = THREAT_BY_ROOK_PARAM + 1;
+pub const SAFE_CHECK_PARAMS: usize = ZONE_ATTACK_PARAMS + 4;
+pub const SHIELD_PARAMS: usize = SAFE_CHECK_PARAMS + 4;
+pub const STORM_PARAMS: usize = SHIELD_PARAMS + 8;
+pub const BLOCKED_STORM_PARAM: usize = STORM_PARAMS + 8;
+pub const SEMI_OPEN_FILE_PARAM: usize = BLOCKED_STORM_PARAM + 1;
+pub const OPEN_FILE_PARAM: usize = SEMI_OPEN_FILE_PARAM + 1;
+pub const PARAM_COUNT: usize = OPEN_FILE_PARAM + 1;
+
+/// Every handcrafted evaluation weight, laid out by the offsets above.
+pub static PARAMS: [Weight; PARAM_COUNT] = get_default_params();
+
+pub const MG_MATERIAL: [i32; 7] = [0, 82, 337, 365, 477, 1025, 0];
+pub const EG_MATERIAL: [i32; 7] = [0, 94, 281, 297, 512, 936, 0];
+
+#[rustfmt::skip]
+const MG_PAWN_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     98, 134,  61,  95,  68, 126,  34, -11,
+     -6,   7,  26,  31,  65,  56,  25, -20,
+    -14,  13,   6,  21,  23,  12,  17, -23,
+    -27,  -2,  -5,  12,  17,   6,  10, -25,
+    -26,  -4,  -4, -10,   3,   3,  33, -12,
+    -35,  -1, -20, -23, -15,  24,  38, -22,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const EG_PAWN_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+    178, 173, 158, 134, 147, 132, 165, 187,
+     94, 100,  85,  67,  56,  53,  82,  84,
+     32,  24,  13,   5,  -2,   4,  17,  17,
+     13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+      4,   7,  -6,   1,   0,  -5,  -1,  -8,
+     13,   8,   8,  10,  13,   0,   2,  -7,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const MG_KNIGHT_TABLE: [i32; 64] = [
+   -167, -89, -34, -49,  61, -97, -15,-107,
+    -73, -41,  72,  36,  23,  62,   7, -17,
+    -47,  60,  37,  65,  84, 129,  73,  44,
+     -9,  17,  19,  53,  37,  69,  18,  22,
+    -13,   4,  16,  13,  28,  19,  21,  -8,
+    -23,  -9,  12,  10,  19,  17,  25, -16,
+    -29, -53, -12,  -3,  -1,  18, -14, -19,
+   -105, -21, -58, -33, -17, -28, -19, -23,
+];
+
+#[rustfmt::skip]
+const EG_KNIGHT_TABLE: [i32; 64] = [
+    -58, -38, -13, -28, -31, -27, -63, -99,
+    -25,  -8, -25,  -2,  -9, -25, -24, -52,
+    -24, -20,  10,   9,  -1,  -9, -19, -41,
+    -17,   3,  22,  22,  22,  11,   8, -18,
+    -18,  -6,  16,  25,  16,  17,   4, -18,
+    -23,  -3,  -1,  15,  10,  -3, -20, -22,
+    -42, -20, -10,  -5,  -2, -20, -23, -44,
+    -29, -51, -23, -15, -22, -18, -50, -64,
+];
+
+#[rustfmt::skip]
+const MG_BISHOP_TABLE: [i32; 64] = [
+    -29,   4, -82, -37, -25, -42,   7,  -8,
+    -26,  16, -18, -13,  30,  59,  18, -47,
+    -16,  37,  43,  40,  35,  50,  37,  -2,
+     -4,   5,  19,  50,  37,  37,   7,  -2,
+     -6,  13,  13,  26,  34,  12,  10,   4,
+      0,  15,  15,  15,  14,  27,  18,  10,
+      4,  15,  16,   0,   7,  21,  33,   1,
+    -33,  -3, -14, -21, -13, -12, -39, -21,
+];
+
+#[rustfmt::skip]
+const EG_BISHOP_TABLE: [i32; 64] = [
+    -14, -21, -11,  -8,  -7,  -9, -17, -24,
+     -8,  -4,   7, -12,  -3, -13,  -4, -14,
+      2,  -8,   0,  -1,  -2,   6,   0,   4,
+     -3,   9,  12,   9,  14,  10,   3,   2,
+     -6,   3,  13,  19,   7,  10,  -3,  -9,
+    -12,  -3,   8,  10,  13,   3,  -7, -15,
+    -14, -18,  -7,  -1,   4,  -9, -15, -27,
+    -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+];
+
+#[rustfmt::skip]
+const MG_ROOK_TABLE: [i32; 64] = [
+     32,  42,  32,  51,  63,   9,  31,  43,
+     27,  32,  58,  62,  80,  67,  26,  44,
+     -5,  19,  26,  36,  17,  45,  61,  16,
+    -24, -11,   7,  26,  24,  35,  -8, -20,
+    -36, -26, -12,  -1,   9,  -7,   6, -23,
+    -45, -25, -16, -17,   3,   0,  -5, -33,
+    -44, -16, -20,  -9,  -1,  11,  -6, -71,
+    -19, -13,   1,  17,  16,   7, -37, -26,
+];
+
+#[rustfmt::skip]
+const EG_ROOK_TABLE: [i32; 64] = [
+     13,  10,  18,  15,  12,  12,   8,   5,
+     11,  13,  13,  11,  -3,   3,   8,   3,
+      7,   7,   7,   5,   4,  -3,  -5,  -3,
+      4,   3,  13,   1,   2,   1,  -1,   2,
+      3,   5,   8,   4,  -5,  -6,  -8, -11,
+     -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+     -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+     -9,   2,   3,  -1,  -5, -13,   4, -20,
+];
+
+#[rustfmt::skip]
+const MG_QUEEN_TABLE: [i32; 64] = [
+    -28,   0,  29,  12,  59,  44,  43,  45,
+    -24, -39,  -5,   1, -16,  57,  28,  54,
+    -13, -17,   7,   8,  29,  56,  47,  57,
+    -27, -27, -16, -16,  -1,  17,  -2,   1,
+     -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+    -14,   2, -11,  -2,  -5,   2,  14,   5,
+    -35,  -8,  11,   2,   8,  15,  -3,   1,
+     -1, -18,  -9,  10, -15, -25, -31, -50,
+];
+
+#[rustfmt::skip]
+const EG_QUEEN_TABLE: [i32; 64] = [
+     -9,  22,  22,  27,  27,  19,  10,  20,
+    -17,  20,  32,  41,  58,  25,  30,   0,
+    -20,   6,   9,  49,  47,  35,  19,   9,
+      3,  22,  24,  45,  57,  40,  57,  36,
+    -18,  28,  19,  47,  31,  34,  39,  23,
+    -16, -27,  15,   6,   9,  17,  10,   5,
+    -22, -23, -30, -16, -16, -23, -36, -32,
+    -33, -28, -22, -43,  -5, -32, -20, -41,
+];
+
+#[rustfmt::skip]
+const MG_KING_TABLE: [i32; 64] = [
+    -65,  23,  16, -15, -56, -34,   2,  13,
+     29,  -1, -20,  -7,  -8,  -4, -38, -29,
+     -9,  24,   2, -16, -20,   6,  22, -22,
+    -17, -20, -12, -27, -30, -25, -14, -36,
+    -49,  -1, -27, -39, -46, -44, -33, -51,
+    -14, -14, -22, -46, -44, -30, -15, -27,
+      1,   7,  -8, -64, -43, -16,   9,   8,
+    -15,  36,  12, -54,   8, -28,  24,  14,
+];
+
+#[rustfmt::skip]
+const EG_KING_TABLE: [i32; 64] = [
+    -74, -35, -18, -18, -11,  15,   4, -17,
+    -12,  17,  14,  17,  17,  38,  23,  11,
+     10,  17,  23,  15,  20,  45,  44,  13,
+     -8,  22,  24,  27,  26,  33,  26,   3,
+    -18,  -4,  21,  24,  27,  23,   9, -11,
+    -19,  -3,  11,  21,  23,  16,   7,  -9,
+    -27, -11,   4,  13,  14,   4,  -5, -17,
+    -53, -34, -21, -11, -28, -14, -24, -43,
+];
+
+const MG_TABLES: [&[i32; 64]; 7] = [
+    &[0; 64],
+    &MG_PAWN_TABLE,
+    &MG_KNIGHT_TABLE,
+    &MG_BISHOP_TABLE,
+    &MG_ROOK_TABLE,
+    &MG_QUEEN_TABLE,
+    &MG_KING_TABLE,
+];
+
+const EG_TABLES: [&[i32; 64]; 7] = [
+    &[0; 64],
+    &EG_PAWN_TABLE,
+    &EG_KNIGHT_TABLE,
+    &EG_BISHOP_TABLE,
+    &EG_ROOK_TABLE,
+    &EG_QUEEN_TABLE,
+    &EG_KING_TABLE,
+];
+
+const DOUBLED_PENALTY: Weight = (-10, -20);
+const ISOLATED_PENALTY: Weight = (-8, -12);
+const BACKWARD_PENALTY: Weight = (-6, -10);
+#[rustfmt::skip]
+const PASSED_BONUS: [Weight; 8] = [
+    (0, 0), (5, 10), (8, 15), (12, 25), (25, 45), (45, 80), (75, 130), (0, 0),
+];
+#[rustfmt::skip]
+const CONNECTED_BONUS: [Weight; 8] = [
+    (0, 0), (3, 2), (5, 4), (8, 6), (15, 12), (25, 20), (40, 30), (0, 0),
+];
+#[rustfmt::skip]
+const CANDIDATE_BONUS: [Weight; 8] = [
+    (0, 0), (3, 5), (5, 8), (8, 12), (12, 20), (18, 30), (0, 0), (0, 0),
+];
+
+#[rustfmt::skip]
+const KNIGHT_MOBILITY: [Weight; 9] = [
+    (-20, -25), (-14, -16), (-6, -8), (-2, -3), (0, 0), (3, 3), (6, 6), (8, 8),
+    (10, 9),
+];
+#[rustfmt::skip]
+const BISHOP_MOBILITY: [Weight; 14] = [
+    (-25, -30), (-16, -20), (-8, -12), (-4, -6), (-2, -3), (0, 0), (2, 3), (4, 6),
+    (6, 8), (8, 10), (10, 12), (11, 13), (12, 14), (13, 15),
+];
+#[rustfmt::skip]
+const ROOK_MOBILITY: [Weight; 15] = [
+    (-20, -35), (-14, -25), (-8, -16), (-6, -11), (-4, -7), (-2, -4), (-1, -2), (0, 0),
+    (2, 3), (4, 6), (5, 9), (6, 12), (7, 14), (8, 16), (9, 18),
+];
+#[rustfmt::skip]
+const QUEEN_MOBILITY: [Weight; 28] = [
+    (-18, -24), (-16, -22), (-15, -20), (-14, -18), (-12, -16), (-10, -14), (-9, -12), (-8, -10),
+    (-6, -8), (-4, -6), (-3, -4), (-2, -2), (0, 0), (1, 2), (2, 4), (3, 6),
+    (4, 8), (5, 10), (6, 12), (7, 14), (8, 16), (9, 18), (10, 20), (10, 20),
+    (10, 20), (10, 20), (10, 20), (10, 20),
+];
+
+const KNIGHT_OUTPOST: Weight = (20, 10);
+const BISHOP_OUTPOST: Weight = (10, 5);
+const TRAPPED_MINOR: Weight = (-40, -30);
+const TRAPPED_ROOK: Weight = (-40, -10);
+const ROOK_SEVENTH: Weight = (20, 30);
+const THREAT_BY_PAWN: Weight = (40, 30);
+const THREAT_BY_MINOR: Weight = (25, 25);
+const THREAT_BY_ROOK: Weight = (30, 20);
+
+#[rustfmt::skip]
+const ZONE_ATTACK_WEIGHTS: [Weight; 4] = [
+    (6, 2), (6, 2), (8, 2), (12, 3),
+];
+#[rustfmt::skip]
+const SAFE_CHECK_WEIGHTS: [Weight; 4] = [
+    (14, 4), (8, 2), (12, 3), (10, 3),
+];
+#[rustfmt::skip]
+const SHIELD_BONUS: [Weight; 8] = [
+    (-20, 0), (15, 0), (8, 0), (0, 0), (0, 0), (0, 0), (0, 0), (0, 0),
+];
+#[rustfmt::skip]
+const STORM_PENALTY: [Weight; 8] = [
+    (0, 0), (-4, 0), (-20, 0), (-12, 0), (-6, 0), (0, 0), (0, 0), (0, 0),
+];
+const BLOCKED_STORM_PENALTY: Weight = (-4, 0);
+const SEMI_OPEN_FILE_PENALTY: Weight = (-10, 0);
+const OPEN_FILE_PENALTY: Weight = (-20, 0);
+
+/// Names and offsets of the parameters after material and the piece-square tables, in the order
+/// `print_params` writes them.
+const PARAM_BLOCKS: [(&str, usize, usize); 25] = [
+    ("DOUBLED_PENALTY", DOUBLED_PARAM, 1),
+    ("ISOLATED_PENALTY", ISOLATED_PARAM, 1),
+    ("BACKWARD_PENALTY", BACKWARD_PARAM, 1),
+    ("PASSED_BONUS", PASSED_PARAMS, 8),
+    ("CONNECTED_BONUS", CONNECTED_PARAMS, 8),
+    ("CANDIDATE_BONUS", CANDIDATE_PARAMS, 8),
+    ("KNIGHT_MOBILITY", KNIGHT_MOBILITY_PARAMS, 9),
+    ("BISHOP_MOBILITY", BISHOP_MOBILITY_PARAMS, 14),
+    ("ROOK_MOBILITY", ROOK_MOBILITY_PARAMS, 15),
+    ("QUEEN_MOBILITY", QUEEN_MOBILITY_PARAMS, 28),
+    ("KNIGHT_OUTPOST", KNIGHT_OUTPOST_PARAM, 1),
+    ("BISHOP_OUTPOST", BISHOP_OUTPOST_PARAM, 1),
+    ("TRAPPED_MINOR", TRAPPED_MINOR_PARAM, 1),
+    ("TRAPPED_ROOK", TRAPPED_ROOK_PARAM, 1),
+    ("ROOK_SEVENTH", ROOK_SEVENTH_PARAM, 1),
+    ("THREAT_BY_PAWN", THREAT_BY_PAWN_PARAM, 1),
+    ("THREAT_BY_MINOR", THREAT_BY_MINOR_PARAM, 1),
+    ("THREAT_BY_ROOK", THREAT_BY_ROOK_PARAM, 1),
+    ("ZONE_ATTACK_WEIGHTS", ZONE_ATTACK_PARAMS, 4),
+    ("SAFE_CHECK_WEIGHTS", SAFE_CHECK_PARAMS, 4),
+    ("SHIELD_BONUS", SHIELD_PARAMS, 8),
+    ("STORM_PENALTY", STORM_PARAMS, 8),
+    ("BLOCKED_STORM_PENALTY", BLOCKED_STORM_PARAM, 1),
+    ("SEMI_OPEN_FILE_PENALTY", SEMI_OPEN_FILE_PARAM, 1),
+    ("OPEN_FILE_PENALTY", OPEN_FILE_PARAM, 1),
+];
+
+const PIECE_NAMES: [&str; 6] = ["PAWN", "KNIGHT", "BISHOP", "ROOK", "QUEEN", "KING"];
+
+/// Receives evaluation terms as weights applied `count` times, so the same code can score a
+/// position or record how much each parameter contributes to it.
+pub trait EvalTerms {
+    /// Adds the weight at `param` `count` times to `term` for `side` (0 white, 1 black).
+    fn add(&mut self, term: usize, side: usize, param: usize, count: i32);
+
+    /// Adds the weights at `params`, each applied its count times, scaled by
+    /// `numerator / denominator` after summing.
+    fn add_scaled(
+        &mut self,
+        term: usize,
+        side: usize,
+        params: &[(usize, i32)],
+        numerator: i32,
+        denominator: i32,
+    );
+}
+
+impl<const N: usize> EvalTerms for [[Weight; 2]; N] {
+    fn add(&mut self, term: usize, side: usize, param: usize, count: i32) {
+        let (mg, eg) = PARAMS[param];
+        let score = &mut self[term][side];
+        score.0 += mg * count;
+        score.1 += eg * count;
+    }
+
+    fn add_scaled(
+        &mut self,
+        term: usize,
+        side: usize,
+        params: &[(usize, i32)],
+        numerator: i32,
+        denominator: i32,
+    ) {
+        let (mg, eg) = params.iter().fold((0, 0), |(mg, eg), (param, count)| {
+            (mg + PARAMS[*param].0 * count, eg + PARAMS[*param].1 * count)
+        });
+        let score = &mut self[term][side];
+        score.0 += mg * numerator / denominator;
+        score.1 += eg * numerator / denominator;
+    }
+}
+
+/// White-relative coefficient of every parameter in a position's evaluation, for the tuner.
+pub struct Trace {
+    pub coefficients: Vec<f64>,
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Trace::new()
+    }
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Trace {
+            coefficients: vec![0.0; PARAM_COUNT],
+        }
+    }
+
+    fn get_sign(side: usize) -> f64 {
+        if side == 0 { 1.0 } else { -1.0 }
+    }
+}
+
+impl EvalTerms for Trace {
+    fn add(&mut self, _term: usize, side: usize, param: usize, count: i32) {
+        self.coefficients[param] += Trace::get_sign(side) * count as f64;
+    }
+
+    fn add_scaled(
+        &mut self,
+        _term: usize,
+        side: usize,
+        params: &[(usize, i32)],
+        numerator: i32,
+        denominator: i32,
+    ) {
+        let scale = Trace::get_sign(side) * numerator as f64 / denominator as f64;
+        for (param, count) in params {
+            self.coefficients[*param] += scale * *count as f64;
+        }
+    }
+}
+
+/// Prints `params` as the weight declarations of this file.
+pub fn print_params(params: &[Weight]) {
+    for (phase, prefix) in ["MG", "EG"].into_iter().enumerate() {
+        let material = params[MATERIAL_PARAMS..MATERIAL_PARAMS + 5]
+            .iter()
+            .map(|weight| get_phase_weight(weight, phase).to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        println!("pub const {prefix}_MATERIAL: [i32; 7] = [0, {material}, 0];");
+    }
+    println!();
+
+    for (i, name) in PIECE_NAMES.iter().enumerate() {
+        for (phase, prefix) in ["MG", "EG"].into_iter().enumerate() {
+            println!("#[rustfmt::skip]");
+            println!("const {prefix}_{name}_TABLE: [i32; 64] = [");
+            for rank in params[PST_PARAMS + i * 64..PST_PARAMS + (i + 1) * 64].chunks(8) {
+                print!("   ");
+                for weight in rank {
+                    print!("{:>4},", get_phase_weight(weight, phase));
+                }
+                println!();
+            }
+            println!("];");
+            println!();
+        }
+    }
+
+    for (name, offset, len) in PARAM_BLOCKS {
+        if len == 1 {
+            let (mg, eg) = params[offset];
+            println!("const {name}: Weight = ({mg}, {eg});");
+            continue;
+        }
+        println!("#[rustfmt::skip]");
+        println!("const {name}: [Weight; {len}] = [");
+        for row in params[offset..offset + len].chunks(8) {
+            let row: Vec<String> = row.iter().map(|(mg, eg)| format!("({mg}, {eg})")).collect();
+            println!("    {},", row.join(", "));
+        }
+        println!("];");
+    }
+}
+
+fn get_phase_weight(weight: &Weight, phase: usize) -> i32 {
+    if phase == 0 { weight.0 } else { weight.1 }
+}
+
+const fn get_default_params() -> [Weight; PARAM_COUNT] {
+    let mut params = [(0, 0); PARAM_COUNT];
+    let mut piece = 1;
+    while piece <= 6 {
+        if piece < 6 {
+            params[MATERIAL_PARAMS + piece - 1] = (MG_MATERIAL[piece], EG_MATERIAL[piece]);
+        }
+        let mut square = 0;
+        while square < 64 {
+            params[PST_PARAMS + (piece - 1) * 64 + square] =
+                (MG_TABLES[piece][square], EG_TABLES[piece][square]);
+            square += 1;
+        }
+        piece += 1;
+    }
+    params[DOUBLED_PARAM] = DOUBLED_PENALTY;
+    params[ISOLATED_PARAM] = ISOLATED_PENALTY;
+    params[BACKWARD_PARAM] = BACKWARD_PENALTY;
+    copy_params(&mut params, PASSED_PARAMS, &PASSED_BONUS);
+    copy_params(&mut params, CONNECTED_PARAMS, &CONNECTED_BONUS);
+    copy_params(&mut params, CANDIDATE_PARAMS, &CANDIDATE_BONUS);
+    copy_params(&mut params, KNIGHT_MOBILITY_PARAMS, &KNIGHT_MOBILITY);
+    copy_params(&mut params, BISHOP_MOBILITY_PARAMS, &BISHOP_MOBILITY);
+    copy_params(&mut params, ROOK_MOBILITY_PARAMS, &ROOK_MOBILITY);
+    copy_params(&mut params, QUEEN_MOBILITY_PARAMS, &QUEEN_MOBILITY);
+    params[KNIGHT_OUTPOST_PARAM] = KNIGHT_OUTPOST;
+    params[BISHOP_OUTPOST_PARAM] = BISHOP_OUTPOST;
+    params[TRAPPED_MINOR_PARAM] = TRAPPED_MINOR;
+    params[TRAPPED_ROOK_PARAM] = TRAPPED_ROOK;
+    params[ROOK_SEVENTH_PARAM] = ROOK_SEVENTH;
+    params[THREAT_BY_PAWN_PARAM] = THREAT_BY_PAWN;
+    params[THREAT_BY_MINOR_PARAM] = THREAT_BY_MINOR;
+    params[THREAT_BY_ROOK_PARAM] = THREAT_BY_ROOK;
+    copy_params(&mut params, ZONE_ATTACK_PARAMS, &ZONE_ATTACK_WEIGHTS);
+    copy_params(&mut params, SAFE_CHECK_PARAMS, &SAFE_CHECK_WEIGHTS);
+    copy_params(&mut params, SHIELD_PARAMS, &SHIELD_BONUS);
+    copy_params(&mut params, STORM_PARAMS, &STORM_PENALTY);
+    params[BLOCKED_STORM_PARAM] = BLOCKED_STORM_PENALTY;
+    params[SEMI_OPEN_FILE_PARAM] = SEMI_OPEN_FILE_PENALTY;
+    params[OPEN_FILE_PARAM] = OPEN_FILE_PENALTY;
+    params
+}
+
+const fn copy_params(params: &mut [Weight; PARAM_COUNT], offset: usize, weights: &[Weight]) {
+    let mut i = 0;
+    while i < weights.len() {
+        params[offset + i] = weights[i];
+        i += 1;
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{
+    board_rep::{
+        bit_operations::{
+            count_bits, east_one, file_fill, first_bit_pop, north_fill, south_fill, west_one,
+        },
+        board::{BLACK, Board, WHITE},
+    },
+    evaluation::params::{
+        BACKWARD_PARAM, CANDIDATE_PARAMS, CONNECTED_PARAMS, DOUBLED_PARAM, EvalTerms,
+        ISOLATED_PARAM, PASSED_PARAMS,
+    },
+};
+
+pub const DEFAULT_PAWN_TABLE_SIZE: usize = 16384;
+
+pub const DOUBLED: usize = 0;
+pub const ISOLATED: usize = 1;
+pub const BACKWARD: usize = 2;
+pub const PASSED: usize = 3;
+pub const CONNECTED: usize = 4;
+pub const CANDIDATE: usize = 5;
+pub const PAWN_TERMS: usize = 6;
+pub const PAWN_TERM_NAMES: [&str; PAWN_TERMS] = [
+    "Doubled",
+    "Isolated",
+    "Backward",
+    "Passed",
+    "Connected",
+    "Candidate",
+];
+
+#[derive(Clone, Copy, Default)]
+pub struct PawnEval {
+    pub terms: [[(i32, i32); 2]; PAWN_TERMS],
+    pub passed: [u64; 2],
+}
+
+impl PawnEval {
+    pub fn get_total(&self) -> (i32, i32) {
+        self.terms.iter().fold((0, 0), |(mg, eg), [white, black]| {
+            (mg + white.0 - black.0, eg + white.1 - black.1)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct PawnEntry {
+    key: u64,
+    mg: i32,
+    eg: i32,
+    passed: [u64; 2],
+}
+
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        PawnTable::new(DEFAULT_PAWN_TABLE_SIZE)
+    }
+}
+
+impl PawnTable {
+    pub fn new(size: usize) -> Self {
+        PawnTable {
+            entries: vec![PawnEntry::default(); size.next_power_of_two()],
+        }
+    }
+
+    pub fn probe(&mut self, board: &Board) -> (i32, i32, [u64; 2]) {
+        let index = board.pawn_hash as usize & (self.entries.len() - 1);
+        let entry = &mut self.entries[index];
+        if entry.key != board.pawn_hash || board.pawn_hash == 0 {
+            let pawn_eval = evaluate_pawns(board);
+            let (mg, eg) = pawn_eval.get_total();
+            *entry = PawnEntry {
+                key: board.pawn_hash,
+                mg,
+                eg,
+                passed: pawn_eval.passed,
+            };
+        }
+        (entry.mg, entry.eg, entry.passed)
+    }
+}
+
+pub fn evaluate_pawns(board: &Board) -> PawnEval {
+    let mut pawn_eval = PawnEval::default();
+    pawn_eval.passed = add_pawn_terms(board, &mut pawn_eval.terms);
+    pawn_eval
+}
+
+/// Adds the pawn structure terms to `terms` and returns the passed pawns of each side.
+pub fn add_pawn_terms<T: EvalTerms>(board: &Board, terms: &mut T) -> [u64; 2] {
+    let mut passed_pawns = [0; 2];
+    for (side, color) in [WHITE, BLACK].iter().enumerate() {
+        let (own, enemy) = if *color == WHITE {
+            (board.w_pawns, board.b_pawns)
+        } else {
+            (board.b_pawns, board.w_pawns)
+        };
+        let own_front = get_front_span(own, *color);
+        let own_rear = get_rear_span(own, *color);
+        let enemy_front = get_front_span(enemy, *color ^ (WHITE | BLACK));
+        let own_attacks = get_pawn_attacks(own, *color);
+        let enemy_attacks = get_pawn_attacks(enemy, *color ^ (WHITE | BLACK));
+
+        let doubled = own & own_rear;
+        let neighbours = file_fill(own);
+        let isolated = own & !(east_one(neighbours) | west_one(neighbours));
+        let enemy_span = enemy_front | east_one(enemy_front) | west_one(enemy_front);
+        let passed = own & !enemy_span;
+        let connected = own & (own_attacks | east_one(own) | west_one(own));
+        let own_attack_span = get_front_span(east_one(own) | west_one(own), *color);
+        let stops = get_stop_squares(own, *color);
+        let backward = get_stop_squares(
+            stops & enemy_attacks & !own_attack_span,
+            *color ^ (WHITE | BLACK),
+        ) & !isolated;
+        let semi_open = own & !file_fill(enemy);
+        let candidates = semi_open & !passed & !own_front;
+
+        terms.add(DOUBLED, side, DOUBLED_PARAM, count_bits(doubled) as i32);
+        terms.add(ISOLATED, side, ISOLATED_PARAM, count_bits(isolated) as i32);
+        terms.add(BACKWARD, side, BACKWARD_PARAM, count_bits(backward) as i32);
+        add_by_rank(terms, PASSED, side, passed, *color, PASSED_PARAMS);
+        add_by_rank(terms, CONNECTED, side, connected, *color, CONNECTED_PARAMS);
+
+        let mut candidate = 0;
+        let mut pawns = candidates;
+        while pawns != 0 {
+            let square = first_bit_pop(&mut pawns);
+            let pawn = 1u64 << square;
+            let adjacent = east_one(pawn) | west_one(pawn);
+            let helpers = own & (adjacent | get_rear_span(adjacent, *color));
+            let sentries = enemy & get_front_span(adjacent, *color);
+            if count_bits(helpers) >= count_bits(sentries) {
+                candidate |= pawn;
+            }
+        }
+        add_by_rank(terms, CANDIDATE, side, candidate, *color, CANDIDATE_PARAMS);
+        passed_pawns[side] = passed;
+    }
+    passed_pawns
+}
+
+pub fn get_relative_rank(square: u8, color: u8) -> usize {
+    if color == WHITE {
+        7 - square as usize / 8
+    } else {
+        square as usize / 8
+    }
+}
+
+pub fn get_front_span(pawns: u64, color: u8) -> u64 {
+    if color == WHITE {
+        north_fill(pawns >> 8)
+    } else {
+        south_fill(pawns << 8)
+    }
+}
+
+fn get_rear_span(pawns: u64, color: u8) -> u64 {
+    if color == WHITE {
+        south_fill(pawns << 8)
+    } else {
+        north_fill(pawns >> 8)
+    }
+}
+
+pub fn get_stop_squares(pawns: u64, color: u8) -> u64 {
+    if color == WHITE {
+        pawns >> 8
+    } else {
+        pawns << 8
+    }
+}
+
+pub fn get_pawn_attacks(pawns: u64, color: u8) -> u64 {
+    let attacks = east_one(pawns) | west_one(pawns);
+    get_stop_squares(attacks, color)
+}
+
+fn add_by_rank<T: EvalTerms>(
+    terms: &mut T,
+    term: usize,
+    side: usize,
+    mut pawns: u64,
+    color: u8,
+    params: usize,
+) {
+    while pawns != 0 {
+        let rank = get_relative_rank(first_bit_pop(&mut pawns), color);
+        terms.add(term, side, params + rank, 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    /// White's and black's score for `term`, summed over both phases.
+    fn get_term(fen: &str, term: usize) -> [i32; 2] {
+        let mut board = Board::new();
+        board.set_from_fen(fen, get_test_magic_bitboards());
+        evaluate_pawns(&board).terms[term].map(|(mg, eg)| mg + eg)
+    }
+
+    #[test]
+    fn passed_pawn_is_a_bonus() {
+        let [white, black] = get_term("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1", PASSED);
+        assert!(white > 0);
+        assert_eq!(black, 0);
+    }
+
+    #[test]
+    fn isolated_pawn_is_a_penalty() {
+        let [white, black] = get_term("4k3/ppp5/8/8/8/8/P1P5/4K3 w - - 0 1", ISOLATED);
+        assert!(white < 0);
+        assert_eq!(black, 0);
+    }
+
+    #[test]
+    fn doubled_pawn_is_a_penalty() {
+        let [white, black] = get_term("4k3/2p5/1p6/8/8/2P5/1PP5/4K3 w - - 0 1", DOUBLED);
+        assert!(white < 0);
+        assert_eq!(black, 0);
+    }
+
+    #[test]
+    fn backward_pawn_is_a_penalty() {
+        let [white, black] = get_term("4k3/8/8/4p3/2P5/3P4/8/4K3 w - - 0 1", BACKWARD);
+        assert!(white < 0);
+        assert_eq!(black, 0);
+    }
+}
@@ -0,0 +1,8 @@
+pub mod board_rep;
+pub mod book;
+pub mod engine;
+pub mod evaluation;
+pub mod play;
+pub mod tablebases;
+pub mod uci;
+pub mod xboard;
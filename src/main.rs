@@ -1,23 +1,55 @@
-use crate::board_rep::{board::Board, magic_bitboards::MagicBitboards};
+use std::{path::Path, sync::Arc};
 
-pub mod board_rep;
-pub mod uci;
+use nuvo_chess::{
+    board_rep::{board::Board, magic_bitboards::MagicBitboards},
+    engine::searcher::Searcher,
+    evaluation::{kpk::init_kpk, nnue::DEFAULT_EVAL_FILE},
+    uci,
+    xboard::XBoard,
+};
 
 fn main() {
     let mut board = Board::new();
-    let magic_bitboards = MagicBitboards::new();
+    let magic_bitboards = Arc::new(MagicBitboards::new());
+    init_kpk(&magic_bitboards);
+    let mut searcher = Searcher::new();
+    if Path::new(DEFAULT_EVAL_FILE).exists() {
+        uci::load_eval_file(DEFAULT_EVAL_FILE);
+    }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        uci::uci_command(&args.join(" "), &mut board, &magic_bitboards, &mut searcher);
+        return;
+    }
     board.set_from_fen(
         "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
         &magic_bitboards,
     );
 
     let mut input = String::new();
+    let mut xboard: Option<XBoard> = None;
+    let mut first_line = true;
     loop {
         input.clear();
-        std::io::stdin()
+        let bytes = std::io::stdin()
             .read_line(&mut input)
             .expect("Failed to read line");
-        if !uci::uci_command(input.as_str(), &mut board, &magic_bitboards) {
+        if bytes == 0 {
+            match xboard.as_mut() {
+                Some(xboard) => xboard.wait(&mut searcher),
+                None => searcher.wait(),
+            }
+            break;
+        }
+        if first_line && input.trim() == "xboard" {
+            xboard = Some(XBoard::new(&magic_bitboards));
+        }
+        first_line = false;
+        let running = match xboard.as_mut() {
+            Some(xboard) => xboard.command(input.as_str(), &magic_bitboards, &mut searcher),
+            None => uci::uci_command(input.as_str(), &mut board, &magic_bitboards, &mut searcher),
+        };
+        if !running {
             break;
         }
     }
@@ -0,0 +1,376 @@
+use std::{
+    io::Write,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Instant,
+};
+
+use crate::{
+    board_rep::{
+        board::{BLACK, Board, WHITE},
+        magic_bitboards::MagicBitboards,
+        move_gen::generate_moves,
+        san::{get_legal_moves, get_san, parse_san},
+    },
+    engine::{
+        limits::{MAX_DEPTH, SearchLimits},
+        search::{InfoFormat, format_score},
+        searcher::{SEARCH_STACK_SIZE, run_search},
+        strength::{MAX_ELO, MIN_ELO},
+        tt::{DEFAULT_HASH_MB, TranspositionTable},
+    },
+};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const DEFAULT_MOVE_TIME: u64 = 1000;
+const DRAW_ACCEPT_SCORE: i32 = 25;
+const DRAW_ACCEPT_MOVE: u16 = 20;
+
+pub struct Play {
+    board: Board,
+    history: Vec<(Board, String)>,
+    human: u8,
+    depth: usize,
+    nodes: u64,
+    move_time: Option<u64>,
+    elo: Option<u32>,
+    seed: Option<u64>,
+    increment: u64,
+    clocks: Option<[i64; 2]>,
+    use_ascii: bool,
+    engine_score: i32,
+    tt: TranspositionTable,
+    stop: AtomicBool,
+}
+
+impl Play {
+    pub fn new(args: &[&str], board: &Board) -> Self {
+        let mut play = Play {
+            board: *board,
+            history: Vec::new(),
+            human: WHITE,
+            depth: MAX_DEPTH,
+            nodes: u64::MAX,
+            move_time: None,
+            elo: None,
+            seed: None,
+            increment: 0,
+            clocks: None,
+            use_ascii: false,
+            engine_score: 0,
+            tt: TranspositionTable::new(DEFAULT_HASH_MB),
+            stop: AtomicBool::new(false),
+        };
+        let mut i = 0;
+        while i < args.len() {
+            let value = args.get(i + 1).unwrap_or(&"");
+            match args[i] {
+                "white" => play.human = WHITE,
+                "black" => play.human = BLACK,
+                "ascii" => play.use_ascii = true,
+                "depth" => {
+                    play.depth = value.parse().unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+                    i += 1;
+                }
+                "nodes" => {
+                    play.nodes = value.parse().unwrap_or(u64::MAX);
+                    i += 1;
+                }
+                "movetime" => {
+                    play.move_time = value.parse().ok();
+                    i += 1;
+                }
+                "elo" => {
+                    play.elo = value
+                        .parse()
+                        .ok()
+                        .map(|elo: u32| elo.clamp(MIN_ELO, MAX_ELO));
+                    i += 1;
+                }
+                "seed" => {
+                    play.seed = value.parse().ok();
+                    i += 1;
+                }
+                "tc" => {
+                    let (base, increment) = value.split_once('+').unwrap_or((value, "0"));
+                    let base = (base.parse::<f64>().unwrap_or(0.0) * 60000.0) as i64;
+                    play.increment = (increment.parse::<f64>().unwrap_or(0.0) * 1000.0) as u64;
+                    play.clocks = (base > 0).then_some([base; 2]);
+                    i += 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        if play.move_time.is_none()
+            && play.clocks.is_none()
+            && play.depth == MAX_DEPTH
+            && play.nodes == u64::MAX
+        {
+            play.move_time = Some(DEFAULT_MOVE_TIME);
+        }
+        play
+    }
+
+    pub fn run(&mut self, magic_bitboards: &MagicBitboards) {
+        println!("Commands: <move> (e4, Nf3, e2e4), undo, draw, resign, moves, board, help, quit");
+        self.print_board();
+        let mut turn_start = Instant::now();
+        loop {
+            if let Some(result) = self.get_game_result(magic_bitboards) {
+                println!("{result}");
+                return;
+            }
+            if self.board.stm != self.human {
+                if let Some(result) = self.engine_move(magic_bitboards) {
+                    println!("{result}");
+                    return;
+                }
+                self.print_board();
+                turn_start = Instant::now();
+                continue;
+            }
+
+            let Some(input) = self.prompt(turn_start) else {
+                return;
+            };
+            if let Some(clocks) = self.clocks
+                && clocks[(self.human == BLACK) as usize] <= turn_start.elapsed().as_millis() as i64
+            {
+                let termination = format!("{} loses on time", get_color_name(self.human));
+                println!(
+                    "{}",
+                    get_win_result(self.human ^ (WHITE | BLACK), &termination)
+                );
+                return;
+            }
+            match input.as_str() {
+                "" => {}
+                "quit" | "exit" => return,
+                "help" => {
+                    println!("Enter moves in SAN (Nf3, exd5, O-O, e8=Q) or long algebraic (g1f3).");
+                    println!(
+                        "undo takes back your last move, draw offers a draw, resign ends the game."
+                    );
+                }
+                "board" => self.print_board(),
+                "moves" => {
+                    let moves: Vec<String> = get_legal_moves(&self.board, magic_bitboards)
+                        .into_iter()
+                        .map(|c_move| get_san(&self.board, magic_bitboards, c_move))
+                        .collect();
+                    println!("{}", moves.join(" "));
+                }
+                "undo" => {
+                    if self.history.is_empty() {
+                        println!("Nothing to undo");
+                        continue;
+                    }
+                    while let Some((board, _)) = self.history.pop() {
+                        self.board = board;
+                        if self.board.stm == self.human {
+                            break;
+                        }
+                    }
+                    self.print_board();
+                    turn_start = Instant::now();
+                }
+                "resign" => {
+                    let termination = format!("{} resigns", get_color_name(self.human));
+                    println!(
+                        "{}",
+                        get_win_result(self.human ^ (WHITE | BLACK), &termination)
+                    );
+                    return;
+                }
+                "draw" => {
+                    if self.engine_score <= DRAW_ACCEPT_SCORE
+                        && self.board.fullmove_number >= DRAW_ACCEPT_MOVE
+                    {
+                        println!("Draw offer accepted");
+                        println!("1/2-1/2 {{Draw by agreement}}");
+                        return;
+                    }
+                    println!("Draw offer declined");
+                }
+                move_str => match parse_san(&self.board, magic_bitboards, move_str) {
+                    Some(c_move) => {
+                        self.update_clock(turn_start);
+                        let san = get_san(&self.board, magic_bitboards, c_move);
+                        self.history.push((self.board, san));
+                        self.board.make_move(c_move);
+                    }
+                    None => println!("Illegal or ambiguous move: {move_str}"),
+                },
+            }
+        }
+    }
+
+    fn prompt(&self, turn_start: Instant) -> Option<String> {
+        let clock = match self.clocks {
+            Some(clocks) => {
+                let elapsed = turn_start.elapsed().as_millis() as i64;
+                format!(
+                    " [{}]",
+                    format_clock(clocks[(self.human == BLACK) as usize] - elapsed)
+                )
+            }
+            None => String::new(),
+        };
+        print!(
+            "{}{}{clock}> ",
+            self.board.fullmove_number,
+            if self.board.stm == WHITE { "." } else { "..." }
+        );
+        std::io::stdout().flush().unwrap();
+        let mut input = String::new();
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(input.trim().to_string()),
+        }
+    }
+
+    fn engine_move(&mut self, magic_bitboards: &MagicBitboards) -> Option<String> {
+        let mut limits = SearchLimits::new();
+        limits.depth = self.depth;
+        limits.nodes = self.nodes;
+        limits.move_time = self.move_time;
+        limits.elo = self.elo;
+        limits.seed = self.seed;
+        if let Some(clocks) = self.clocks {
+            limits.w_time = Some(clocks[0].max(1) as u64);
+            limits.b_time = Some(clocks[1].max(1) as u64);
+            limits.w_inc = self.increment;
+            limits.b_inc = self.increment;
+        }
+        self.stop.store(false, Ordering::Relaxed);
+
+        let now = Instant::now();
+        let board = self.board;
+        let history: Vec<u64> = self.history.iter().map(|(board, _)| board.hash).collect();
+        let result = thread::scope(|scope| {
+            thread::Builder::new()
+                .stack_size(SEARCH_STACK_SIZE)
+                .spawn_scoped(scope, || {
+                    run_search(
+                        &board,
+                        &history,
+                        magic_bitboards,
+                        &self.tt,
+                        &self.stop,
+                        &limits,
+                        1,
+                        InfoFormat::None,
+                    )
+                })
+                .expect("Failed to spawn search thread")
+                .join()
+                .expect("Search thread panicked")
+        });
+        if let Some(result) = self.update_clock(now) {
+            return Some(result);
+        }
+        let (result, _) = result?;
+        self.engine_score = result.score;
+        let san = get_san(&self.board, magic_bitboards, result.best_move);
+        println!(
+            "nuvo_chess plays {}{} {san} ({}, depth {}, {:.1}s)",
+            self.board.fullmove_number,
+            if self.board.stm == WHITE { "." } else { "..." },
+            format_score(result.score),
+            result.depth,
+            now.elapsed().as_secs_f64()
+        );
+        self.history.push((self.board, san));
+        self.board.make_move(result.best_move);
+        None
+    }
+
+    fn update_clock(&mut self, start: Instant) -> Option<String> {
+        let clocks = self.clocks.as_mut()?;
+        let side = (self.board.stm == BLACK) as usize;
+        clocks[side] -= start.elapsed().as_millis() as i64;
+        if clocks[side] <= 0 {
+            let termination = format!("{} loses on time", get_color_name(self.board.stm));
+            return Some(get_win_result(
+                self.board.stm ^ (WHITE | BLACK),
+                &termination,
+            ));
+        }
+        clocks[side] += self.increment as i64;
+        None
+    }
+
+    fn print_board(&self) {
+        println!();
+        self.board.print(self.use_ascii);
+        if let Some(clocks) = self.clocks {
+            println!(
+                "   White {}   Black {}",
+                format_clock(clocks[0]),
+                format_clock(clocks[1])
+            );
+        }
+        if !self.history.is_empty() {
+            let moves: Vec<&str> = self.history.iter().map(|(_, san)| san.as_str()).collect();
+            println!("   Moves: {}", moves.join(" "));
+        }
+        println!();
+    }
+
+    fn get_game_result(&self, magic_bitboards: &MagicBitboards) -> Option<String> {
+        let board = &self.board;
+        if get_legal_moves(board, magic_bitboards).is_empty() {
+            if generate_moves(board, magic_bitboards).check_count == 0 {
+                return Some("1/2-1/2 {Stalemate}".to_string());
+            }
+            let winner = board.stm ^ (WHITE | BLACK);
+            let termination = format!("{} mates", get_color_name(winner));
+            return Some(get_win_result(winner, &termination));
+        }
+        if board.halfmove_clock >= 100 {
+            return Some("1/2-1/2 {Fifty move rule}".to_string());
+        }
+        let repetitions = self
+            .history
+            .iter()
+            .rev()
+            .take(board.halfmove_clock as usize)
+            .filter(|(previous, _)| previous.hash == board.hash)
+            .count();
+        if repetitions >= 2 {
+            return Some("1/2-1/2 {3-fold repetition}".to_string());
+        }
+        let heavy = board.w_pawns | board.b_pawns | board.w_rooks | board.b_rooks;
+        let minors = board.w_knights | board.b_knights | board.w_bishops | board.b_bishops;
+        if heavy | board.w_queens | board.b_queens == 0 && minors.count_ones() <= 1 {
+            return Some("1/2-1/2 {Insufficient material}".to_string());
+        }
+        None
+    }
+}
+
+pub fn start_play(args: &[&str], board: &Board, magic_bitboards: &MagicBitboards) {
+    let mut start = *board;
+    if start.all_occupancy == 0 {
+        start.set_from_fen(START_FEN, magic_bitboards);
+    }
+    Play::new(args, &start).run(magic_bitboards);
+}
+
+fn get_win_result(winner: u8, termination: &str) -> String {
+    if winner == WHITE {
+        format!("1-0 {{{termination}}}")
+    } else {
+        format!("0-1 {{{termination}}}")
+    }
+}
+
+fn get_color_name(color: u8) -> &'static str {
+    if color == WHITE { "White" } else { "Black" }
+}
+
+fn format_clock(millis: i64) -> String {
+    let seconds = millis.max(0) / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
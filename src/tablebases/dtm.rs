@@ -0,0 +1,450 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use crate::board_rep::{
+    bit_operations::{count_bits, first_bit_pop},
+    board::{BISHOP, BLACK, Board, EN_PASSANT_NONE, KING, KNIGHT, PAWN, QUEEN, ROOK, WHITE},
+};
+
+pub const DTM_SUFFIX: &str = ".ndtm";
+pub const DTM_PIECES: usize = 4;
+
+const DTM_MAGIC: [u8; 4] = *b"NDTM";
+const DTM_VERSION: u8 = 1;
+const LOSS_OFFSET: u8 = 128;
+const PIECE_CHARS: [(char, u8); 6] = [
+    ('K', KING),
+    ('Q', QUEEN),
+    ('R', ROOK),
+    ('B', BISHOP),
+    ('N', KNIGHT),
+    ('P', PAWN),
+];
+
+static DTM_TABLES: RwLock<Option<Arc<DtmTables>>> = RwLock::new(None);
+
+pub fn set_dtm_tables(tables: Option<DtmTables>) {
+    *DTM_TABLES.write().unwrap() = tables.map(Arc::new);
+}
+
+pub fn get_dtm_tables() -> Option<Arc<DtmTables>> {
+    DTM_TABLES.read().unwrap().clone()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dtm {
+    Draw,
+    Win(u8),
+    Loss(u8),
+}
+
+impl Dtm {
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            0 => Dtm::Draw,
+            1..LOSS_OFFSET => Dtm::Win(byte),
+            _ => Dtm::Loss(byte - LOSS_OFFSET),
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Dtm::Draw => 0,
+            Dtm::Win(moves) => moves,
+            Dtm::Loss(moves) => LOSS_OFFSET + moves,
+        }
+    }
+
+    pub fn from_plies(plies: usize) -> Self {
+        if plies % 2 == 1 {
+            Dtm::Win(plies.div_ceil(2) as u8)
+        } else {
+            Dtm::Loss((plies / 2) as u8)
+        }
+    }
+
+    pub fn get_plies(self) -> Option<usize> {
+        match self {
+            Dtm::Draw => None,
+            Dtm::Win(moves) => Some(moves as usize * 2 - 1),
+            Dtm::Loss(moves) => Some(moves as usize * 2),
+        }
+    }
+
+    pub fn get_parent(self) -> Self {
+        match self {
+            Dtm::Draw => Dtm::Draw,
+            Dtm::Win(moves) => Dtm::Loss(moves),
+            Dtm::Loss(moves) => Dtm::Win(moves + 1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TablePiece {
+    pub piece: u8,
+    pub color: u8,
+}
+
+#[derive(Clone)]
+pub struct Material {
+    pub name: String,
+    pub pieces: Vec<TablePiece>,
+    pub has_pawns: bool,
+    king_slots: usize,
+}
+
+impl Material {
+    pub fn new(name: &str) -> Option<Self> {
+        let (strong, weak) = name.split_once('v')?;
+        let strong = parse_side(strong)?;
+        let weak = parse_side(weak)?;
+        if strong.len() + weak.len() > DTM_PIECES || get_normalized_name(&strong, &weak) != name {
+            return None;
+        }
+        let mut pieces = vec![
+            TablePiece {
+                piece: KING,
+                color: WHITE,
+            },
+            TablePiece {
+                piece: KING,
+                color: BLACK,
+            },
+        ];
+        for (side, color) in [(&strong, WHITE), (&weak, BLACK)] {
+            pieces.extend(side[1..].iter().map(|piece| TablePiece {
+                piece: *piece,
+                color,
+            }));
+        }
+        let has_pawns = pieces.iter().any(|piece| piece.piece == PAWN);
+        Some(Material {
+            name: name.to_string(),
+            pieces,
+            has_pawns,
+            king_slots: if has_pawns { 32 } else { 10 },
+        })
+    }
+
+    pub fn get_size(&self) -> usize {
+        2 * self.king_slots * (1 << (6 * (self.pieces.len() - 1)))
+    }
+
+    pub fn get_index(&self, squares: &[u8], stm_is_weak: bool) -> usize {
+        let symmetries = if self.has_pawns { 2 } else { 8 };
+        let mut best = usize::MAX;
+        let mut transformed = [0u8; DTM_PIECES];
+        for symmetry in 0..symmetries {
+            for (square, original) in transformed.iter_mut().zip(squares) {
+                *square = get_symmetric_square(*original, symmetry);
+            }
+            let Some(slot) = self.get_king_slot(transformed[0]) else {
+                continue;
+            };
+            let count = self.pieces.len();
+            for i in 2..count {
+                let mut j = i;
+                while j > 2
+                    && self.pieces[j - 1] == self.pieces[j]
+                    && transformed[j - 1] > transformed[j]
+                {
+                    transformed.swap(j - 1, j);
+                    j -= 1;
+                }
+            }
+            let mut index = stm_is_weak as usize * self.king_slots + slot;
+            for square in &transformed[1..count] {
+                index = index * 64 + *square as usize;
+            }
+            best = best.min(index);
+        }
+        best
+    }
+
+    pub fn get_squares(&self, index: usize) -> ([u8; DTM_PIECES], bool) {
+        let mut squares = [0u8; DTM_PIECES];
+        let mut rest = index;
+        for square in squares[1..self.pieces.len()].iter_mut().rev() {
+            *square = (rest % 64) as u8;
+            rest /= 64;
+        }
+        squares[0] = self.get_slot_square(rest % self.king_slots);
+        (squares, rest / self.king_slots == 1)
+    }
+
+    fn get_king_slot(&self, square: u8) -> Option<usize> {
+        let file = (square % 8) as usize;
+        let rank = 7 - (square / 8) as usize;
+        if file > 3 {
+            None
+        } else if self.has_pawns {
+            Some(rank * 4 + file)
+        } else if rank <= file {
+            Some(TRIANGLE_SLOTS[file] + rank)
+        } else {
+            None
+        }
+    }
+
+    fn get_slot_square(&self, slot: usize) -> u8 {
+        let (file, rank) = if self.has_pawns {
+            (slot % 4, slot / 4)
+        } else {
+            let file = TRIANGLE_SLOTS
+                .iter()
+                .rposition(|start| *start <= slot)
+                .unwrap_or(0);
+            (file, slot - TRIANGLE_SLOTS[file])
+        };
+        ((7 - rank) * 8 + file) as u8
+    }
+}
+
+const TRIANGLE_SLOTS: [usize; 4] = [0, 1, 3, 6];
+
+pub struct DtmTable {
+    pub material: Material,
+    pub values: Vec<u8>,
+}
+
+impl DtmTable {
+    pub fn load(path: &Path, name: &str) -> Result<Self, String> {
+        let material = Material::new(name).ok_or(format!("invalid material {name}"))?;
+        let bytes = fs::read(path).map_err(|error| format!("{}: {error}", path.display()))?;
+        if bytes.len() != material.get_size() + 5
+            || bytes[..4] != DTM_MAGIC
+            || bytes[4] != DTM_VERSION
+        {
+            return Err(format!("{} is not a valid DTM table", path.display()));
+        }
+        Ok(DtmTable {
+            material,
+            values: bytes[5..].to_vec(),
+        })
+    }
+
+    pub fn save(&self, directory: &Path) -> Result<(), String> {
+        let path = directory.join(format!("{}{DTM_SUFFIX}", self.material.name));
+        let mut bytes = Vec::with_capacity(self.values.len() + 5);
+        bytes.extend_from_slice(&DTM_MAGIC);
+        bytes.push(DTM_VERSION);
+        bytes.extend_from_slice(&self.values);
+        fs::write(&path, bytes).map_err(|error| format!("{}: {error}", path.display()))
+    }
+
+    pub fn get(&self, squares: &[u8], stm_is_weak: bool) -> Dtm {
+        Dtm::from_byte(self.values[self.material.get_index(squares, stm_is_weak)])
+    }
+
+    pub fn get_max_win(&self) -> u8 {
+        self.values
+            .iter()
+            .filter(|value| (1..LOSS_OFFSET).contains(*value))
+            .max()
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn probe(&self, board: &Board, strong: u8) -> Dtm {
+        let mut squares = [0u8; DTM_PIECES];
+        let mut i = 0;
+        while i < self.material.pieces.len() {
+            let piece = self.material.pieces[i];
+            let color = if piece.color == WHITE {
+                strong
+            } else {
+                strong ^ (WHITE | BLACK)
+            };
+            let mut pieces = get_pieces(board, piece.piece, color);
+            while pieces != 0 {
+                let square = first_bit_pop(&mut pieces);
+                squares[i] = if strong == WHITE { square } else { square ^ 56 };
+                i += 1;
+            }
+        }
+        self.get(&squares, board.stm != strong)
+    }
+}
+
+#[derive(Default)]
+pub struct DtmTables {
+    tables: HashMap<String, DtmTable>,
+}
+
+impl DtmTables {
+    pub fn load(directory: &str) -> Self {
+        let mut tables = DtmTables::default();
+        let Ok(entries) = fs::read_dir(directory) else {
+            return tables;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(DTM_SUFFIX))
+            else {
+                continue;
+            };
+            match DtmTable::load(&path, name) {
+                Ok(table) => tables.insert(table),
+                Err(error) => println!("info string {error}"),
+            }
+        }
+        tables
+    }
+
+    pub fn insert(&mut self, table: DtmTable) {
+        self.tables.insert(table.material.name.clone(), table);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        name == "KvK" || self.tables.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DtmTable> {
+        self.tables.get(name)
+    }
+
+    pub fn get_table_count(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn get_names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self.tables.keys().collect();
+        names.sort();
+        names
+    }
+
+    pub fn probe(&self, board: &Board) -> Option<Dtm> {
+        if board.castling != 0 || count_bits(board.all_occupancy) as usize > DTM_PIECES {
+            return None;
+        }
+        // Tables are generated without en passant rights.
+        if board.w_pawns != 0 && board.b_pawns != 0 && board.en_passant != EN_PASSANT_NONE {
+            return None;
+        }
+        let (name, strong) = get_board_material(board)?;
+        if name == "KvK" {
+            return Some(Dtm::Draw);
+        }
+        Some(self.tables.get(&name)?.probe(board, strong))
+    }
+}
+
+pub fn get_board_material(board: &Board) -> Option<(String, u8)> {
+    let mut sides = [Vec::new(), Vec::new()];
+    for (side, color) in [WHITE, BLACK].into_iter().enumerate() {
+        for (_, piece) in PIECE_CHARS {
+            for _ in 0..count_bits(get_pieces(board, piece, color)) {
+                sides[side].push(piece);
+            }
+        }
+        if sides[side].first() != Some(&KING) || sides[side][1..].contains(&KING) {
+            return None;
+        }
+    }
+    let name = get_normalized_name(&sides[0], &sides[1]);
+    let strong = if name == get_side_name(&sides[0]) + "v" + &get_side_name(&sides[1]) {
+        WHITE
+    } else {
+        BLACK
+    };
+    Some((name, strong))
+}
+
+pub fn get_normalized_name(first: &[u8], second: &[u8]) -> String {
+    let key = |side: &[u8]| {
+        let value: i32 = side.iter().map(|piece| get_piece_value(*piece)).sum();
+        (value, side.to_vec())
+    };
+    let (strong, weak) = if key(first) >= key(second) {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    get_side_name(strong) + "v" + &get_side_name(weak)
+}
+
+pub fn parse_side(side: &str) -> Option<Vec<u8>> {
+    let pieces: Option<Vec<u8>> = side
+        .chars()
+        .map(|c| {
+            PIECE_CHARS
+                .iter()
+                .find(|(piece_char, _)| *piece_char == c)
+                .map(|(_, piece)| *piece)
+        })
+        .collect();
+    let pieces = pieces?;
+    let sorted = pieces.windows(2).all(|pair| 7 - pair[0] <= 7 - pair[1]);
+    (pieces.first() == Some(&KING) && !pieces[1..].contains(&KING) && sorted).then_some(pieces)
+}
+
+fn get_side_name(side: &[u8]) -> String {
+    side.iter()
+        .filter_map(|piece| {
+            PIECE_CHARS
+                .iter()
+                .find(|(_, p)| p == piece)
+                .map(|(piece_char, _)| *piece_char)
+        })
+        .collect()
+}
+
+fn get_piece_value(piece: u8) -> i32 {
+    match piece {
+        QUEEN => 9,
+        ROOK => 5,
+        BISHOP | KNIGHT => 3,
+        PAWN => 1,
+        _ => 0,
+    }
+}
+
+pub fn get_pieces(board: &Board, piece: u8, color: u8) -> u64 {
+    let (white, black) = match piece {
+        PAWN => (board.w_pawns, board.b_pawns),
+        KNIGHT => (board.w_knights, board.b_knights),
+        BISHOP => (board.w_bishops, board.b_bishops),
+        ROOK => (board.w_rooks, board.b_rooks),
+        QUEEN => (board.w_queens, board.b_queens),
+        _ => (board.w_king, board.b_king),
+    };
+    if color == WHITE { white } else { black }
+}
+
+pub fn get_symmetric_square(square: u8, symmetry: usize) -> u8 {
+    let mut square = square;
+    if symmetry & 4 != 0 {
+        let file = square % 8;
+        let rank = 7 - square / 8;
+        square = (7 - file) * 8 + rank;
+    }
+    if symmetry & 1 != 0 {
+        square ^= 7;
+    }
+    if symmetry & 2 != 0 {
+        square ^= 56;
+    }
+    square
+}
+
+pub fn print_dtm_probe(board: &Board) {
+    let Some(tables) = get_dtm_tables() else {
+        println!("info string no DTM tables loaded");
+        return;
+    };
+    match tables.probe(board) {
+        Some(Dtm::Draw) => println!("draw"),
+        Some(Dtm::Win(moves)) => println!("win, mate in {moves}"),
+        Some(Dtm::Loss(0)) => println!("loss, checkmated"),
+        Some(Dtm::Loss(moves)) => println!("loss, mated in {moves}"),
+        None => println!("info string position not in DTM tables"),
+    }
+}
@@ -0,0 +1,3 @@
+pub mod dtm;
+pub mod retrograde;
+pub mod syzygy;
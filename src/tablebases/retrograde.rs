@@ -0,0 +1,405 @@
+use std::{path::Path, time::Instant};
+
+use crate::{
+    board_rep::{
+        board::{BLACK, Board, EMPTY, KING, PAWN, WHITE},
+        magic_bitboards::MagicBitboards,
+        move_gen::generate_moves,
+    },
+    engine::search::{get_root_moves, is_capture},
+    evaluation::eval::get_piece_attacks,
+    tablebases::dtm::{
+        DTM_PIECES, Dtm, DtmTable, DtmTables, Material, get_normalized_name, parse_side,
+    },
+};
+
+pub const DTM_TABLE_NAMES: [&str; 35] = [
+    "KQvK", "KRvK", "KBvK", "KNvK", "KPvK", "KQQvK", "KQRvK", "KQBvK", "KQNvK", "KQPvK", "KRRvK",
+    "KRBvK", "KRNvK", "KRPvK", "KBBvK", "KBNvK", "KBPvK", "KNNvK", "KNPvK", "KPPvK", "KQvKQ",
+    "KQvKR", "KQvKB", "KQvKN", "KQvKP", "KRvKR", "KRvKB", "KRvKN", "KRvKP", "KBvKB", "KBvKN",
+    "KBvKP", "KNvKN", "KNvKP", "KPvKP",
+];
+
+const MAX_PLIES: usize = 256;
+const VALID: u8 = 1;
+const DETERMINED: u8 = 2;
+const PROCESSED: u8 = 4;
+const EXIT_DRAW: u8 = 8;
+
+struct Generator<'a> {
+    material: &'a Material,
+    tables: &'a DtmTables,
+    magic_bitboards: &'a MagicBitboards,
+    values: Vec<u8>,
+    states: Vec<u8>,
+    remaining: Vec<u8>,
+    exit_wins: Vec<u8>,
+    exit_losses: Vec<u8>,
+    buckets: Vec<Vec<u32>>,
+}
+
+impl Generator<'_> {
+    fn initialize(&mut self) -> Option<()> {
+        let mut children = Vec::with_capacity(64);
+        for index in 0..self.values.len() {
+            let (squares, stm_is_weak) = self.material.get_squares(index);
+            if !self.is_valid(&squares, stm_is_weak)
+                || self.material.get_index(&squares, stm_is_weak) != index
+            {
+                continue;
+            }
+            self.states[index] = VALID;
+            let board = self.get_board(&squares, stm_is_weak);
+            let moves = get_root_moves(&board, self.magic_bitboards, &[]);
+            if moves.count == 0 {
+                if generate_moves(&board, self.magic_bitboards).check_count > 0 {
+                    self.push(index, Dtm::Loss(0));
+                } else {
+                    self.states[index] |= DETERMINED;
+                }
+                continue;
+            }
+
+            children.clear();
+            for c_move in &moves.moves[..moves.count] {
+                if is_capture(&board, c_move) || c_move.promotion != EMPTY {
+                    let mut child = board;
+                    child.make_move(*c_move);
+                    let dtm = self.tables.probe(&child)?.get_parent();
+                    match dtm {
+                        Dtm::Draw => self.states[index] |= EXIT_DRAW,
+                        Dtm::Win(moves) => {
+                            let best = self.exit_wins[index];
+                            self.exit_wins[index] = if best == 0 { moves } else { best.min(moves) };
+                        }
+                        Dtm::Loss(moves) => {
+                            self.exit_losses[index] = self.exit_losses[index].max(moves + 1);
+                        }
+                    }
+                } else {
+                    // Double pushes index the child without en passant rights, so a reply
+                    // capturing en passant is not seen by the generator.
+                    let mut child_squares = squares;
+                    let moved = child_squares
+                        .iter()
+                        .position(|square| *square == c_move.from_square)
+                        .unwrap_or(0);
+                    child_squares[moved] = c_move.to_square;
+                    children.push(self.material.get_index(&child_squares, !stm_is_weak));
+                }
+            }
+            children.sort_unstable();
+            children.dedup();
+            self.remaining[index] = children.len() as u8;
+
+            if self.exit_wins[index] > 0 {
+                let dtm = Dtm::Win(self.exit_wins[index]);
+                self.buckets[dtm.get_plies().unwrap_or(0)].push(index as u32);
+            } else if children.is_empty() {
+                self.resolve_without_moves(index, 0);
+            }
+        }
+        Some(())
+    }
+
+    fn propagate(&mut self) {
+        let mut predecessors = Vec::with_capacity(128);
+        for plies in 0..MAX_PLIES {
+            let bucket = std::mem::take(&mut self.buckets[plies]);
+            let dtm = Dtm::from_plies(plies);
+            for index in bucket {
+                let index = index as usize;
+                let state = self.states[index];
+                if state & PROCESSED != 0
+                    || (state & DETERMINED != 0 && self.values[index] != dtm.to_byte())
+                {
+                    continue;
+                }
+                self.values[index] = dtm.to_byte();
+                self.states[index] |= DETERMINED | PROCESSED;
+
+                self.get_predecessors(index, &mut predecessors);
+                for predecessor in predecessors.iter().map(|index| *index as usize) {
+                    if self.states[predecessor] & DETERMINED != 0 {
+                        continue;
+                    }
+                    if let Dtm::Loss(_) = dtm {
+                        self.push(predecessor, Dtm::from_plies(plies + 1));
+                        continue;
+                    }
+                    self.remaining[predecessor] -= 1;
+                    if self.remaining[predecessor] == 0 && self.exit_wins[predecessor] == 0 {
+                        self.resolve_without_moves(predecessor, plies + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    fn resolve_without_moves(&mut self, index: usize, min_plies: usize) {
+        if self.states[index] & EXIT_DRAW != 0 {
+            self.states[index] |= DETERMINED;
+            return;
+        }
+        let exit_plies = match self.exit_losses[index] {
+            0 => 0,
+            moves => Dtm::Loss(moves - 1).get_plies().unwrap_or(0),
+        };
+        self.push(index, Dtm::from_plies(min_plies.max(exit_plies)));
+    }
+
+    fn push(&mut self, index: usize, dtm: Dtm) {
+        self.values[index] = dtm.to_byte();
+        self.states[index] |= DETERMINED;
+        self.buckets[dtm.get_plies().unwrap_or(0)].push(index as u32);
+    }
+
+    fn get_predecessors(&self, index: usize, predecessors: &mut Vec<u32>) {
+        predecessors.clear();
+        let (squares, stm_is_weak) = self.material.get_squares(index);
+        let count = self.material.pieces.len();
+        let mover = if stm_is_weak { WHITE } else { BLACK };
+        let occupancy = squares[..count]
+            .iter()
+            .fold(0u64, |occupancy, square| occupancy | 1 << square);
+
+        for (i, piece) in self.material.pieces.iter().enumerate() {
+            if piece.color != mover {
+                continue;
+            }
+            let square = squares[i];
+            let mut from_squares = match piece.piece {
+                PAWN => get_pawn_unmoves(square, mover, occupancy),
+                KING => self.magic_bitboards.king_attacks[square as usize] & !occupancy,
+                _ => {
+                    get_piece_attacks(self.magic_bitboards, piece.piece, square, occupancy)
+                        & !occupancy
+                }
+            };
+            while from_squares != 0 {
+                let from_square = from_squares.trailing_zeros() as u8;
+                from_squares &= from_squares - 1;
+                let mut previous = squares;
+                previous[i] = from_square;
+                let defender = if mover == WHITE { 1 } else { 0 };
+                let previous_occupancy = occupancy ^ (1 << square) ^ (1 << from_square);
+                if self.is_attacked(&previous, previous[defender], mover, previous_occupancy) {
+                    continue;
+                }
+                predecessors.push(self.material.get_index(&previous, !stm_is_weak) as u32);
+            }
+        }
+        predecessors.sort_unstable();
+        predecessors.dedup();
+    }
+
+    fn is_valid(&self, squares: &[u8; DTM_PIECES], stm_is_weak: bool) -> bool {
+        let count = self.material.pieces.len();
+        let mut occupancy = 0u64;
+        for (square, piece) in squares[..count].iter().zip(&self.material.pieces) {
+            if occupancy & 1 << square != 0 || (piece.piece == PAWN && matches!(square / 8, 0 | 7))
+            {
+                return false;
+            }
+            occupancy |= 1 << square;
+        }
+        let (defender, attacker) = if stm_is_weak { (0, BLACK) } else { (1, WHITE) };
+        !self.is_attacked(squares, squares[defender], attacker, occupancy)
+    }
+
+    fn is_attacked(
+        &self,
+        squares: &[u8; DTM_PIECES],
+        target: u8,
+        attacker: u8,
+        occupancy: u64,
+    ) -> bool {
+        let target = 1u64 << target;
+        self.material
+            .pieces
+            .iter()
+            .zip(squares)
+            .filter(|(piece, _)| piece.color == attacker)
+            .any(|(piece, square)| {
+                let attacks = match piece.piece {
+                    PAWN if attacker == WHITE => {
+                        self.magic_bitboards.w_pawn_capture[*square as usize]
+                    }
+                    PAWN => self.magic_bitboards.b_pawn_capture[*square as usize],
+                    KING => self.magic_bitboards.king_attacks[*square as usize],
+                    _ => get_piece_attacks(self.magic_bitboards, piece.piece, *square, occupancy),
+                };
+                attacks & target != 0
+            })
+    }
+
+    fn get_board(&self, squares: &[u8; DTM_PIECES], stm_is_weak: bool) -> Board {
+        let mut board = Board::new();
+        for (square, piece) in squares.iter().zip(&self.material.pieces) {
+            board.set_piece_at(*square, piece.piece | piece.color);
+        }
+        board.stm = if stm_is_weak { BLACK } else { WHITE };
+        board
+    }
+}
+
+fn get_pawn_unmoves(square: u8, color: u8, occupancy: u64) -> u64 {
+    let rank = 7 - square / 8;
+    let (relative_rank, behind) = if color == WHITE {
+        (rank, square + 8)
+    } else {
+        (7 - rank, square.wrapping_sub(8))
+    };
+    if relative_rank < 2 || occupancy & 1 << behind != 0 {
+        return 0;
+    }
+    let mut from_squares = 1 << behind;
+    let double = if color == WHITE {
+        square + 16
+    } else {
+        square.wrapping_sub(16)
+    };
+    if relative_rank == 3 && occupancy & 1 << double == 0 {
+        from_squares |= 1 << double;
+    }
+    from_squares
+}
+
+/// Generates the table for `material`, or `None` if a subtable it captures or promotes into
+/// is missing from `tables`.
+///
+/// Positions are stored without en passant rights: the generator does not consider en
+/// passant captures, and `DtmTables::probe` declines positions where one may be possible.
+pub fn generate_table(
+    material: &Material,
+    tables: &DtmTables,
+    magic_bitboards: &MagicBitboards,
+) -> Option<DtmTable> {
+    let size = material.get_size();
+    let mut generator = Generator {
+        material,
+        tables,
+        magic_bitboards,
+        values: vec![0; size],
+        states: vec![0; size],
+        remaining: vec![0; size],
+        exit_wins: vec![0; size],
+        exit_losses: vec![0; size],
+        buckets: vec![Vec::new(); MAX_PLIES],
+    };
+    generator.initialize()?;
+    generator.propagate();
+    Some(DtmTable {
+        material: material.clone(),
+        values: generator.values,
+    })
+}
+
+pub fn get_subtable_names(name: &str) -> Vec<String> {
+    let Some((strong, weak)) = name.split_once('v') else {
+        return Vec::new();
+    };
+    let (Some(strong), Some(weak)) = (parse_side(strong), parse_side(weak)) else {
+        return Vec::new();
+    };
+    let mut names = Vec::new();
+    for (own, other) in [(&strong, &weak), (&weak, &strong)] {
+        for i in 1..own.len() {
+            let mut captured = own.clone();
+            captured.remove(i);
+            names.push(get_normalized_name(&captured, other));
+            if own[i] == PAWN {
+                for promotion in [2, 3, 4, 5] {
+                    let mut promoted = own.clone();
+                    promoted[i] = promotion;
+                    promoted[1..].sort_by(|a, b| b.cmp(a));
+                    names.push(get_normalized_name(&promoted, other));
+                }
+            }
+        }
+    }
+    names.retain(|subtable| subtable != "KvK");
+    names.sort();
+    names.dedup();
+    names
+}
+
+pub fn ensure_table(
+    name: &str,
+    tables: &mut DtmTables,
+    directory: Option<&Path>,
+    magic_bitboards: &MagicBitboards,
+) -> Result<(), String> {
+    if tables.contains(name) {
+        return Ok(());
+    }
+    let material = Material::new(name).ok_or(format!("invalid DTM material {name}"))?;
+    for subtable in get_subtable_names(name) {
+        ensure_table(&subtable, tables, directory, magic_bitboards)?;
+    }
+    let start = Instant::now();
+    let table = generate_table(&material, tables, magic_bitboards)
+        .ok_or(format!("missing DTM subtable for {name}"))?;
+    println!(
+        "info string generated {name} {} positions max dtm {} in {:.1}s",
+        table.values.len(),
+        table.get_max_win(),
+        start.elapsed().as_secs_f64()
+    );
+    if let Some(directory) = directory {
+        table.save(directory)?;
+    }
+    tables.insert(table);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_rep::magic_bitboards::get_test_magic_bitboards;
+
+    const KNOWN_MAX_DTM: [(&str, u8); 10] = [
+        ("KQvK", 10),
+        ("KRvK", 16),
+        ("KPvK", 28),
+        ("KBBvK", 19),
+        ("KBNvK", 33),
+        ("KQvKN", 21),
+        ("KQvKB", 17),
+        ("KQvKR", 35),
+        ("KRvKB", 29),
+        ("KRvKN", 40),
+    ];
+
+    fn check_max_dtm(piece_count: usize) {
+        let magic_bitboards = get_test_magic_bitboards();
+        let mut tables = DtmTables::default();
+        for (name, expected) in KNOWN_MAX_DTM {
+            if name.len() - 1 != piece_count {
+                continue;
+            }
+            ensure_table(name, &mut tables, None, magic_bitboards).unwrap();
+            let max_dtm = tables.get(name).map_or(0, |table| table.get_max_win());
+            assert_eq!(max_dtm, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn three_man_max_dtm() {
+        check_max_dtm(3);
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds"]
+    fn four_man_max_dtm() {
+        check_max_dtm(4);
+    }
+
+    #[test]
+    fn missing_subtable_is_an_error() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let material = Material::new("KPvK").unwrap();
+        let tables = DtmTables::default();
+        assert!(generate_table(&material, &tables, magic_bitboards).is_none());
+    }
+}
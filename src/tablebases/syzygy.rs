@@ -0,0 +1,1323 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+use crate::{
+    board_rep::{
+        bit_operations::count_bits,
+        board::{BLACK, Board, PAWN, PIECE_MASK, WHITE},
+        c_move::CMove,
+        c_move_list::CMoveList,
+        magic_bitboards::MagicBitboards,
+        move_gen::generate_moves,
+    },
+    engine::search::{get_root_moves, is_capture},
+    evaluation::endgame::{get_material_key, get_signature_key},
+};
+
+pub const WDL_LOSS: i32 = -2;
+pub const WDL_BLESSED_LOSS: i32 = -1;
+pub const WDL_DRAW: i32 = 0;
+pub const WDL_CURSED_WIN: i32 = 1;
+pub const WDL_WIN: i32 = 2;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+const WDL_SUFFIX: &str = ".rtbw";
+const DTZ_SUFFIX: &str = ".rtbz";
+const TB_PIECES: usize = 7;
+
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+static TABLEBASES: RwLock<Option<Arc<Tablebases>>> = RwLock::new(None);
+static ENCODING: OnceLock<Encoding> = OnceLock::new();
+
+pub fn set_tablebases(tablebases: Option<Tablebases>) {
+    *TABLEBASES.write().unwrap() = tablebases.map(Arc::new);
+}
+
+pub fn get_tablebases() -> Option<Arc<Tablebases>> {
+    TABLEBASES.read().unwrap().clone()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ProbeState {
+    Ok,
+    Fail,
+    ChangeStm,
+    ZeroingBestMove,
+}
+
+struct Encoding {
+    map_b1h1h7: [u64; 64],
+    map_a1d1d4: [u64; 64],
+    map_kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; 6],
+    map_pawns: [u64; 64],
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+impl Encoding {
+    fn new() -> Self {
+        let mut encoding = Encoding {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 6],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        let mut code = 0;
+        for square in 0..64 {
+            if off_diagonal(square) < 0 {
+                encoding.map_b1h1h7[square] = code;
+                code += 1;
+            }
+        }
+
+        let mut diagonal = Vec::new();
+        code = 0;
+        for square in 0..28 {
+            if off_diagonal(square) < 0 && square % 8 <= 3 {
+                encoding.map_a1d1d4[square] = code;
+                code += 1;
+            } else if off_diagonal(square) == 0 && square % 8 <= 3 {
+                diagonal.push(square);
+            }
+        }
+        for square in diagonal {
+            encoding.map_a1d1d4[square] = code;
+            code += 1;
+        }
+
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for index in 0..10 {
+            for king in 0..28 {
+                if encoding.map_a1d1d4[king] != index || (index == 0 && king != 1) {
+                    continue;
+                }
+                for other in 0..64 {
+                    if get_distance(king, other) <= 1 {
+                        continue;
+                    }
+                    if off_diagonal(king) == 0 && off_diagonal(other) > 0 {
+                        continue;
+                    }
+                    if off_diagonal(king) == 0 && off_diagonal(other) == 0 {
+                        both_on_diagonal.push((index as usize, other));
+                    } else {
+                        encoding.map_kk[index as usize][other] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (index, other) in both_on_diagonal {
+            encoding.map_kk[index][other] = code;
+            code += 1;
+        }
+
+        encoding.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..6.min(n + 1) {
+                encoding.binomial[k][n] = if k > 0 {
+                    encoding.binomial[k - 1][n - 1]
+                } else {
+                    0
+                } + if k < n {
+                    encoding.binomial[k][n - 1]
+                } else {
+                    0
+                };
+            }
+        }
+
+        let mut available_squares = 47;
+        for lead_pawns in 1..=5 {
+            for file in 0..4 {
+                let mut index = 0;
+                for rank in 1..7 {
+                    let square = rank * 8 + file;
+                    if lead_pawns == 1 {
+                        encoding.map_pawns[square] = available_squares;
+                        encoding.map_pawns[square ^ 7] = available_squares - 1;
+                        available_squares = available_squares.saturating_sub(2);
+                    }
+                    encoding.lead_pawn_idx[lead_pawns][square] = index;
+                    index += encoding.binomial[lead_pawns - 1][encoding.map_pawns[square] as usize];
+                }
+                encoding.lead_pawns_size[lead_pawns][file] = index;
+            }
+        }
+        encoding
+    }
+}
+
+#[derive(Clone, Default)]
+struct PairsData {
+    flags: u8,
+    block_size: usize,
+    span: usize,
+    block_count: usize,
+    max_sym_len: usize,
+    min_sym_len: usize,
+    lowest_sym: usize,
+    btree: usize,
+    block_length: usize,
+    block_length_size: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    data: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    pieces: [u8; TB_PIECES],
+    group_idx: [u64; TB_PIECES + 1],
+    group_len: [usize; TB_PIECES + 1],
+    map_idx: [usize; 4],
+}
+
+struct TableData {
+    bytes: Vec<u8>,
+    pairs: Vec<PairsData>,
+    sides: usize,
+    map: usize,
+}
+
+impl TableData {
+    fn get(&self, stm: usize, file: usize) -> &PairsData {
+        &self.pairs[file * self.sides + stm % self.sides]
+    }
+
+    fn get_mut(&mut self, stm: usize, file: usize) -> &mut PairsData {
+        &mut self.pairs[file * self.sides + stm % self.sides]
+    }
+
+    fn read_u8(&self, offset: usize) -> u8 {
+        self.bytes.get(offset).copied().unwrap_or(0)
+    }
+
+    fn read_u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.read_u8(offset), self.read_u8(offset + 1)])
+    }
+
+    fn read_u32(&self, offset: usize) -> u32 {
+        u32::from_le_bytes([
+            self.read_u8(offset),
+            self.read_u8(offset + 1),
+            self.read_u8(offset + 2),
+            self.read_u8(offset + 3),
+        ])
+    }
+
+    fn read_u32_be(&self, offset: usize) -> u32 {
+        u32::from_be_bytes([
+            self.read_u8(offset),
+            self.read_u8(offset + 1),
+            self.read_u8(offset + 2),
+            self.read_u8(offset + 3),
+        ])
+    }
+
+    fn get_btree(&self, pairs: &PairsData, sym: usize) -> (usize, usize) {
+        let offset = pairs.btree + sym * 3;
+        let bytes = [
+            self.read_u8(offset) as usize,
+            self.read_u8(offset + 1) as usize,
+            self.read_u8(offset + 2) as usize,
+        ];
+        let left = ((bytes[1] & 0xF) << 8) | bytes[0];
+        let right = (bytes[2] << 4) | (bytes[1] >> 4);
+        (left, right)
+    }
+}
+
+struct Table {
+    key: u64,
+    key2: u64,
+    piece_count: usize,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    pawn_count: [usize; 2],
+    is_dtz: bool,
+    path: Option<PathBuf>,
+    data: OnceLock<Option<TableData>>,
+}
+
+impl Table {
+    fn new(name: &str, path: Option<PathBuf>, is_dtz: bool) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+        let valid = |pieces: &str| {
+            pieces.starts_with('K') && pieces.chars().all(|piece| "KQRBNP".contains(piece))
+        };
+        if !valid(white) || !valid(black) {
+            return None;
+        }
+        let count = |pieces: &str, piece: char| pieces.chars().filter(|c| *c == piece).count();
+        let has_unique_pieces = "PNBRQ"
+            .chars()
+            .any(|piece| count(white, piece) == 1 || count(black, piece) == 1);
+        let white_pawns = count(white, 'P');
+        let black_pawns = count(black, 'P');
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        let pawn_count = if white_leads {
+            [white_pawns, black_pawns]
+        } else {
+            [black_pawns, white_pawns]
+        };
+        Some(Table {
+            key: get_signature_key(white.as_bytes(), black.as_bytes()),
+            key2: get_signature_key(black.as_bytes(), white.as_bytes()),
+            piece_count: white.len() + black.len(),
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            pawn_count,
+            is_dtz,
+            path,
+            data: OnceLock::new(),
+        })
+    }
+
+    fn get_data(&self) -> Option<&TableData> {
+        self.data.get_or_init(|| self.load()).as_ref()
+    }
+
+    fn load(&self) -> Option<TableData> {
+        let bytes = fs::read(self.path.as_ref()?).ok()?;
+        let magic = if self.is_dtz { DTZ_MAGIC } else { WDL_MAGIC };
+        if bytes.len() < 5 || bytes[..4] != magic {
+            println!(
+                "info string corrupted tablebase file {}",
+                self.path.as_ref()?.display()
+            );
+            return None;
+        }
+        let sides = if !self.is_dtz && self.key != self.key2 {
+            2
+        } else {
+            1
+        };
+        let files = if self.has_pawns { 4 } else { 1 };
+        let mut data = TableData {
+            bytes,
+            pairs: vec![PairsData::default(); sides * files],
+            sides,
+            map: 0,
+        };
+        let end = self.init_data(&mut data, 4, sides, files);
+        if end > data.bytes.len() {
+            println!(
+                "info string truncated tablebase file {}",
+                self.path.as_ref()?.display()
+            );
+            return None;
+        }
+        Some(data)
+    }
+
+    fn init_data(
+        &self,
+        data: &mut TableData,
+        mut offset: usize,
+        sides: usize,
+        files: usize,
+    ) -> usize {
+        let encoding = ENCODING.get_or_init(Encoding::new);
+        offset += 1;
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+
+        for file in 0..files {
+            let first = data.read_u8(offset);
+            let second = if both_pawns {
+                data.read_u8(offset + 1)
+            } else {
+                0xFF
+            };
+            let orders = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            offset += 1 + both_pawns as usize;
+
+            for k in 0..self.piece_count {
+                let byte = data.read_u8(offset);
+                for side in 0..sides {
+                    data.get_mut(side, file).pieces[k] =
+                        if side == 1 { byte >> 4 } else { byte & 0xF };
+                }
+                offset += 1;
+            }
+            for (side, order) in orders.iter().enumerate().take(sides) {
+                self.set_groups(encoding, data.get_mut(side, file), order, file);
+            }
+        }
+        offset += offset & 1;
+
+        for file in 0..files {
+            for side in 0..sides {
+                offset = set_sizes(data, side, file, offset);
+            }
+        }
+
+        if self.is_dtz {
+            data.map = offset;
+            for file in 0..files {
+                let flags = data.get(0, file).flags;
+                if flags & FLAG_MAPPED == 0 {
+                    continue;
+                }
+                for i in 0..4 {
+                    if flags & FLAG_WIDE != 0 {
+                        offset += offset & 1;
+                        data.get_mut(0, file).map_idx[i] = (offset - data.map) / 2 + 1;
+                        offset += 2 * data.read_u16(offset) as usize + 2;
+                    } else {
+                        data.get_mut(0, file).map_idx[i] = offset - data.map + 1;
+                        offset += data.read_u8(offset) as usize + 1;
+                    }
+                }
+            }
+            offset += offset & 1;
+        }
+
+        for file in 0..files {
+            for side in 0..sides {
+                let pairs = data.get_mut(side, file);
+                pairs.sparse_index = offset;
+                offset += pairs.sparse_index_size * 6;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let pairs = data.get_mut(side, file);
+                pairs.block_length = offset;
+                offset += pairs.block_length_size * 2;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                offset = (offset + 0x3F) & !0x3F;
+                let pairs = data.get_mut(side, file);
+                pairs.data = offset;
+                offset += pairs.block_count * pairs.block_size;
+            }
+        }
+        offset
+    }
+
+    fn set_groups(&self, encoding: &Encoding, pairs: &mut PairsData, order: &[u8; 2], file: usize) {
+        let mut n = 0;
+        let mut first_len: i32 = if self.has_pawns {
+            0
+        } else if self.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+        pairs.group_len[n] = 1;
+        for i in 1..self.piece_count {
+            first_len -= 1;
+            if first_len > 0 || pairs.pieces[i] == pairs.pieces[i - 1] {
+                pairs.group_len[n] += 1;
+            } else {
+                n += 1;
+                pairs.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        pairs.group_len[n] = 0;
+
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares =
+            64 - pairs.group_len[0] - if both_pawns { pairs.group_len[1] } else { 0 };
+        let mut idx: u64 = 1;
+        let mut k = 0;
+        while next < n || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                pairs.group_idx[0] = idx;
+                idx *= if self.has_pawns {
+                    encoding.lead_pawns_size[pairs.group_len[0]][file]
+                } else if self.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] as usize {
+                pairs.group_idx[1] = idx;
+                idx *= encoding.binomial[pairs.group_len[1]][48 - pairs.group_len[0]];
+            } else {
+                pairs.group_idx[next] = idx;
+                idx *= encoding.binomial[pairs.group_len[next]][free_squares];
+                free_squares -= pairs.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        pairs.group_idx[n] = idx;
+    }
+}
+
+fn set_sizes(data: &mut TableData, side: usize, file: usize, mut offset: usize) -> usize {
+    let flags = data.read_u8(offset);
+    offset += 1;
+    if flags & FLAG_SINGLE_VALUE != 0 {
+        let value = data.read_u8(offset) as usize;
+        let pairs = data.get_mut(side, file);
+        pairs.flags = flags;
+        pairs.min_sym_len = value;
+        return offset + 1;
+    }
+
+    let block_size = 1usize << data.read_u8(offset);
+    let span = 1usize << data.read_u8(offset + 1);
+    let padding = data.read_u8(offset + 2) as usize;
+    let block_count = data.read_u32(offset + 3) as usize;
+    let max_sym_len = data.read_u8(offset + 7) as usize;
+    let min_sym_len = data.read_u8(offset + 8) as usize;
+    offset += 9;
+    let lowest_sym = offset;
+    let base_len = (max_sym_len + 1).saturating_sub(min_sym_len);
+    let mut base64 = vec![0u64; base_len];
+    for i in (0..base_len.saturating_sub(1)).rev() {
+        let lowest = data.read_u16(lowest_sym + i * 2) as i64;
+        let next_lowest = data.read_u16(lowest_sym + (i + 1) * 2) as i64;
+        base64[i] = ((base64[i + 1] as i64 + lowest - next_lowest) / 2) as u64;
+    }
+    for (i, base) in base64.iter_mut().enumerate() {
+        let shift = 64 - i - min_sym_len;
+        *base = if shift >= 64 { 0 } else { *base << shift };
+    }
+    offset += base_len * 2;
+    let sym_count = data.read_u16(offset) as usize;
+    offset += 2;
+    let btree = offset;
+
+    let pairs = data.get(side, file);
+    let tb_size = pairs.group_idx[pairs
+        .group_len
+        .iter()
+        .position(|len| *len == 0)
+        .unwrap_or(0)];
+    let mut pairs = pairs.clone();
+    pairs.flags = flags;
+    pairs.block_size = block_size;
+    pairs.span = span;
+    pairs.sparse_index_size = tb_size.div_ceil(span as u64) as usize;
+    pairs.block_count = block_count;
+    pairs.block_length_size = block_count + padding;
+    pairs.max_sym_len = max_sym_len;
+    pairs.min_sym_len = min_sym_len;
+    pairs.lowest_sym = lowest_sym;
+    pairs.base64 = base64;
+    pairs.btree = btree;
+    pairs.symlen = vec![0; sym_count];
+    let mut visited = vec![false; sym_count];
+    for sym in 0..sym_count {
+        if !visited[sym] {
+            pairs.symlen[sym] = set_symlen(data, &mut pairs, sym, &mut visited);
+        }
+    }
+    *data.get_mut(side, file) = pairs;
+    btree + sym_count * 3 + (sym_count & 1)
+}
+
+fn set_symlen(data: &TableData, pairs: &mut PairsData, sym: usize, visited: &mut [bool]) -> u8 {
+    visited[sym] = true;
+    let (left, right) = data.get_btree(pairs, sym);
+    if right == 0xFFF {
+        return 0;
+    }
+    for child in [left, right] {
+        if child < visited.len() && !visited[child] {
+            pairs.symlen[child] = set_symlen(data, pairs, child, visited);
+        }
+    }
+    let symlen = |sym: usize| pairs.symlen.get(sym).copied().unwrap_or(0);
+    symlen(left).wrapping_add(symlen(right)).wrapping_add(1)
+}
+
+fn decompress_pairs(data: &TableData, pairs: &PairsData, idx: u64) -> i32 {
+    if pairs.flags & FLAG_SINGLE_VALUE != 0 {
+        return pairs.min_sym_len as i32;
+    }
+
+    let k = (idx / pairs.span as u64) as usize;
+    let mut block = data.read_u32(pairs.sparse_index + k * 6) as usize;
+    let mut offset = data.read_u16(pairs.sparse_index + k * 6 + 4) as i64;
+    offset += (idx % pairs.span as u64) as i64 - (pairs.span / 2) as i64;
+
+    let block_length = |block: usize| data.read_u16(pairs.block_length + block * 2) as i64;
+    while offset < 0 {
+        block -= 1;
+        offset += block_length(block) + 1;
+    }
+    while offset > block_length(block) {
+        offset -= block_length(block) + 1;
+        block += 1;
+    }
+
+    let mut ptr = pairs.data + block * pairs.block_size;
+    let mut buf64 = (data.read_u32_be(ptr) as u64) << 32 | data.read_u32_be(ptr + 4) as u64;
+    ptr += 8;
+    let mut buf64_size = 64;
+    let symlen = |sym: usize| pairs.symlen.get(sym).copied().unwrap_or(0) as i64;
+    let mut sym;
+    loop {
+        let mut len = 0;
+        while len + 1 < pairs.base64.len() && buf64 < pairs.base64[len] {
+            len += 1;
+        }
+        sym = ((buf64 - pairs.base64[len]) >> (64 - len - pairs.min_sym_len)) as usize;
+        sym += data.read_u16(pairs.lowest_sym + len * 2) as usize;
+        if offset < symlen(sym) + 1 {
+            break;
+        }
+        offset -= symlen(sym) + 1;
+        len += pairs.min_sym_len;
+        buf64 <<= len;
+        buf64_size -= len;
+        if buf64_size <= 32 {
+            buf64_size += 32;
+            buf64 |= (data.read_u32_be(ptr) as u64) << (64 - buf64_size);
+            ptr += 4;
+        }
+    }
+
+    while symlen(sym) != 0 {
+        let (left, right) = data.get_btree(pairs, sym);
+        if offset < symlen(left) + 1 {
+            sym = left;
+        } else {
+            offset -= symlen(left) + 1;
+            sym = right;
+        }
+    }
+    data.get_btree(pairs, sym).0 as i32
+}
+
+pub struct Tablebases {
+    wdl: Vec<Table>,
+    dtz: Vec<Table>,
+    index: HashMap<u64, usize>,
+    pub max_pieces: usize,
+}
+
+impl Tablebases {
+    pub fn new(paths: &str) -> Self {
+        let mut wdl_files = HashMap::new();
+        let mut dtz_files = HashMap::new();
+        let separator = if cfg!(windows) { ';' } else { ':' };
+        for directory in paths.split(separator).filter(|path| !path.is_empty()) {
+            let Ok(entries) = fs::read_dir(directory) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                if let Some(name) = name.strip_suffix(WDL_SUFFIX) {
+                    wdl_files.insert(name.to_string(), path.clone());
+                } else if let Some(name) = name.strip_suffix(DTZ_SUFFIX) {
+                    dtz_files.insert(name.to_string(), path.clone());
+                }
+            }
+        }
+
+        let mut tablebases = Tablebases {
+            wdl: Vec::new(),
+            dtz: Vec::new(),
+            index: HashMap::new(),
+            max_pieces: 0,
+        };
+        let mut names: Vec<&String> = wdl_files.keys().collect();
+        names.sort();
+        for name in names {
+            let wdl_path = wdl_files.get(name).cloned();
+            let dtz_path = dtz_files.get(name).cloned();
+            let (Some(wdl), Some(dtz)) = (
+                Table::new(name, wdl_path, false),
+                Table::new(name, dtz_path, true),
+            ) else {
+                continue;
+            };
+            if wdl.piece_count > TB_PIECES {
+                continue;
+            }
+            let index = tablebases.wdl.len();
+            tablebases.max_pieces = tablebases.max_pieces.max(wdl.piece_count);
+            tablebases.index.insert(wdl.key, index);
+            tablebases.index.insert(wdl.key2, index);
+            tablebases.wdl.push(wdl);
+            tablebases.dtz.push(dtz);
+        }
+        tablebases
+    }
+
+    pub fn get_table_count(&self) -> usize {
+        self.wdl.len()
+    }
+
+    pub fn can_probe(&self, board: &Board) -> bool {
+        board.castling == 0 && count_bits(board.all_occupancy) as usize <= self.max_pieces
+    }
+
+    pub fn probe_wdl(&self, board: &Board, magic_bitboards: &MagicBitboards) -> Option<i32> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        let mut state = ProbeState::Ok;
+        let wdl = self.search(board, magic_bitboards, false, &mut state);
+        (state != ProbeState::Fail).then_some(wdl)
+    }
+
+    pub fn probe_dtz(&self, board: &Board, magic_bitboards: &MagicBitboards) -> Option<i32> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        let mut state = ProbeState::Ok;
+        let dtz = self.get_dtz(board, magic_bitboards, &mut state);
+        (state != ProbeState::Fail).then_some(dtz)
+    }
+
+    pub fn probe_root(
+        &self,
+        board: &Board,
+        magic_bitboards: &MagicBitboards,
+        root_moves: &CMoveList,
+    ) -> Option<Vec<(CMove, i32)>> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        let mut ranked_moves = Vec::new();
+        for c_move in &root_moves.moves[..root_moves.count] {
+            let mut new_board = *board;
+            new_board.make_move(*c_move);
+            let mut state = ProbeState::Ok;
+            let mut dtz = if new_board.halfmove_clock == 0 {
+                get_dtz_before_zeroing(-self.search(&new_board, magic_bitboards, false, &mut state))
+            } else {
+                let dtz = -self.get_dtz(&new_board, magic_bitboards, &mut state);
+                dtz + dtz.signum()
+            };
+            if dtz == 2 && is_mate(&new_board, magic_bitboards) {
+                dtz = 1;
+            }
+            if state == ProbeState::Fail {
+                return None;
+            }
+            ranked_moves.push((*c_move, get_root_rank(dtz, board.halfmove_clock as i32)));
+        }
+        Some(ranked_moves)
+    }
+
+    /// Keeps only the root moves with the best tablebase rank.
+    pub fn filter_root_moves(
+        &self,
+        board: &Board,
+        magic_bitboards: &MagicBitboards,
+        root_moves: &CMoveList,
+    ) -> Option<CMoveList> {
+        let ranked_moves = self.probe_root(board, magic_bitboards, root_moves)?;
+        let best_rank = ranked_moves.iter().map(|(_, rank)| *rank).max()?;
+        let mut filtered = CMoveList::new();
+        for (c_move, rank) in ranked_moves {
+            if rank == best_rank {
+                filtered.push(c_move);
+            }
+        }
+        Some(filtered)
+    }
+
+    fn search(
+        &self,
+        board: &Board,
+        magic_bitboards: &MagicBitboards,
+        check_zeroing_moves: bool,
+        state: &mut ProbeState,
+    ) -> i32 {
+        let moves = get_root_moves(board, magic_bitboards, &[]);
+        let mut best = WDL_LOSS;
+        let mut move_count = 0;
+        for c_move in &moves.moves[..moves.count] {
+            let is_pawn_move = board.get_piece_at(c_move.from_square) & PIECE_MASK == PAWN;
+            if !is_capture(board, c_move) && (!check_zeroing_moves || !is_pawn_move) {
+                continue;
+            }
+            move_count += 1;
+            let mut new_board = *board;
+            new_board.make_move(*c_move);
+            let value = -self.search(&new_board, magic_bitboards, false, state);
+            if *state == ProbeState::Fail {
+                return WDL_DRAW;
+            }
+            if value > best {
+                best = value;
+                if value >= WDL_WIN {
+                    *state = ProbeState::ZeroingBestMove;
+                    return value;
+                }
+            }
+        }
+
+        let no_more_moves = move_count > 0 && move_count == moves.count;
+        let value = if no_more_moves {
+            best
+        } else {
+            let value = self.probe_table(board, false, WDL_DRAW, state);
+            if *state == ProbeState::Fail {
+                return WDL_DRAW;
+            }
+            value
+        };
+
+        if best >= value {
+            *state = if best > WDL_DRAW || no_more_moves {
+                ProbeState::ZeroingBestMove
+            } else {
+                ProbeState::Ok
+            };
+            return best;
+        }
+        *state = ProbeState::Ok;
+        value
+    }
+
+    fn get_dtz(
+        &self,
+        board: &Board,
+        magic_bitboards: &MagicBitboards,
+        state: &mut ProbeState,
+    ) -> i32 {
+        *state = ProbeState::Ok;
+        let wdl = self.search(board, magic_bitboards, true, state);
+        if *state == ProbeState::Fail || wdl == WDL_DRAW {
+            return 0;
+        }
+        if *state == ProbeState::ZeroingBestMove {
+            return get_dtz_before_zeroing(wdl);
+        }
+
+        let dtz = self.probe_table(board, true, wdl, state);
+        if *state == ProbeState::Fail {
+            return 0;
+        }
+        if *state != ProbeState::ChangeStm {
+            let cursed = wdl == WDL_BLESSED_LOSS || wdl == WDL_CURSED_WIN;
+            return (dtz + 100 * cursed as i32) * wdl.signum();
+        }
+
+        let mut min_dtz = 0xFFFF;
+        let moves = get_root_moves(board, magic_bitboards, &[]);
+        for c_move in &moves.moves[..moves.count] {
+            let zeroing = is_capture(board, c_move)
+                || board.get_piece_at(c_move.from_square) & PIECE_MASK == PAWN;
+            let mut new_board = *board;
+            new_board.make_move(*c_move);
+            let mut dtz = if zeroing {
+                -get_dtz_before_zeroing(self.search(&new_board, magic_bitboards, false, state))
+            } else {
+                -self.get_dtz(&new_board, magic_bitboards, state)
+            };
+            if dtz == 1 && is_mate(&new_board, magic_bitboards) {
+                min_dtz = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == wdl.signum() {
+                min_dtz = dtz;
+            }
+            if *state == ProbeState::Fail {
+                return 0;
+            }
+        }
+        if min_dtz == 0xFFFF { -1 } else { min_dtz }
+    }
+
+    fn probe_table(&self, board: &Board, is_dtz: bool, wdl: i32, state: &mut ProbeState) -> i32 {
+        if count_bits(board.all_occupancy) == 2 {
+            return WDL_DRAW;
+        }
+        let key = get_material_key(board, WHITE);
+        let Some(index) = self.index.get(&key) else {
+            *state = ProbeState::Fail;
+            return 0;
+        };
+        let table = if is_dtz {
+            &self.dtz[*index]
+        } else {
+            &self.wdl[*index]
+        };
+        let Some(data) = table.get_data() else {
+            *state = ProbeState::Fail;
+            return 0;
+        };
+        probe_table_data(board, table, data, wdl, state)
+    }
+}
+
+fn probe_table_data(
+    board: &Board,
+    table: &Table,
+    data: &TableData,
+    wdl: i32,
+    state: &mut ProbeState,
+) -> i32 {
+    let (stm, tb_file, idx) = get_table_index(board, table, data);
+    if table.is_dtz {
+        let flags = data.get(stm, tb_file).flags;
+        let symmetric = table.key == table.key2 && !table.has_pawns;
+        if (flags & FLAG_STM) as usize != stm && !symmetric {
+            *state = ProbeState::ChangeStm;
+            return 0;
+        }
+    }
+
+    let value = decompress_pairs(data, data.get(stm, tb_file), idx);
+    if table.is_dtz {
+        map_dtz_score(data, tb_file, value, wdl)
+    } else {
+        value - 2
+    }
+}
+
+/// Returns the side to move, pawn file and index of `board` within `table`.
+fn get_table_index(board: &Board, table: &Table, data: &TableData) -> (usize, usize, u64) {
+    let encoding = ENCODING.get_or_init(Encoding::new);
+    let symmetric_black_to_move = table.key == table.key2 && board.stm == BLACK;
+    let black_stronger = get_material_key(board, WHITE) != table.key;
+    let flip = symmetric_black_to_move || black_stronger;
+    let flip_color = if flip { 8 } else { 0 };
+    let flip_squares = if flip { 56 } else { 0 };
+    let stm = (flip ^ (board.stm == BLACK)) as usize;
+
+    let mut squares = [0usize; TB_PIECES];
+    let mut pieces = [0u8; TB_PIECES];
+    let mut size = 0;
+    let mut lead_pawns = 0;
+    let mut lead_pawn_count = 0;
+    let mut tb_file = 0;
+
+    if table.has_pawns {
+        let lead_piece = data.get(0, 0).pieces[0] ^ flip_color;
+        let lead_color = if lead_piece & 8 == 0 { WHITE } else { BLACK };
+        lead_pawns = if lead_color == WHITE {
+            board.w_pawns
+        } else {
+            board.b_pawns
+        };
+        for square in 0..64 {
+            if lead_pawns & 1 << (square ^ 56) != 0 {
+                squares[size] = square ^ flip_squares;
+                size += 1;
+            }
+        }
+        lead_pawn_count = size;
+        let lead = (0..lead_pawn_count)
+            .max_by_key(|i| encoding.map_pawns[squares[*i]])
+            .unwrap_or(0);
+        squares.swap(0, lead);
+        tb_file = (squares[0] % 8).min(7 - squares[0] % 8);
+    }
+
+    for square in 0..64 {
+        let bit = 1u64 << (square ^ 56);
+        if board.all_occupancy & bit == 0 || lead_pawns & bit != 0 {
+            continue;
+        }
+        let piece = board.get_piece_at((square ^ 56) as u8);
+        let color = if piece & BLACK != 0 { 8 } else { 0 };
+        squares[size] = square ^ flip_squares;
+        pieces[size] = ((piece & PIECE_MASK) | color) ^ flip_color;
+        size += 1;
+    }
+
+    let pairs = data.get(stm, tb_file);
+    for i in lead_pawn_count..size.saturating_sub(1) {
+        for j in i + 1..size {
+            if pairs.pieces[i] == pieces[j] {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+                break;
+            }
+        }
+    }
+
+    if squares[0] % 8 > 3 {
+        for square in squares.iter_mut().take(size) {
+            *square ^= 7;
+        }
+    }
+
+    let mut idx;
+    if table.has_pawns {
+        idx = encoding.lead_pawn_idx[lead_pawn_count][squares[0]];
+        squares[1..lead_pawn_count].sort_by_key(|square| encoding.map_pawns[*square]);
+        for (i, square) in squares.iter().enumerate().take(lead_pawn_count).skip(1) {
+            idx += encoding.binomial[i][encoding.map_pawns[*square] as usize];
+        }
+    } else {
+        if squares[0] / 8 > 3 {
+            for square in squares.iter_mut().take(size) {
+                *square ^= 56;
+            }
+        }
+        for i in 0..pairs.group_len[0] {
+            let off = off_diagonal(squares[i]);
+            if off == 0 {
+                continue;
+            }
+            if off > 0 {
+                for square in squares.iter_mut().take(size).skip(i) {
+                    *square = ((*square >> 3) | (*square << 3)) & 63;
+                }
+            }
+            break;
+        }
+
+        if table.has_unique_pieces {
+            let adjust1 = (squares[1] > squares[0]) as u64;
+            let adjust2 = (squares[2] > squares[0]) as u64 + (squares[2] > squares[1]) as u64;
+            let [s0, s1, s2] = [squares[0], squares[1], squares[2]];
+            let rank = |square: usize| (square / 8) as u64;
+            idx = if off_diagonal(s0) != 0 {
+                (encoding.map_a1d1d4[s0] * 63 + (s1 as u64 - adjust1)) * 62 + s2 as u64 - adjust2
+            } else if off_diagonal(s1) != 0 {
+                (6 * 63 + rank(s0) * 28 + encoding.map_b1h1h7[s1]) * 62 + s2 as u64 - adjust2
+            } else if off_diagonal(s2) != 0 {
+                6 * 63 * 62
+                    + 4 * 28 * 62
+                    + rank(s0) * 7 * 28
+                    + (rank(s1) - adjust1) * 28
+                    + encoding.map_b1h1h7[s2]
+            } else {
+                6 * 63 * 62
+                    + 4 * 28 * 62
+                    + 4 * 7 * 28
+                    + rank(s0) * 7 * 6
+                    + (rank(s1) - adjust1) * 6
+                    + (rank(s2) - adjust2)
+            };
+        } else {
+            idx = encoding.map_kk[encoding.map_a1d1d4[squares[0]] as usize][squares[1]];
+        }
+    }
+
+    idx *= pairs.group_idx[0];
+    let mut group_start = pairs.group_len[0];
+    let mut remaining_pawns = table.has_pawns && table.pawn_count[1] > 0;
+    let mut next = 1;
+    while pairs.group_len[next] != 0 {
+        let group_len = pairs.group_len[next];
+        squares[group_start..group_start + group_len].sort();
+        let mut n = 0;
+        for i in 0..group_len {
+            let square = squares[group_start + i];
+            let adjust = squares[..group_start]
+                .iter()
+                .filter(|other| square > **other)
+                .count();
+            let free = square - adjust - if remaining_pawns { 8 } else { 0 };
+            n += encoding.binomial[i + 1][free];
+        }
+        remaining_pawns = false;
+        idx += n * pairs.group_idx[next];
+        group_start += group_len;
+        next += 1;
+    }
+
+    (stm, tb_file, idx)
+}
+
+fn map_dtz_score(data: &TableData, file: usize, mut value: i32, wdl: i32) -> i32 {
+    const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+    let pairs = data.get(0, file);
+    let flags = pairs.flags;
+    if flags & FLAG_MAPPED != 0 {
+        let map_idx = pairs.map_idx[WDL_MAP[(wdl + 2) as usize]];
+        value = if flags & FLAG_WIDE != 0 {
+            data.read_u16(data.map + 2 * (map_idx + value as usize)) as i32
+        } else {
+            data.read_u8(data.map + map_idx + value as usize) as i32
+        };
+    }
+    if (wdl == WDL_WIN && flags & FLAG_WIN_PLIES == 0)
+        || (wdl == WDL_LOSS && flags & FLAG_LOSS_PLIES == 0)
+        || wdl == WDL_CURSED_WIN
+        || wdl == WDL_BLESSED_LOSS
+    {
+        value *= 2;
+    }
+    value + 1
+}
+
+fn get_dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        WDL_WIN => 1,
+        WDL_CURSED_WIN => 101,
+        WDL_BLESSED_LOSS => -101,
+        WDL_LOSS => -1,
+        _ => 0,
+    }
+}
+
+fn get_root_rank(dtz: i32, halfmove_clock: i32) -> i32 {
+    if dtz > 0 {
+        if dtz + halfmove_clock <= 99 {
+            2000 - dtz.min(999)
+        } else {
+            1000 - dtz.min(999)
+        }
+    } else if dtz < 0 {
+        if -dtz + halfmove_clock <= 100 {
+            -2000 + (-dtz).min(999)
+        } else {
+            -1000 + (-dtz).min(999)
+        }
+    } else {
+        0
+    }
+}
+
+pub fn filter_root_moves(
+    board: &Board,
+    magic_bitboards: &MagicBitboards,
+    root_moves: &CMoveList,
+) -> Option<CMoveList> {
+    get_tablebases()?.filter_root_moves(board, magic_bitboards, root_moves)
+}
+
+fn is_mate(board: &Board, magic_bitboards: &MagicBitboards) -> bool {
+    generate_moves(board, magic_bitboards).check_count > 0
+        && get_root_moves(board, magic_bitboards, &[]).count == 0
+}
+
+fn off_diagonal(square: usize) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+fn get_distance(from: usize, to: usize) -> usize {
+    let file_distance = (from % 8).abs_diff(to % 8);
+    let rank_distance = (from / 8).abs_diff(to / 8);
+    file_distance.max(rank_distance)
+}
+
+#[cfg(test)]
+mod writer;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board_rep::magic_bitboards::get_test_magic_bitboards,
+        tablebases::{
+            dtm::{Dtm, DtmTables, Material},
+            retrograde::ensure_table,
+        },
+    };
+
+    const SYZYGY_POSITIONS: [(&str, i32, Option<i32>); 9] = [
+        ("8/8/8/3k4/8/8/8/KQ6 w - - 0 1", WDL_WIN, None),
+        ("8/8/8/8/8/2k5/1Q6/7K b - - 0 1", WDL_DRAW, Some(0)),
+        ("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", WDL_DRAW, Some(0)),
+        ("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", WDL_LOSS, Some(-1)),
+        ("k7/8/1K6/8/8/8/8/2Q5 w - - 0 1", WDL_WIN, Some(1)),
+        ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", WDL_WIN, None),
+        ("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1", WDL_DRAW, Some(0)),
+        ("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1", WDL_WIN, None),
+        ("k7/8/8/8/8/8/P7/K7 w - - 0 1", WDL_DRAW, Some(0)),
+    ];
+
+    const KRVKP_POSITIONS: [(&str, i32, Option<i32>); 6] = [
+        ("7k/8/p7/8/4K3/8/8/R7 w - - 0 1", WDL_WIN, Some(1)),
+        ("7k/8/p7/8/4K3/8/8/R7 b - - 0 1", WDL_LOSS, Some(-2)),
+        ("8/8/8/8/8/1kp5/8/K6R w - - 0 1", WDL_WIN, Some(7)),
+        ("8/8/8/8/8/1kp5/8/K6R b - - 0 1", WDL_LOSS, Some(-8)),
+        ("7K/8/8/8/2p5/2k5/8/R7 b - - 0 1", WDL_DRAW, Some(0)),
+        ("6K1/8/8/3k4/3p4/8/8/R7 w - - 0 1", WDL_DRAW, Some(0)),
+    ];
+
+    /// Probes `fen` and compares it with the expected values, or `None` if it is not covered.
+    fn check_position(
+        tablebases: &Tablebases,
+        fen: &str,
+        expected_wdl: i32,
+        expected_dtz: Option<i32>,
+        magic_bitboards: &MagicBitboards,
+    ) -> Option<(bool, Option<i32>, Option<i32>)> {
+        let mut board = Board::new();
+        board.set_from_fen(fen, magic_bitboards);
+        if !tablebases.can_probe(&board) {
+            return None;
+        }
+        let wdl = tablebases.probe_wdl(&board, magic_bitboards);
+        let dtz = tablebases.probe_dtz(&board, magic_bitboards);
+        let passed = wdl == Some(expected_wdl)
+            && dtz.is_some_and(|dtz| {
+                dtz.signum() == expected_wdl.signum() && expected_dtz.is_none_or(|d| d == dtz)
+            });
+        Some((passed, wdl, dtz))
+    }
+
+    fn get_test_tablebases() -> Tablebases {
+        let tablebases = Tablebases::new(writer::TEST_TABLE_DIRECTORY);
+        assert_eq!(tablebases.get_table_count(), 6);
+        tablebases
+    }
+
+    #[test]
+    fn known_positions() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let tablebases = get_test_tablebases();
+        for (fen, expected_wdl, expected_dtz) in SYZYGY_POSITIONS.into_iter().chain(KRVKP_POSITIONS)
+        {
+            let checked = check_position(
+                &tablebases,
+                fen,
+                expected_wdl,
+                expected_dtz,
+                magic_bitboards,
+            );
+            assert!(
+                checked.is_some_and(|(passed, _, _)| passed),
+                "{fen}: {checked:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn pawnless_dtz_matches_dtm() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let tablebases = get_test_tablebases();
+        let mut tables = DtmTables::default();
+        for name in ["KQvK", "KRvK"] {
+            ensure_table(name, &mut tables, None, magic_bitboards).unwrap();
+            let table = tables.get(name).unwrap();
+            let material = &table.material;
+            let count = material.pieces.len();
+            for index in (0..material.get_size()).step_by(7) {
+                let (squares, stm_is_weak) = material.get_squares(index);
+                if material.get_index(&squares[..count], stm_is_weak) != index {
+                    continue;
+                }
+                let Some(board) =
+                    writer::get_board(material, &squares[..count], stm_is_weak, magic_bitboards)
+                else {
+                    continue;
+                };
+                let expected = match Dtm::from_byte(table.values[index]) {
+                    Dtm::Draw => 0,
+                    Dtm::Loss(0) => -1,
+                    dtm @ Dtm::Loss(_) => -(dtm.get_plies().unwrap() as i32),
+                    dtm @ Dtm::Win(_) => dtm.get_plies().unwrap() as i32,
+                };
+                let dtz = tablebases.probe_dtz(&board, magic_bitboards);
+                assert_eq!(dtz, Some(expected), "{name} index {index}");
+                let wdl = tablebases.probe_wdl(&board, magic_bitboards);
+                assert_eq!(
+                    wdl,
+                    Some(expected.signum() * WDL_WIN),
+                    "{name} index {index}"
+                );
+            }
+        }
+    }
+
+    /// Compares the bundled tables position by position with the official tables, which cannot
+    /// be bundled; run with `SYZYGY_TEST_PATH=<dir> cargo test --release -- --ignored`.
+    #[test]
+    #[ignore = "needs the official Syzygy tables in SYZYGY_TEST_PATH"]
+    fn bundled_tables_match_official_tables() {
+        let path = std::env::var("SYZYGY_TEST_PATH").expect("SYZYGY_TEST_PATH is set");
+        let magic_bitboards = get_test_magic_bitboards();
+        let official = Tablebases::new(&path);
+        let bundled = get_test_tablebases();
+        for (fen, expected_wdl, expected_dtz) in SYZYGY_POSITIONS.into_iter().chain(KRVKP_POSITIONS)
+        {
+            let checked =
+                check_position(&official, fen, expected_wdl, expected_dtz, magic_bitboards);
+            assert!(
+                checked.is_some_and(|(passed, _, _)| passed),
+                "{fen}: {checked:?}"
+            );
+        }
+        for name in writer::TEST_TABLES {
+            let material = Material::new(name).unwrap();
+            let count = material.pieces.len();
+            for index in 0..material.get_size() {
+                let (squares, stm_is_weak) = material.get_squares(index);
+                if material.get_index(&squares[..count], stm_is_weak) != index {
+                    continue;
+                }
+                let Some(board) =
+                    writer::get_board(&material, &squares[..count], stm_is_weak, magic_bitboards)
+                else {
+                    continue;
+                };
+                assert_eq!(
+                    bundled.probe_wdl(&board, magic_bitboards),
+                    official.probe_wdl(&board, magic_bitboards),
+                    "{name} index {index}"
+                );
+                assert_eq!(
+                    bundled.probe_dtz(&board, magic_bitboards),
+                    official.probe_dtz(&board, magic_bitboards),
+                    "{name} index {index}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn filter_root_moves_keeps_fastest_wins() {
+        let magic_bitboards = get_test_magic_bitboards();
+        let tablebases = get_test_tablebases();
+        for fen in [
+            "k7/8/1K6/8/8/8/8/2Q5 w - - 0 1",
+            "8/8/8/8/8/1kp5/8/K6R w - - 0 1",
+            "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1",
+        ] {
+            let mut board = Board::new();
+            board.set_from_fen(fen, magic_bitboards);
+            let root_moves = get_root_moves(&board, magic_bitboards, &[]);
+            let filtered = tablebases
+                .filter_root_moves(&board, magic_bitboards, &root_moves)
+                .unwrap();
+            let dtz = tablebases.probe_dtz(&board, magic_bitboards).unwrap();
+            assert!(
+                filtered.count > 0 && filtered.count < root_moves.count,
+                "{fen}"
+            );
+            for c_move in &root_moves.moves[..root_moves.count] {
+                let mut child = board;
+                child.make_move(*c_move);
+                let wdl = tablebases.probe_wdl(&child, magic_bitboards).unwrap();
+                let child_dtz = tablebases.probe_dtz(&child, magic_bitboards).unwrap();
+                let zeroing = is_capture(&board, c_move)
+                    || board.get_piece_at(c_move.from_square) & PIECE_MASK == PAWN;
+                let move_dtz = if zeroing || is_mate(&child, magic_bitboards) {
+                    1
+                } else {
+                    1 - child_dtz
+                };
+                let kept = filtered.moves[..filtered.count].contains(c_move);
+                assert_eq!(
+                    kept,
+                    wdl == WDL_LOSS && move_dtz == dtz,
+                    "{fen} {}",
+                    c_move.uci(false)
+                );
+            }
+        }
+    }
+}
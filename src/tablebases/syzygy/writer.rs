@@ -0,0 +1,723 @@
+//! Writes Syzygy tables for the tests in `tests/data/syzygy`.
+//!
+//! The values come from the DTM generator, with distance to zeroing computed by a second
+//! retrograde pass. Symbols are pair-compressed and Huffman coded in the layout the prober
+//! reads, and positions are indexed with the prober's own `get_table_index`, so a written table
+//! is checked against every position it holds before it is kept.
+//!
+//! These are not the official tables. Because the writer shares the prober's indexing, the
+//! bundled tables only show that decoding agrees with the DTM generator; the layout itself is
+//! checked by `bundled_tables_match_official_tables` against official tables when
+//! `SYZYGY_TEST_PATH` points at them.
+use std::{cmp::Reverse, collections::BinaryHeap, fs, path::Path};
+
+use super::{
+    DTZ_MAGIC, DTZ_SUFFIX, ENCODING, Encoding, FLAG_LOSS_PLIES, FLAG_SINGLE_VALUE, FLAG_WIN_PLIES,
+    PairsData, ProbeState, Table, TableData, WDL_DRAW, WDL_LOSS, WDL_MAGIC, WDL_SUFFIX, WDL_WIN,
+    get_table_index, probe_table_data,
+};
+use crate::{
+    board_rep::{
+        board::{BLACK, Board, KING, PAWN, PIECE_MASK, WHITE},
+        magic_bitboards::MagicBitboards,
+        move_gen::is_square_attacked,
+    },
+    engine::search::{get_root_moves, is_capture},
+    evaluation::eval::get_piece_attacks,
+    tablebases::{
+        dtm::{DTM_PIECES, Dtm, DtmTable, DtmTables, Material},
+        retrograde::ensure_table,
+    },
+};
+
+pub const TEST_TABLE_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/syzygy");
+pub const TEST_TABLES: [&str; 6] = ["KQvK", "KRvK", "KBvK", "KNvK", "KPvK", "KRvKP"];
+
+const DTZ_STM: usize = 0;
+const MAX_DTZ: usize = 100;
+const BLOCK_SIZE_LOG: u8 = 11;
+const SPAN_LOG: u8 = 14;
+const MAX_BLOCK_VALUES: usize = 0x10000 - (1 << SPAN_LOG);
+const MAX_SYMBOLS: usize = 4000;
+const MAX_SYMBOL_VALUES: usize = 256;
+const MIN_PAIR_COUNT: u32 = 8;
+const MAX_CODE_LENGTH: usize = 32;
+const LEAF: u16 = 0xFFF;
+
+fn get_wdl(dtm: Dtm) -> i32 {
+    match dtm {
+        Dtm::Draw => WDL_DRAW,
+        Dtm::Win(_) => WDL_WIN,
+        Dtm::Loss(_) => WDL_LOSS,
+    }
+}
+
+/// Builds the position with `squares` in `material` order, or `None` if it is not legal.
+pub(super) fn get_board(
+    material: &Material,
+    squares: &[u8],
+    stm_is_weak: bool,
+    magic_bitboards: &MagicBitboards,
+) -> Option<Board> {
+    let mut occupancy = 0u64;
+    for (square, piece) in squares.iter().zip(&material.pieces) {
+        if occupancy & 1 << square != 0 || (piece.piece == PAWN && matches!(square / 8, 0 | 7)) {
+            return None;
+        }
+        occupancy |= 1 << square;
+    }
+    let mut board = Board::new();
+    for (square, piece) in squares.iter().zip(&material.pieces) {
+        board.set_piece_at(*square, piece.piece | piece.color);
+    }
+    board.stm = if stm_is_weak { WHITE } else { BLACK };
+    let king = if stm_is_weak { squares[0] } else { squares[1] };
+    if is_square_attacked(&board, magic_bitboards, king) {
+        return None;
+    }
+    board.stm = if stm_is_weak { BLACK } else { WHITE };
+    Some(board)
+}
+
+/// Signed distance to zeroing in plies for every index of `table`, 0 for draws and unused
+/// indices. Wins count the zeroing move or mate; losses count to the opponent's.
+fn generate_dtz(
+    table: &DtmTable,
+    tables: &DtmTables,
+    magic_bitboards: &MagicBitboards,
+) -> Vec<i16> {
+    let material = &table.material;
+    let size = material.get_size();
+    let mut dtz = vec![0i16; size];
+    let mut remaining = vec![0u8; size];
+    let mut buckets = vec![Vec::new(); MAX_DTZ + 2];
+    let mut children = Vec::new();
+    for index in 0..size {
+        let (squares, stm_is_weak) = material.get_squares(index);
+        let squares = &squares[..material.pieces.len()];
+        if material.get_index(squares, stm_is_weak) != index {
+            continue;
+        }
+        let Some(board) = get_board(material, squares, stm_is_weak, magic_bitboards) else {
+            continue;
+        };
+        let wdl = get_wdl(Dtm::from_byte(table.values[index]));
+        if wdl == WDL_DRAW {
+            continue;
+        }
+
+        let moves = get_root_moves(&board, magic_bitboards, &[]);
+        let mut zeroing_win = false;
+        children.clear();
+        for c_move in &moves.moves[..moves.count] {
+            let mut child = board;
+            child.make_move(*c_move);
+            let child_dtm = tables.probe(&child).expect("missing DTM table");
+            let zeroing = is_capture(&board, c_move)
+                || board.get_piece_at(c_move.from_square) & PIECE_MASK == PAWN;
+            if zeroing || child_dtm == Dtm::Loss(0) {
+                zeroing_win |= matches!(child_dtm, Dtm::Loss(_));
+                continue;
+            }
+            let mut child_squares = [0u8; DTM_PIECES];
+            child_squares[..squares.len()].copy_from_slice(squares);
+            let moved = squares
+                .iter()
+                .position(|square| *square == c_move.from_square)
+                .unwrap_or(0);
+            child_squares[moved] = c_move.to_square;
+            children.push(material.get_index(&child_squares[..squares.len()], !stm_is_weak));
+        }
+
+        if wdl == WDL_WIN {
+            if zeroing_win {
+                dtz[index] = 1;
+                buckets[1].push(index);
+            }
+        } else {
+            children.sort_unstable();
+            children.dedup();
+            remaining[index] = children.len() as u8;
+            if children.is_empty() {
+                dtz[index] = -1;
+                buckets[1].push(index);
+            }
+        }
+    }
+
+    let mut predecessors = Vec::new();
+    for level in 1..=MAX_DTZ {
+        for index in std::mem::take(&mut buckets[level]) {
+            let winning = dtz[index] > 0;
+            get_predecessors(material, index, magic_bitboards, &mut predecessors);
+            for predecessor in predecessors.iter().copied() {
+                if dtz[predecessor] != 0 {
+                    continue;
+                }
+                let wdl = get_wdl(Dtm::from_byte(table.values[predecessor]));
+                if !winning && wdl == WDL_WIN {
+                    dtz[predecessor] = level as i16 + 1;
+                    buckets[level + 1].push(predecessor);
+                } else if winning && wdl == WDL_LOSS {
+                    remaining[predecessor] -= 1;
+                    if remaining[predecessor] == 0 {
+                        dtz[predecessor] = -(level as i16 + 1);
+                        buckets[level + 1].push(predecessor);
+                    }
+                }
+            }
+        }
+    }
+    assert!(
+        buckets[MAX_DTZ + 1].is_empty(),
+        "{} has wins beyond the 50-move rule",
+        material.name
+    );
+    dtz
+}
+
+/// Indices of the positions with a move into `index` that neither captures nor moves a pawn.
+fn get_predecessors(
+    material: &Material,
+    index: usize,
+    magic_bitboards: &MagicBitboards,
+    predecessors: &mut Vec<usize>,
+) {
+    predecessors.clear();
+    let (squares, stm_is_weak) = material.get_squares(index);
+    let count = material.pieces.len();
+    let mover = if stm_is_weak { WHITE } else { BLACK };
+    let occupancy = squares[..count]
+        .iter()
+        .fold(0u64, |occupancy, square| occupancy | 1 << square);
+    for (i, piece) in material.pieces.iter().enumerate() {
+        if piece.color != mover || piece.piece == PAWN {
+            continue;
+        }
+        let mut from_squares = if piece.piece == KING {
+            magic_bitboards.king_attacks[squares[i] as usize]
+        } else {
+            get_piece_attacks(magic_bitboards, piece.piece, squares[i], occupancy)
+        } & !occupancy;
+        while from_squares != 0 {
+            let from_square = from_squares.trailing_zeros() as u8;
+            from_squares &= from_squares - 1;
+            let mut previous = squares;
+            previous[i] = from_square;
+            if get_board(material, &previous[..count], !stm_is_weak, magic_bitboards).is_some() {
+                predecessors.push(material.get_index(&previous[..count], !stm_is_weak));
+            }
+        }
+    }
+    predecessors.sort_unstable();
+    predecessors.dedup();
+}
+
+/// Calls `f` with every legal position of `material` and its DTM index.
+fn for_each_position(
+    material: &Material,
+    magic_bitboards: &MagicBitboards,
+    mut f: impl FnMut(&Board, usize),
+) {
+    let count = material.pieces.len();
+    let mut squares = [0u8; DTM_PIECES];
+    for combination in 0..1usize << (6 * count) {
+        for (i, square) in squares[..count].iter_mut().enumerate() {
+            *square = (combination >> (6 * i) & 63) as u8;
+        }
+        for stm_is_weak in [false, true] {
+            let squares = &squares[..count];
+            if let Some(board) = get_board(material, squares, stm_is_weak, magic_bitboards) {
+                f(&board, material.get_index(squares, stm_is_weak));
+            }
+        }
+    }
+}
+
+/// Syzygy piece codes in the order the table encodes them: leading pawns first, then the
+/// remaining pieces in `material` order.
+fn get_piece_codes(material: &Material) -> Vec<u8> {
+    let code = |piece: u8, color: u8| piece | if color == BLACK { 8 } else { 0 };
+    let pawns = |color: u8| {
+        material
+            .pieces
+            .iter()
+            .filter(|piece| piece.piece == PAWN && piece.color == color)
+            .count()
+    };
+    let (white_pawns, black_pawns) = (pawns(WHITE), pawns(BLACK));
+    assert!(
+        white_pawns == 0 || black_pawns == 0,
+        "pawns on both sides are not supported"
+    );
+    let lead = if black_pawns == 0 { WHITE } else { BLACK };
+    let is_lead = |piece: u8, color: u8| piece == PAWN && color == lead;
+    let mut codes: Vec<u8> = material
+        .pieces
+        .iter()
+        .filter(|piece| is_lead(piece.piece, piece.color))
+        .map(|piece| code(piece.piece, piece.color))
+        .collect();
+    codes.extend(
+        material
+            .pieces
+            .iter()
+            .filter(|piece| !is_lead(piece.piece, piece.color))
+            .map(|piece| code(piece.piece, piece.color)),
+    );
+    codes
+}
+
+fn get_table_skeleton(table: &Table, codes: &[u8], sides: usize) -> TableData {
+    let encoding = ENCODING.get_or_init(Encoding::new);
+    let files = if table.has_pawns { 4 } else { 1 };
+    let mut data = TableData {
+        bytes: Vec::new(),
+        pairs: vec![PairsData::default(); sides * files],
+        sides,
+        map: 0,
+    };
+    for file in 0..files {
+        for side in 0..sides {
+            let pairs = data.get_mut(side, file);
+            pairs.pieces[..codes.len()].copy_from_slice(codes);
+            table.set_groups(encoding, pairs, &[0, 0xF], file);
+        }
+    }
+    data
+}
+
+fn get_table_size(pairs: &PairsData) -> usize {
+    let groups = pairs
+        .group_len
+        .iter()
+        .position(|len| *len == 0)
+        .unwrap_or(0);
+    pairs.group_idx[groups] as usize
+}
+
+/// Compressed values of one side and file, split into the sections of the file layout.
+struct Section {
+    sizes: Vec<u8>,
+    sparse_index: Vec<u8>,
+    block_lengths: Vec<u8>,
+    blocks: Vec<u8>,
+}
+
+fn compress(values: &[Option<u16>], flags: u8) -> Section {
+    let first = values.iter().flatten().next().copied().unwrap_or(0);
+    let mut previous = first;
+    let values: Vec<u16> = values
+        .iter()
+        .map(|value| {
+            previous = value.unwrap_or(previous);
+            previous
+        })
+        .collect();
+    if values.iter().all(|value| *value == first) {
+        return Section {
+            sizes: vec![flags | FLAG_SINGLE_VALUE, first as u8],
+            sparse_index: Vec::new(),
+            block_lengths: Vec::new(),
+            blocks: Vec::new(),
+        };
+    }
+
+    let (symbols, sequence) = pair_symbols(&values);
+    let lengths = get_code_lengths(&symbols, &sequence);
+    let coded = lengths.iter().filter(|length| **length > 0).count();
+    let mut order: Vec<usize> = (0..symbols.len()).collect();
+    order.sort_by_key(|symbol| (lengths[*symbol] == 0, Reverse(lengths[*symbol]), *symbol));
+    let mut numbers = vec![0; symbols.len()];
+    for (number, symbol) in order.iter().enumerate() {
+        numbers[*symbol] = number;
+    }
+
+    let min_length = lengths
+        .iter()
+        .copied()
+        .filter(|length| *length > 0)
+        .min()
+        .unwrap_or(1);
+    let max_length = lengths.iter().copied().max().unwrap_or(1);
+    assert!(max_length <= MAX_CODE_LENGTH, "Huffman code too long");
+    let base_len = max_length - min_length + 1;
+    let mut counts = vec![0u64; base_len];
+    for length in lengths.iter().filter(|length| **length > 0) {
+        counts[length - min_length] += 1;
+    }
+    let mut lowest = vec![0u64; base_len];
+    let mut base = vec![0u64; base_len];
+    for i in (0..base_len - 1).rev() {
+        lowest[i] = lowest[i + 1] + counts[i + 1];
+        assert!(
+            (base[i + 1] + counts[i + 1]) % 2 == 0,
+            "incomplete Huffman code"
+        );
+        base[i] = (base[i + 1] + counts[i + 1]) / 2;
+    }
+    debug_assert_eq!(lowest[0] + counts[0], coded as u64);
+    let get_code = |symbol: usize| {
+        let i = lengths[symbol] - min_length;
+        (
+            base[i] + numbers[symbol] as u64 - lowest[i],
+            lengths[symbol],
+        )
+    };
+
+    let block_bits = 8usize << BLOCK_SIZE_LOG;
+    let mut blocks: Vec<u8> = Vec::new();
+    let mut block_values = Vec::new();
+    let mut writer = BitWriter::default();
+    let mut values_in_block = 0;
+    for symbol in &sequence {
+        let (code, length) = get_code(*symbol as usize);
+        let symbol_values = symbols[*symbol as usize].1;
+        if writer.bits + length > block_bits || values_in_block + symbol_values > MAX_BLOCK_VALUES {
+            blocks.extend(writer.finish(block_bits / 8));
+            block_values.push(values_in_block);
+            values_in_block = 0;
+        }
+        writer.write(code, length);
+        values_in_block += symbol_values;
+    }
+    blocks.extend(writer.finish(block_bits / 8));
+    block_values.push(values_in_block);
+
+    let mut sizes = vec![flags, BLOCK_SIZE_LOG, SPAN_LOG, 0];
+    sizes.extend_from_slice(&(block_values.len() as u32).to_le_bytes());
+    sizes.extend_from_slice(&[max_length as u8, min_length as u8]);
+    for value in &lowest {
+        sizes.extend_from_slice(&(*value as u16).to_le_bytes());
+    }
+    sizes.extend_from_slice(&(symbols.len() as u16).to_le_bytes());
+    for symbol in &order {
+        let (left, right) = match symbols[*symbol].0 {
+            Symbol::Value(value) => (value, LEAF),
+            Symbol::Pair(left, right) => (
+                numbers[left as usize] as u16,
+                numbers[right as usize] as u16,
+            ),
+        };
+        sizes.extend_from_slice(&[
+            left as u8,
+            (left >> 8) as u8 | (right << 4) as u8,
+            (right >> 4) as u8,
+        ]);
+    }
+    if symbols.len() % 2 == 1 {
+        sizes.push(0);
+    }
+
+    let mut first_values = Vec::with_capacity(block_values.len());
+    let mut total = 0;
+    for count in &block_values {
+        first_values.push(total);
+        total += count;
+    }
+    let span = 1usize << SPAN_LOG;
+    let mut sparse_index = Vec::new();
+    for k in 0..values.len().div_ceil(span) {
+        let idx = k * span + span / 2;
+        let block = first_values.partition_point(|first| *first <= idx) - 1;
+        let offset = idx - first_values[block];
+        assert!(
+            offset <= u16::MAX as usize,
+            "sparse index offset out of range"
+        );
+        sparse_index.extend_from_slice(&(block as u32).to_le_bytes());
+        sparse_index.extend_from_slice(&(offset as u16).to_le_bytes());
+    }
+    let block_lengths = block_values
+        .iter()
+        .flat_map(|count| ((count - 1) as u16).to_le_bytes())
+        .collect();
+    Section {
+        sizes,
+        sparse_index,
+        block_lengths,
+        blocks,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Symbol {
+    Value(u16),
+    Pair(u16, u16),
+}
+
+/// Replaces the most frequent adjacent symbol pairs with new symbols until none repeats often
+/// enough. Returns each symbol with the number of values it expands to, and the sequence.
+fn pair_symbols(values: &[u16]) -> (Vec<(Symbol, usize)>, Vec<u16>) {
+    let mut distinct = values.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    let mut symbols: Vec<(Symbol, usize)> = distinct
+        .iter()
+        .map(|value| (Symbol::Value(*value), 1))
+        .collect();
+    let mut sequence: Vec<u16> = values
+        .iter()
+        .map(|value| distinct.binary_search(value).unwrap_or(0) as u16)
+        .collect();
+
+    let mut counts = vec![0u32; 1 << 24];
+    let mut touched = Vec::new();
+    while symbols.len() < MAX_SYMBOLS {
+        touched.clear();
+        let mut skip = false;
+        for i in 0..sequence.len() - 1 {
+            if skip {
+                skip = false;
+                continue;
+            }
+            let (left, right) = (sequence[i], sequence[i + 1]);
+            let key = (left as usize) << 12 | right as usize;
+            if counts[key] == 0 {
+                touched.push(key);
+            }
+            counts[key] += 1;
+            skip = left == right && sequence.get(i + 2) == Some(&left);
+        }
+        let best = touched
+            .iter()
+            .copied()
+            .filter(|key| symbols[key >> 12].1 + symbols[key & 0xFFF].1 <= MAX_SYMBOL_VALUES)
+            .max_by_key(|key| (counts[*key], Reverse(*key)));
+        let best_count = best.map_or(0, |key| counts[key]);
+        for key in &touched {
+            counts[*key] = 0;
+        }
+        let Some(key) = best.filter(|_| best_count >= MIN_PAIR_COUNT) else {
+            break;
+        };
+
+        let (left, right) = ((key >> 12) as u16, (key & 0xFFF) as u16);
+        let symbol = symbols.len() as u16;
+        symbols.push((
+            Symbol::Pair(left, right),
+            symbols[left as usize].1 + symbols[right as usize].1,
+        ));
+        let mut paired = Vec::with_capacity(sequence.len());
+        let mut i = 0;
+        while i < sequence.len() {
+            if i + 1 < sequence.len() && sequence[i] == left && sequence[i + 1] == right {
+                paired.push(symbol);
+                i += 2;
+            } else {
+                paired.push(sequence[i]);
+                i += 1;
+            }
+        }
+        sequence = paired;
+    }
+    (symbols, sequence)
+}
+
+/// Huffman code length of every symbol in `sequence`, 0 for symbols it does not use.
+fn get_code_lengths(symbols: &[(Symbol, usize)], sequence: &[u16]) -> Vec<usize> {
+    let mut frequencies = vec![0u64; symbols.len()];
+    for symbol in sequence {
+        frequencies[*symbol as usize] += 1;
+    }
+    let mut lengths = vec![0; symbols.len()];
+    let mut heap: BinaryHeap<Reverse<(u64, Vec<usize>)>> = frequencies
+        .iter()
+        .enumerate()
+        .filter(|(_, frequency)| **frequency > 0)
+        .map(|(symbol, frequency)| Reverse((*frequency, vec![symbol])))
+        .collect();
+    if heap.len() == 1 {
+        let Reverse((_, members)) = heap.pop().unwrap();
+        lengths[members[0]] = 1;
+        return lengths;
+    }
+    while heap.len() > 1 {
+        let Reverse((first, mut members)) = heap.pop().unwrap();
+        let Reverse((second, others)) = heap.pop().unwrap();
+        members.extend(others);
+        for symbol in &members {
+            lengths[*symbol] += 1;
+        }
+        heap.push(Reverse((first + second, members)));
+    }
+    lengths
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u64, length: usize) {
+        for bit in (0..length).rev() {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if code >> bit & 1 != 0 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.bits % 8);
+            }
+            self.bits += 1;
+        }
+    }
+
+    fn finish(&mut self, size: usize) -> Vec<u8> {
+        let mut bytes = std::mem::take(&mut self.bytes);
+        bytes.resize(size, 0);
+        self.bits = 0;
+        bytes
+    }
+}
+
+fn write_file(
+    path: &Path,
+    table: &Table,
+    codes: &[u8],
+    sections: &[Section],
+) -> Result<(), String> {
+    let files = if table.has_pawns { 4 } else { 1 };
+    let sides = sections.len() / files;
+    let mut bytes = if table.is_dtz {
+        DTZ_MAGIC.to_vec()
+    } else {
+        WDL_MAGIC.to_vec()
+    };
+    bytes.push((sides == 2) as u8 | (table.has_pawns as u8) << 1);
+    for _ in 0..files {
+        bytes.push(0);
+        bytes.extend(codes.iter().map(|code| code | code << 4));
+    }
+    bytes.resize(bytes.len() + (bytes.len() & 1), 0);
+    for section in sections {
+        bytes.extend_from_slice(&section.sizes);
+    }
+    if table.is_dtz {
+        bytes.resize(bytes.len() + (bytes.len() & 1), 0);
+    }
+    for section in sections {
+        bytes.extend_from_slice(&section.sparse_index);
+    }
+    for section in sections {
+        bytes.extend_from_slice(&section.block_lengths);
+    }
+    for section in sections {
+        bytes.resize((bytes.len() + 0x3F) & !0x3F, 0);
+        bytes.extend_from_slice(&section.blocks);
+    }
+    fs::write(path, bytes).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+/// Writes the WDL and DTZ tables for `name` to `directory` and checks them against the values
+/// they were written from.
+fn write_table(
+    name: &str,
+    tables: &mut DtmTables,
+    directory: &Path,
+    magic_bitboards: &MagicBitboards,
+) -> Result<(), String> {
+    ensure_table(name, tables, None, magic_bitboards)?;
+    let dtm_table = tables
+        .get(name)
+        .ok_or(format!("missing DTM table {name}"))?;
+    let material = &dtm_table.material;
+    let dtz = generate_dtz(dtm_table, tables, magic_bitboards);
+    let codes = get_piece_codes(material);
+    let wdl_table = Table::new(name, None, false).ok_or(format!("invalid table {name}"))?;
+    let dtz_table = Table::new(name, None, true).ok_or(format!("invalid table {name}"))?;
+    assert!(
+        wdl_table.key != wdl_table.key2,
+        "symmetric tables are not supported"
+    );
+    let wdl_data = get_table_skeleton(&wdl_table, &codes, 2);
+    let dtz_data = get_table_skeleton(&dtz_table, &codes, 1);
+    let get_values = |data: &TableData| -> Vec<Vec<Option<u16>>> {
+        data.pairs
+            .iter()
+            .map(|pairs| vec![None; get_table_size(pairs)])
+            .collect()
+    };
+    let mut wdl_values = get_values(&wdl_data);
+    let mut dtz_values = get_values(&dtz_data);
+
+    let set = |values: &mut Vec<Option<u16>>, idx: u64, value: u16| {
+        let entry = &mut values[idx as usize];
+        assert!(
+            entry.is_none_or(|old| old == value),
+            "{name}: conflicting values at index {idx}"
+        );
+        *entry = Some(value);
+    };
+    for_each_position(material, magic_bitboards, |board, index| {
+        let wdl = get_wdl(Dtm::from_byte(dtm_table.values[index]));
+        let (stm, file, idx) = get_table_index(board, &wdl_table, &wdl_data);
+        set(&mut wdl_values[file * 2 + stm], idx, (wdl + 2) as u16);
+        let (stm, file, idx) = get_table_index(board, &dtz_table, &dtz_data);
+        if stm == DTZ_STM && wdl != WDL_DRAW {
+            set(&mut dtz_values[file], idx, dtz[index].unsigned_abs() - 1);
+        }
+    });
+
+    let dtz_flags = DTZ_STM as u8 | FLAG_WIN_PLIES | FLAG_LOSS_PLIES;
+    let wdl_sections: Vec<Section> = wdl_values
+        .iter()
+        .map(|values| compress(values, 0))
+        .collect();
+    let dtz_sections: Vec<Section> = dtz_values
+        .iter()
+        .map(|values| compress(values, dtz_flags))
+        .collect();
+    let wdl_path = directory.join(format!("{name}{WDL_SUFFIX}"));
+    let dtz_path = directory.join(format!("{name}{DTZ_SUFFIX}"));
+    write_file(&wdl_path, &wdl_table, &codes, &wdl_sections)?;
+    write_file(&dtz_path, &dtz_table, &codes, &dtz_sections)?;
+
+    let wdl_table = Table::new(name, Some(wdl_path), false).ok_or("invalid table")?;
+    let dtz_table = Table::new(name, Some(dtz_path), true).ok_or("invalid table")?;
+    let wdl_data = wdl_table
+        .get_data()
+        .ok_or(format!("{name}: unreadable WDL table"))?;
+    let dtz_data = dtz_table
+        .get_data()
+        .ok_or(format!("{name}: unreadable DTZ table"))?;
+    let mut mismatches = 0;
+    for_each_position(material, magic_bitboards, |board, index| {
+        let wdl = get_wdl(Dtm::from_byte(dtm_table.values[index]));
+        let mut state = ProbeState::Ok;
+        if probe_table_data(board, &wdl_table, wdl_data, WDL_DRAW, &mut state) != wdl {
+            mismatches += 1;
+        }
+        if wdl == WDL_DRAW {
+            return;
+        }
+        let value = probe_table_data(board, &dtz_table, dtz_data, wdl, &mut state);
+        if state != ProbeState::ChangeStm && value != dtz[index].abs() as i32 {
+            mismatches += 1;
+        }
+    });
+    if mismatches > 0 {
+        return Err(format!("{name}: {mismatches} positions read back wrongly"));
+    }
+    Ok(())
+}
+
+/// Regenerates the bundled test tables; run with `cargo test --release -- --ignored`.
+#[test]
+#[ignore = "regenerates the bundled tables"]
+fn write_test_tables() {
+    std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(|| {
+            let magic_bitboards = Box::new(MagicBitboards::new());
+            let directory = Path::new(TEST_TABLE_DIRECTORY);
+            fs::create_dir_all(directory).unwrap();
+            let mut tables = DtmTables::default();
+            for name in TEST_TABLES {
+                write_table(name, &mut tables, directory, &magic_bitboards).unwrap();
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -1,8 +1,47 @@
-use std::io::Write;
+use std::{io::Write, path::Path, sync::Arc};
 
-use crate::board_rep::{board::Board, magic_bitboards::MagicBitboards, perft::print_perft};
+use crate::{
+    board_rep::{
+        board::Board,
+        chess960::{
+            CHESS960_POSITIONS, DOUBLE_CHESS960_POSITIONS, get_960_board, get_960_fen,
+            get_double_960_fen, get_double_960_fen_from_index,
+        },
+        magic_bitboards::MagicBitboards,
+        perft::{print_perft, print_perft_960},
+    },
+    book::polyglot::{Book, print_book_moves},
+    engine::{
+        analysis::print_analysis,
+        bench::{DEFAULT_BENCH_DEPTH, print_bench},
+        limits::SearchLimits,
+        search::InfoFormat,
+        searcher::{MAX_THREADS, Searcher},
+        smp_bench::print_smp_bench,
+        strength::{DEFAULT_ELO, MAX_ELO, MIN_ELO},
+        test_suite::print_test_suite,
+        tt::DEFAULT_HASH_MB,
+    },
+    evaluation::{
+        eval::print_eval,
+        nnue::{DEFAULT_EVAL_FILE, Network, set_network},
+    },
+    play::start_play,
+    tablebases::{
+        dtm::{DtmTables, print_dtm_probe, set_dtm_tables},
+        retrograde::{DTM_TABLE_NAMES, ensure_table},
+        syzygy::{Tablebases, set_tablebases},
+    },
+};
 
-pub fn uci_command(command: &str, board: &mut Board, magic_bitboards: &MagicBitboards) -> bool {
+const DEFAULT_TEST_SUITE_MOVE_TIME: u64 = 1000;
+
+pub fn uci_command(
+    command: &str,
+    board: &mut Board,
+    magic_bitboards: &Arc<MagicBitboards>,
+    searcher: &mut Searcher,
+) -> bool {
     let parts: Vec<&str> = command
         .split_whitespace()
         .filter(|s| !s.is_empty())
@@ -11,6 +50,20 @@ pub fn uci_command(command: &str, board: &mut Board, magic_bitboards: &MagicBitb
         "uci" => {
             println!("id name nuvo_chess");
             println!("id author Caden Miller");
+            println!("option name Hash type spin default {DEFAULT_HASH_MB} min 1 max 65536");
+            println!("option name Threads type spin default 1 min 1 max {MAX_THREADS}");
+            println!("option name UCI_Chess960 type check default false");
+            println!("option name EvalFile type string default {DEFAULT_EVAL_FILE}");
+            println!("option name SyzygyPath type string default <empty>");
+            println!("option name DtmPath type string default <empty>");
+            println!("option name OwnBook type check default false");
+            println!("option name BookFile type string default <empty>");
+            println!("option name BookBestMove type check default false");
+            println!("option name UCI_LimitStrength type check default false");
+            println!(
+                "option name UCI_Elo type spin default {DEFAULT_ELO} min {MIN_ELO} max {MAX_ELO}"
+            );
+            println!("option name StrengthSeed type spin default 0 min 0 max 2147483647");
             println!("uciok");
             true
         }
@@ -19,31 +72,126 @@ pub fn uci_command(command: &str, board: &mut Board, magic_bitboards: &MagicBitb
             true
         }
         "position" => {
-            if parts.get(1).unwrap_or(&"") == &"startpos" {
-                let mut fen =
-                    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string();
-                let moves = parts.get(3..).unwrap_or(&[]).join(" ");
-                if !moves.is_empty() {
-                    fen = format!("{fen} moves {moves}");
+            let moves_index = parts.iter().position(|part| *part == "moves");
+            let fen = match parts.get(1) {
+                Some(&"startpos") => {
+                    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string()
                 }
-                board.set_from_fen(fen.as_str(), magic_bitboards);
-            } else if parts.get(1).unwrap_or(&"") == &"fen" {
-                let fen = parts.get(2..).unwrap_or(&[""]).join(" ");
-                board.set_from_fen(fen.as_str(), magic_bitboards);
+                Some(&"fen") => parts
+                    .get(2..moves_index.unwrap_or(parts.len()))
+                    .unwrap_or(&[])
+                    .join(" "),
+                _ => return true,
+            };
+            board.set_from_fen(fen.as_str(), magic_bitboards);
+            let mut history = Vec::new();
+            for move_str in moves_index.map_or(&[][..], |index| &parts[index + 1..]) {
+                history.push(board.hash);
+                board.move_from_str(magic_bitboards, move_str);
             }
+            searcher.set_history(history);
+            true
+        }
+        "setoption" => {
+            set_option(&parts, board, searcher);
+            true
+        }
+        "ucinewgame" => {
+            searcher.clear_hash();
             true
         }
-        "quit" => false,
+        "go" => {
+            let mut limits = SearchLimits::from_go(parts.get(1..).unwrap_or(&[]));
+            searcher.apply_strength_options(&mut limits);
+            if limits.search_moves.is_empty()
+                && limits.mate.is_none()
+                && !limits.infinite
+                && let Some(book_move) = searcher.get_book_move(board, magic_bitboards)
+            {
+                searcher.stop();
+                println!("bestmove {}", book_move.uci(board.chess960));
+                return true;
+            }
+            let chess960 = board.chess960;
+            searcher.start(
+                board,
+                magic_bitboards,
+                limits,
+                InfoFormat::Uci,
+                move |best_move| match best_move {
+                    Some(best_move) => println!("bestmove {}", best_move.uci(chess960)),
+                    None => println!("bestmove 0000"),
+                },
+            );
+            true
+        }
+        "stop" => {
+            searcher.stop();
+            true
+        }
+        "quit" => {
+            searcher.stop();
+            false
+        }
         "perft" => {
             let depth: usize = parts.get(1).unwrap_or(&"1").parse().unwrap_or(1);
             print_perft(board, magic_bitboards, depth, false);
             true
         }
+        "perft960" => {
+            print_perft_960(magic_bitboards);
+            true
+        }
+        "tbgen" => {
+            searcher.stop();
+            generate_dtm_tables(
+                parts.get(1).copied(),
+                parts.get(2..).unwrap_or(&[]),
+                magic_bitboards,
+            );
+            true
+        }
+        "dtm" => {
+            print_dtm_probe(board);
+            true
+        }
+        "book" => {
+            print_book_moves(board, magic_bitboards, searcher.get_book());
+            true
+        }
         "perftfull" => {
             let depth: usize = parts.get(1).unwrap_or(&"1").parse().unwrap_or(1);
             print_perft(board, magic_bitboards, depth, true);
             true
         }
+        "startpos960" => {
+            let indices: Vec<usize> = parts
+                .iter()
+                .skip(1)
+                .filter_map(|index| index.parse().ok())
+                .collect();
+            let fen = match (parts.get(1), indices.as_slice()) {
+                (Some(&"double"), [index]) if *index < DOUBLE_CHESS960_POSITIONS => {
+                    get_double_960_fen_from_index(*index)
+                }
+                (_, []) => get_960_fen(518),
+                (_, [index]) if *index < CHESS960_POSITIONS => get_960_fen(*index),
+                (_, [white_index, black_index])
+                    if *white_index < CHESS960_POSITIONS && *black_index < CHESS960_POSITIONS =>
+                {
+                    get_double_960_fen(*white_index, *black_index)
+                }
+                _ => {
+                    println!(
+                        "info string usage: startpos960 <index below {CHESS960_POSITIONS}> [black index] | startpos960 double <index below {DOUBLE_CHESS960_POSITIONS}>"
+                    );
+                    return true;
+                }
+            };
+            *board = get_960_board(&fen, magic_bitboards);
+            println!("{fen}");
+            true
+        }
         "print" => {
             board.print(false);
             true
@@ -52,11 +200,57 @@ pub fn uci_command(command: &str, board: &mut Board, magic_bitboards: &MagicBitb
             board.print(true);
             true
         }
+        "eval" => {
+            print_eval(board, magic_bitboards);
+            true
+        }
+        "analyze" => {
+            searcher.stop();
+            print_analysis(board, magic_bitboards, parts.get(1..).unwrap_or(&[]));
+            true
+        }
         "move" => {
             let move_str = parts.get(1).unwrap_or(&"");
             board.move_from_str(magic_bitboards, move_str);
             true
         }
+        "bench" => {
+            let depth: usize = parts
+                .get(1)
+                .unwrap_or(&"")
+                .parse()
+                .unwrap_or(DEFAULT_BENCH_DEPTH);
+            searcher.stop();
+            print_bench(magic_bitboards, depth);
+            true
+        }
+        "smpbench" => {
+            let max_threads: usize = parts.get(1).unwrap_or(&"16").parse().unwrap_or(16);
+            let move_time: u64 = parts.get(2).unwrap_or(&"5000").parse().unwrap_or(5000);
+            searcher.stop();
+            print_smp_bench(magic_bitboards, max_threads, move_time);
+            true
+        }
+        "testsuite" => {
+            let Some(path) = parts.get(1) else {
+                println!(
+                    "info string usage: testsuite <file.epd> [movetime <ms> | depth <n> | nodes <n>]"
+                );
+                return true;
+            };
+            let mut limits = SearchLimits::from_go(parts.get(2..).unwrap_or(&[]));
+            if parts.len() <= 2 {
+                limits.move_time = Some(DEFAULT_TEST_SUITE_MOVE_TIME);
+            }
+            searcher.stop();
+            print_test_suite(magic_bitboards, path, &limits);
+            true
+        }
+        "play" => {
+            searcher.stop();
+            start_play(parts.get(1..).unwrap_or(&[]), board, magic_bitboards);
+            true
+        }
         "genmagics" => {
             let mut magic_bitboards = MagicBitboards::new();
             magic_bitboards.generate_magic_numbers();
@@ -70,3 +264,135 @@ pub fn uci_command(command: &str, board: &mut Board, magic_bitboards: &MagicBitb
         _ => true,
     }
 }
+
+fn set_option(parts: &[&str], board: &mut Board, searcher: &mut Searcher) {
+    let value_index = parts.iter().position(|part| *part == "value");
+    let name = parts
+        .get(2..value_index.unwrap_or(parts.len()))
+        .unwrap_or(&[])
+        .join(" ");
+    let value = value_index
+        .and_then(|index| parts.get(index + 1..))
+        .unwrap_or(&[])
+        .join(" ");
+    match name.to_lowercase().as_str() {
+        "hash" => searcher.set_hash_size(value.parse().unwrap_or(DEFAULT_HASH_MB)),
+        "threads" => searcher.set_threads(value.parse().unwrap_or(1)),
+        "uci_chess960" => board.chess960 = value == "true",
+        "evalfile" => load_eval_file(&value),
+        "syzygypath" => load_syzygy_path(&value),
+        "dtmpath" => load_dtm_path(&value),
+        "ownbook" => searcher.set_own_book(value == "true"),
+        "bookfile" => load_book_file(&value, searcher),
+        "bookbestmove" => searcher.set_best_book_move(value == "true"),
+        "uci_limitstrength" => searcher.set_limit_strength(value == "true"),
+        "uci_elo" => searcher.set_elo(value.parse().unwrap_or(DEFAULT_ELO)),
+        "strengthseed" => searcher.set_strength_seed(value.parse().unwrap_or(0)),
+        _ => println!("info string unknown option {name}"),
+    }
+}
+
+pub fn load_eval_file(path: &str) {
+    if path.is_empty() || path == "<empty>" {
+        set_network(None);
+        println!("info string NNUE disabled, using handcrafted evaluation");
+        return;
+    }
+    match Network::load(path) {
+        Ok(network) => {
+            set_network(Some(network));
+            println!("info string NNUE network loaded from {path}");
+        }
+        Err(error) => {
+            set_network(None);
+            println!("info string {error}, using handcrafted evaluation");
+        }
+    }
+}
+
+pub fn load_syzygy_path(path: &str) {
+    if path.is_empty() || path == "<empty>" {
+        set_tablebases(None);
+        return;
+    }
+    let tablebases = Tablebases::new(path);
+    if tablebases.get_table_count() == 0 {
+        set_tablebases(None);
+        println!("info string no tablebases found in {path}");
+        return;
+    }
+    println!(
+        "info string found {} tablebases up to {} pieces",
+        tablebases.get_table_count(),
+        tablebases.max_pieces
+    );
+    set_tablebases(Some(tablebases));
+}
+
+pub fn load_dtm_path(path: &str) {
+    if path.is_empty() || path == "<empty>" {
+        set_dtm_tables(None);
+        return;
+    }
+    let tables = DtmTables::load(path);
+    if tables.get_table_count() == 0 {
+        set_dtm_tables(None);
+        println!("info string no DTM tables found in {path}");
+        return;
+    }
+    println!("info string loaded {} DTM tables", tables.get_table_count());
+    set_dtm_tables(Some(tables));
+}
+
+pub fn load_book_file(path: &str, searcher: &mut Searcher) {
+    if path.is_empty() || path == "<empty>" {
+        searcher.set_book(None);
+        return;
+    }
+    match Book::load(path) {
+        Ok(book) => {
+            println!(
+                "info string loaded {} book entries from {path}",
+                book.get_entry_count()
+            );
+            searcher.set_book(Some(book));
+        }
+        Err(error) => {
+            searcher.set_book(None);
+            println!("info string {error}");
+        }
+    }
+}
+
+fn generate_dtm_tables(directory: Option<&str>, names: &[&str], magic_bitboards: &MagicBitboards) {
+    let Some(directory) = directory else {
+        println!("info string usage: tbgen <directory> [tables...]");
+        return;
+    };
+    if let Err(error) = std::fs::create_dir_all(directory) {
+        println!("info string {directory}: {error}");
+        return;
+    }
+    let mut tables = DtmTables::load(directory);
+    let names = if names.is_empty() {
+        &DTM_TABLE_NAMES[..]
+    } else {
+        names
+    };
+    for name in names {
+        if let Err(error) = ensure_table(
+            name,
+            &mut tables,
+            Some(Path::new(directory)),
+            magic_bitboards,
+        ) {
+            println!("info string {error}");
+            return;
+        }
+    }
+    println!(
+        "info string {} DTM tables in {directory}",
+        tables.get_table_count()
+    );
+    set_dtm_tables(Some(tables));
+}
@@ -0,0 +1,407 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    board_rep::{
+        board::{Board, WHITE},
+        c_move::CMove,
+        magic_bitboards::MagicBitboards,
+        move_gen::generate_moves,
+    },
+    engine::{
+        limits::{MAX_DEPTH, SearchLimits},
+        search::{InfoFormat, get_root_moves},
+        searcher::{MAX_THREADS, Searcher},
+    },
+    uci::load_syzygy_path,
+};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Default)]
+struct EngineMove {
+    discard: bool,
+    c_move: Option<CMove>,
+}
+
+pub struct XBoard {
+    board: Board,
+    history: Vec<Board>,
+    force: bool,
+    post: bool,
+    depth: usize,
+    move_time: Option<u64>,
+    moves_per_session: u64,
+    base_time: u64,
+    increment: u64,
+    time: Option<u64>,
+    opponent_time: Option<u64>,
+    engine_move: Arc<Mutex<EngineMove>>,
+}
+
+impl XBoard {
+    pub fn new(magic_bitboards: &MagicBitboards) -> Self {
+        let mut board = Board::new();
+        board.set_from_fen(START_FEN, magic_bitboards);
+        XBoard {
+            board,
+            history: Vec::new(),
+            force: false,
+            post: false,
+            depth: MAX_DEPTH,
+            move_time: None,
+            moves_per_session: 0,
+            base_time: 0,
+            increment: 0,
+            time: None,
+            opponent_time: None,
+            engine_move: Arc::new(Mutex::new(EngineMove::default())),
+        }
+    }
+
+    pub fn command(
+        &mut self,
+        command: &str,
+        magic_bitboards: &Arc<MagicBitboards>,
+        searcher: &mut Searcher,
+    ) -> bool {
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        let value = parts.get(1).unwrap_or(&"");
+        self.apply_engine_move();
+
+        match *parts.first().unwrap_or(&"") {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "white" | "black" => {}
+            "protover" => {
+                println!(
+                    "feature myname=\"nuvo_chess\" ping=1 setboard=1 usermove=1 playother=1 \
+                     san=0 colors=0 analyze=0 sigint=0 sigterm=0 memory=1 smp=1 egt=\"syzygy\" done=1"
+                );
+            }
+            "ping" => println!("pong {value}"),
+            "new" => {
+                self.cancel_search(searcher);
+                self.board.set_from_fen(START_FEN, magic_bitboards);
+                self.history.clear();
+                self.force = false;
+                self.depth = MAX_DEPTH;
+                self.move_time = None;
+                searcher.clear_hash();
+            }
+            "setboard" => {
+                self.cancel_search(searcher);
+                let fen = parts.get(1..).unwrap_or(&[]).join(" ");
+                self.board.set_from_fen(&fen, magic_bitboards);
+                self.history.clear();
+            }
+            "force" => {
+                self.cancel_search(searcher);
+                self.force = true;
+            }
+            "result" => {
+                self.cancel_search(searcher);
+                self.force = true;
+            }
+            "go" => {
+                self.force = false;
+                self.think(magic_bitboards, searcher);
+            }
+            "playother" => {
+                self.force = false;
+            }
+            "usermove" => {
+                let Some(c_move) = self.find_move(magic_bitboards, value) else {
+                    println!("Illegal move: {value}");
+                    return true;
+                };
+                self.history.push(self.board);
+                self.board.make_move(c_move);
+                if !self.force {
+                    self.think(magic_bitboards, searcher);
+                }
+            }
+            "?" => searcher.stop(),
+            "undo" => {
+                self.cancel_search(searcher);
+                self.undo(1);
+            }
+            "remove" => {
+                self.cancel_search(searcher);
+                self.undo(2);
+            }
+            "level" => {
+                self.moves_per_session = value.parse().unwrap_or(0);
+                self.base_time = parse_base_time(parts.get(2).unwrap_or(&"0"));
+                self.increment =
+                    (parts.get(3).unwrap_or(&"0").parse::<f64>().unwrap_or(0.0) * 1000.0) as u64;
+                self.move_time = None;
+            }
+            "st" => {
+                self.move_time = value.parse::<u64>().ok().map(|seconds| seconds * 1000);
+            }
+            "sd" => {
+                self.depth = value.parse().unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+            }
+            "time" => self.time = value.parse::<u64>().ok().map(|centis| centis * 10),
+            "otim" => self.opponent_time = value.parse::<u64>().ok().map(|centis| centis * 10),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "memory" => {
+                if let Ok(size_mb) = value.parse() {
+                    searcher.set_hash_size(size_mb);
+                }
+            }
+            "cores" => {
+                if let Ok(threads) = value.parse::<usize>() {
+                    searcher.set_threads(threads.min(MAX_THREADS));
+                }
+            }
+            "egtpath" => {
+                if *value == "syzygy" {
+                    load_syzygy_path(&parts.get(2..).unwrap_or(&[]).join(" "));
+                }
+            }
+            "quit" => {
+                self.cancel_search(searcher);
+                return false;
+            }
+            _ => println!("Error (unknown command): {}", command.trim()),
+        }
+        true
+    }
+
+    pub fn wait(&mut self, searcher: &mut Searcher) {
+        searcher.wait();
+        self.apply_engine_move();
+    }
+
+    fn think(&mut self, magic_bitboards: &Arc<MagicBitboards>, searcher: &mut Searcher) {
+        let mut hashes: Vec<u64> = self.history.iter().map(|board| board.hash).collect();
+        if let Some(result) = get_game_result(&self.board, &hashes, magic_bitboards) {
+            println!("{result}");
+            return;
+        }
+
+        if let Some(book_move) = searcher.get_book_move(&self.board, magic_bitboards) {
+            println!("move {}", book_move.uci(self.board.chess960));
+            self.history.push(self.board);
+            hashes.push(self.board.hash);
+            self.board.make_move(book_move);
+            if let Some(result) = get_game_result(&self.board, &hashes, magic_bitboards) {
+                println!("{result}");
+            }
+            return;
+        }
+
+        let mut limits = SearchLimits::new();
+        limits.depth = self.depth;
+        if let Some(move_time) = self.move_time {
+            limits.move_time = Some(move_time);
+        } else if self.time.is_some() || self.base_time > 0 {
+            let time = self.time.unwrap_or(self.base_time);
+            let opponent_time = self.opponent_time.unwrap_or(time);
+            let (w_time, b_time) = if self.board.stm == WHITE {
+                (time, opponent_time)
+            } else {
+                (opponent_time, time)
+            };
+            limits.w_time = Some(w_time);
+            limits.b_time = Some(b_time);
+            limits.w_inc = self.increment;
+            limits.b_inc = self.increment;
+            if self.moves_per_session > 0 {
+                let played = (self.board.fullmove_number as u64 - 1) % self.moves_per_session;
+                limits.moves_to_go = Some(self.moves_per_session - played);
+            }
+        }
+
+        *self.engine_move.lock().unwrap() = EngineMove::default();
+        let engine_move = Arc::clone(&self.engine_move);
+        let board = self.board;
+        let magic = Arc::clone(magic_bitboards);
+        let info_format = if self.post {
+            InfoFormat::Xboard
+        } else {
+            InfoFormat::None
+        };
+        searcher.set_history(hashes.clone());
+        hashes.push(board.hash);
+        searcher.start(
+            &self.board,
+            magic_bitboards,
+            limits,
+            info_format,
+            move |best_move| {
+                let mut engine_move = engine_move.lock().unwrap();
+                let Some(best_move) = best_move else {
+                    return;
+                };
+                if engine_move.discard {
+                    return;
+                }
+                engine_move.c_move = Some(best_move);
+                println!("move {}", best_move.uci(board.chess960));
+
+                let mut new_board = board;
+                new_board.make_move(best_move);
+                if let Some(result) = get_game_result(&new_board, &hashes, &magic) {
+                    println!("{result}");
+                }
+            },
+        );
+    }
+
+    fn cancel_search(&mut self, searcher: &mut Searcher) {
+        self.engine_move.lock().unwrap().discard = true;
+        searcher.stop();
+        self.apply_engine_move();
+    }
+
+    fn apply_engine_move(&mut self) {
+        if let Some(c_move) = self.engine_move.lock().unwrap().c_move.take() {
+            self.history.push(self.board);
+            self.board.make_move(c_move);
+        }
+    }
+
+    fn undo(&mut self, plies: usize) {
+        for _ in 0..plies {
+            if let Some(board) = self.history.pop() {
+                self.board = board;
+            }
+        }
+    }
+
+    fn find_move(&self, magic_bitboards: &MagicBitboards, move_str: &str) -> Option<CMove> {
+        let root_moves = get_root_moves(&self.board, magic_bitboards, &[]);
+        root_moves.moves[..root_moves.count]
+            .iter()
+            .find(|c_move| c_move.uci(self.board.chess960).to_string() == move_str)
+            .copied()
+    }
+}
+
+fn parse_base_time(base: &str) -> u64 {
+    let mut parts = base.split(':');
+    let minutes = parts.next().unwrap_or("0").parse::<u64>().unwrap_or(0);
+    let seconds = parts.next().unwrap_or("0").parse::<u64>().unwrap_or(0);
+    (minutes * 60 + seconds) * 1000
+}
+
+fn get_game_result(
+    board: &Board,
+    hashes: &[u64],
+    magic_bitboards: &MagicBitboards,
+) -> Option<&'static str> {
+    let root_moves = get_root_moves(board, magic_bitboards, &[]);
+    if root_moves.count > 0 {
+        if board.halfmove_clock >= 100 {
+            return Some("1/2-1/2 {Fifty move rule}");
+        }
+        let repetitions = hashes
+            .iter()
+            .rev()
+            .take(board.halfmove_clock as usize)
+            .filter(|hash| **hash == board.hash)
+            .count();
+        if repetitions >= 2 {
+            return Some("1/2-1/2 {3-fold repetition}");
+        }
+        let heavy = board.w_pawns | board.b_pawns | board.w_rooks | board.b_rooks;
+        let minors = board.w_knights | board.b_knights | board.w_bishops | board.b_bishops;
+        if heavy | board.w_queens | board.b_queens == 0 && minors.count_ones() <= 1 {
+            return Some("1/2-1/2 {Insufficient material}");
+        }
+        return None;
+    }
+    let in_check = generate_moves(board, magic_bitboards).check_count > 0;
+    Some(match (in_check, board.stm == WHITE) {
+        (true, true) => "0-1 {Black mates}",
+        (true, false) => "1-0 {White mates}",
+        (false, _) => "1/2-1/2 {Stalemate}",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board_rep::magic_bitboards::get_test_magic_bitboards, engine::searcher::SEARCH_STACK_SIZE,
+    };
+
+    fn get_result(fen: &str, moves: &[&str]) -> Option<&'static str> {
+        let magic_bitboards = get_test_magic_bitboards();
+        let mut board = Board::new();
+        board.set_from_fen(fen, magic_bitboards);
+        let mut hashes = Vec::new();
+        for move_str in moves {
+            hashes.push(board.hash);
+            board.move_from_str(magic_bitboards, move_str);
+        }
+        get_game_result(&board, &hashes, magic_bitboards)
+    }
+
+    #[test]
+    fn detects_game_results() {
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        assert_eq!(get_result(START_FEN, &shuffle), None);
+        assert_eq!(
+            get_result(START_FEN, &[shuffle, shuffle].concat()),
+            Some("1/2-1/2 {3-fold repetition}")
+        );
+        assert_eq!(
+            get_result("8/8/4k3/8/8/4K3/4R3/8 w - - 100 80", &[]),
+            Some("1/2-1/2 {Fifty move rule}")
+        );
+        assert_eq!(get_result("8/8/4k3/8/8/4K3/4R3/8 w - - 99 80", &[]), None);
+        assert_eq!(
+            get_result("8/8/4k3/8/8/4K3/4N3/8 w - - 0 1", &[]),
+            Some("1/2-1/2 {Insufficient material}")
+        );
+        assert_eq!(
+            get_result("8/8/4k3/8/8/4K3/4B3/8 b - - 0 1", &[]),
+            Some("1/2-1/2 {Insufficient material}")
+        );
+        assert_eq!(get_result("8/8/4k3/8/8/4K3/3NN3/8 w - - 0 1", &[]), None);
+        assert_eq!(
+            get_result(START_FEN, &["f2f3", "e7e5", "g2g4", "d8h4"]),
+            Some("0-1 {Black mates}")
+        );
+        assert_eq!(
+            get_result("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", &[]),
+            Some("1/2-1/2 {Stalemate}")
+        );
+    }
+
+    #[test]
+    fn colour_commands_are_accepted_and_moves_build_the_history() {
+        std::thread::Builder::new()
+            .stack_size(SEARCH_STACK_SIZE)
+            .spawn(|| {
+                let magic_bitboards = Arc::new(MagicBitboards::new());
+                let mut searcher = Searcher::new();
+                let mut xboard = XBoard::new(&magic_bitboards);
+                for command in ["new", "force", "white", "black"] {
+                    assert!(xboard.command(command, &magic_bitboards, &mut searcher));
+                }
+                assert!(xboard.force);
+                let start_hash = xboard.board.hash;
+                for _ in 0..2 {
+                    for move_str in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                        let command = format!("usermove {move_str}");
+                        assert!(xboard.command(&command, &magic_bitboards, &mut searcher));
+                    }
+                }
+                assert_eq!(xboard.board.hash, start_hash);
+                assert_eq!(xboard.history.len(), 8);
+                let hashes: Vec<u64> = xboard.history.iter().map(|board| board.hash).collect();
+                assert_eq!(
+                    get_game_result(&xboard.board, &hashes, &magic_bitboards),
+                    Some("1/2-1/2 {3-fold repetition}")
+                );
+                assert!(!xboard.command("quit", &magic_bitboards, &mut searcher));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}